@@ -1,10 +1,10 @@
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
-use quote_common::QuoteError;
+use quote_common::{QuoteEncoding, QuoteError, parse_subscription_key, subscription_key};
 
 /// Command line arguments for the quote client.
 #[derive(Debug, Parser)]
@@ -15,12 +15,40 @@ pub struct CliArgs {
     pub server_addr: SocketAddr,
 
     /// Local UDP port to bind for receiving quotes
-    #[arg(long = "udp-port")]
-    pub udp_port: u16,
+    #[arg(long = "udp-port", required_unless_present_any = ["list", "tcp", "multicast_group", "snapshot"])]
+    pub udp_port: Option<u16>,
 
     /// Path to file containing ticker symbols (one per line)
-    #[arg(long = "tickers-file")]
-    pub tickers_file: PathBuf,
+    #[arg(long = "tickers-file", required_unless_present = "list")]
+    pub tickers_file: Option<PathBuf>,
+
+    /// Encoding the server should use for UDP quotes (json or binary)
+    #[arg(long = "encoding", default_value_t = QuoteEncoding::Json)]
+    pub encoding: QuoteEncoding,
+
+    /// Receive quotes over the TCP control connection instead of UDP
+    #[arg(long = "tcp", conflicts_with_all = ["udp_port", "encoding"])]
+    pub tcp: bool,
+
+    /// Join a server multicast group (e.g., 239.255.0.1:30001) instead of sending STREAM
+    #[arg(long = "multicast-group", conflicts_with_all = ["udp_port", "tcp"])]
+    pub multicast_group: Option<SocketAddrV4>,
+
+    /// Local interface address used to join the multicast group
+    #[arg(
+        long = "multicast-interface",
+        default_value_t = Ipv4Addr::UNSPECIFIED,
+        requires = "multicast_group"
+    )]
+    pub multicast_interface: Ipv4Addr,
+
+    /// List the server's tickers (optionally only those starting with PREFIX) and exit
+    #[arg(long = "list", value_name = "PREFIX", conflicts_with_all = ["udp_port", "tickers_file", "tcp", "multicast_group"])]
+    pub list: Option<Option<String>>,
+
+    /// Print the latest quote for each ticker in the tickers file and exit
+    #[arg(long = "snapshot", conflicts_with_all = ["udp_port", "tcp", "multicast_group", "list"])]
+    pub snapshot: bool,
 }
 
 /// Parse command line arguments.
@@ -28,7 +56,9 @@ pub fn parse() -> CliArgs {
     CliArgs::parse()
 }
 
-/// Load ticker symbols from the provided file, normalizing to uppercase.
+/// Load subscription keys from the provided file, normalizing tickers to uppercase.
+/// Lines may name a bar interval (`AAPL@1m`) to receive bars instead of quotes, or
+/// `AAPL@book` to receive order-book depth.
 pub fn load_tickers(path: &Path) -> Result<Vec<String>, QuoteError> {
     let contents = fs::read_to_string(path).map_err(|err| {
         quote_common::quote_error!(
@@ -44,7 +74,16 @@ pub fn load_tickers(path: &Path) -> Result<Vec<String>, QuoteError> {
         if ticker.is_empty() {
             continue;
         }
-        tickers.push(ticker.to_uppercase());
+        let (ticker, feed) = parse_subscription_key(ticker).map_err(|err| {
+            quote_common::quote_error!(
+                ConfigError,
+                "invalid entry '{}' in ticker file '{}': {}",
+                ticker,
+                path.display(),
+                err
+            )
+        })?;
+        tickers.push(subscription_key(&ticker, feed));
     }
 
     if tickers.is_empty() {
@@ -61,6 +100,7 @@ pub fn load_tickers(path: &Path) -> Result<Vec<String>, QuoteError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::CommandFactory;
     use std::io::Write;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -74,6 +114,141 @@ mod tests {
         path
     }
 
+    #[test]
+    fn test_cli_definition_is_valid() {
+        CliArgs::command().debug_assert();
+    }
+
+    #[test]
+    fn test_list_mode_does_not_require_stream_arguments() {
+        let args =
+            CliArgs::try_parse_from(["quote_client", "--server-addr", "127.0.0.1:8080", "--list"])
+                .expect("parse list mode");
+        assert_eq!(args.list, Some(None));
+
+        let args = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--list",
+            "AA",
+        ])
+        .expect("parse list mode with prefix");
+        assert_eq!(args.list, Some(Some("AA".to_string())));
+    }
+
+    #[test]
+    fn test_encoding_argument() {
+        let args = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--udp-port",
+            "34254",
+            "--tickers-file",
+            "tickers.txt",
+            "--encoding",
+            "binary",
+        ])
+        .expect("parse encoding");
+        assert_eq!(args.encoding, QuoteEncoding::Binary);
+    }
+
+    #[test]
+    fn test_stream_mode_requires_udp_port() {
+        let result = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_tcp_mode_replaces_udp_port() {
+        let args = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+            "--tcp",
+        ])
+        .expect("parse tcp mode");
+        assert!(args.tcp);
+        assert_eq!(args.udp_port, None);
+
+        let result = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+            "--tcp",
+            "--encoding",
+            "binary",
+        ]);
+        assert!(result.is_err(), "binary quotes cannot be framed over TCP");
+    }
+
+    #[test]
+    fn test_multicast_mode_replaces_udp_port() {
+        let args = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+            "--multicast-group",
+            "239.255.0.1:30001",
+            "--multicast-interface",
+            "127.0.0.1",
+        ])
+        .expect("parse multicast mode");
+        assert_eq!(
+            args.multicast_group,
+            Some("239.255.0.1:30001".parse().unwrap())
+        );
+        assert_eq!(args.multicast_interface, Ipv4Addr::LOCALHOST);
+
+        let result = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+            "--multicast-group",
+            "239.255.0.1:30001",
+            "--udp-port",
+            "34254",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_snapshot_mode_requires_only_tickers_file() {
+        let args = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--tickers-file",
+            "tickers.txt",
+            "--snapshot",
+        ])
+        .expect("parse snapshot mode");
+        assert!(args.snapshot);
+
+        let result = CliArgs::try_parse_from([
+            "quote_client",
+            "--server-addr",
+            "127.0.0.1:8080",
+            "--snapshot",
+        ]);
+        assert!(result.is_err(), "snapshot needs tickers");
+    }
+
     #[test]
     fn test_load_tickers_filters_and_uppercases() {
         let path = unique_temp_path();
@@ -82,10 +257,22 @@ mod tests {
         file.write_all(b"  msft \n").unwrap();
         file.write_all(b"\n").unwrap();
         file.write_all(b"TsLa\n").unwrap();
+        file.write_all(b"nvda@1M\n").unwrap();
         drop(file);
 
         let tickers = load_tickers(&path).expect("load tickers");
-        assert_eq!(tickers, vec!["AAPL", "MSFT", "TSLA"]);
+        assert_eq!(tickers, vec!["AAPL", "MSFT", "TSLA", "NVDA@1m"]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_tickers_rejects_invalid_bar_interval() {
+        let path = unique_temp_path();
+        fs::write(&path, "AAPL@soon\n").expect("write temp file");
+
+        let err = load_tickers(&path).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ConfigError { .. }));
 
         fs::remove_file(path).unwrap();
     }
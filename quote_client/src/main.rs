@@ -1,18 +1,23 @@
-use std::net::UdpSocket;
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use log::info;
 
-use quote_common::QuoteError;
+use quote_common::{QuoteError, format_price};
 
 mod cli;
+mod order_book;
+mod sequence;
 mod tcp_client;
+mod tcp_receiver;
 mod udp_receiver;
 
 use cli::{load_tickers, parse};
-use tcp_client::send_stream_command;
+use tcp_client::{open_tcp_stream, request_snapshot, request_ticker_list, send_stream_command};
+use tcp_receiver::spawn_tcp_listener;
 use udp_receiver::{spawn_listener, spawn_ping_thread};
 
 const CLIENT_SHUTDOWN_GRACE_MS: u64 = 200;
@@ -29,9 +34,38 @@ fn main() {
 fn run() -> Result<(), QuoteError> {
     let args = parse();
 
-    let tickers = load_tickers(&args.tickers_file)?;
+    if let Some(prefix) = &args.list {
+        return list_tickers(args.server_addr, prefix.as_deref());
+    }
+
+    // clap enforces both arguments outside of list mode; --tcp replaces --udp-port.
+    let Some(tickers_file) = &args.tickers_file else {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "--tickers-file is required to stream quotes"
+        ));
+    };
+
+    let tickers = load_tickers(tickers_file)?;
+
+    if args.snapshot {
+        return print_snapshot(args.server_addr, &tickers);
+    }
+    if args.tcp {
+        return stream_over_tcp(args.server_addr, &tickers);
+    }
+    if let Some(group) = args.multicast_group {
+        return join_multicast_group(group, args.multicast_interface, tickers);
+    }
+
+    let Some(udp_port) = args.udp_port else {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "--udp-port, --tcp or --multicast-group is required to stream quotes"
+        ));
+    };
 
-    let socket = UdpSocket::bind(("0.0.0.0", args.udp_port)).map_err(|err| {
+    let socket = UdpSocket::bind(("0.0.0.0", udp_port)).map_err(|err| {
         quote_common::quote_error!(NetworkError, "failed to bind UDP socket: {}", err)
     })?;
     let local_addr = socket.local_addr().map_err(|err| {
@@ -41,7 +75,8 @@ fn run() -> Result<(), QuoteError> {
     // Send STREAM command and get the client's IP address from the TCP connection.
     // The function constructs the UDP address using the client's IP (from TCP connection)
     // and the UDP port, ensuring the server can send UDP packets back to this client.
-    let client_ip = send_stream_command(args.server_addr, local_addr.port(), &tickers)?;
+    let client_ip =
+        send_stream_command(args.server_addr, local_addr.port(), &tickers, args.encoding)?;
     let advertised_udp_addr = format!("{}:{}", client_ip, local_addr.port());
 
     info!(
@@ -57,7 +92,7 @@ fn run() -> Result<(), QuoteError> {
         quote_common::quote_error!(NetworkError, "failed to clone UDP socket: {}", err)
     })?;
 
-    let listener_handle = spawn_listener(socket, Arc::clone(&shutdown))?;
+    let listener_handle = spawn_listener(socket, None, Arc::clone(&shutdown))?;
     let ping_handle = spawn_ping_thread(ping_socket, args.server_addr, Arc::clone(&shutdown))?;
 
     info!("STREAM established; press Ctrl+C to stop.");
@@ -81,3 +116,96 @@ fn run() -> Result<(), QuoteError> {
 
     Ok(())
 }
+
+/// Stream quotes as newline-delimited frames on the TCP control connection.
+fn stream_over_tcp(server_addr: SocketAddr, tickers: &[String]) -> Result<(), QuoteError> {
+    let reader = open_tcp_stream(server_addr, tickers)?;
+    let shutdown = quote_common::setup_shutdown_flag()?;
+    let listener_handle = spawn_tcp_listener(reader, Arc::clone(&shutdown))?;
+
+    info!("TCP STREAM established; press Ctrl+C to stop.");
+
+    listener_handle
+        .join()
+        .map_err(|_| quote_common::quote_error!(NetworkError, "TCP listener thread panicked"))?;
+
+    info!("Client shut down cleanly.");
+
+    Ok(())
+}
+
+/// Receive a server multicast group and log only the requested tickers.
+/// No STREAM command or PING is sent; the server publishes groups unconditionally.
+fn join_multicast_group(
+    group: SocketAddrV4,
+    interface: Ipv4Addr,
+    tickers: Vec<String>,
+) -> Result<(), QuoteError> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, group.port())).map_err(|err| {
+        quote_common::quote_error!(NetworkError, "failed to bind UDP socket: {}", err)
+    })?;
+    socket
+        .join_multicast_v4(group.ip(), &interface)
+        .map_err(|err| {
+            quote_common::quote_error!(
+                NetworkError,
+                "failed to join multicast group {}: {}",
+                group,
+                err
+            )
+        })?;
+
+    info!(
+        "Joined multicast group {} on {}; filtering for [{}]",
+        group,
+        interface,
+        tickers.join(",")
+    );
+
+    let shutdown = quote_common::setup_shutdown_flag()?;
+    let filter = tickers.into_iter().collect::<HashSet<_>>();
+    let listener_handle = spawn_listener(socket, Some(filter), Arc::clone(&shutdown))?;
+
+    listener_handle
+        .join()
+        .map_err(|_| quote_common::quote_error!(NetworkError, "UDP listener thread panicked"))?;
+
+    info!("Client shut down cleanly.");
+
+    Ok(())
+}
+
+fn print_snapshot(server_addr: SocketAddr, tickers: &[String]) -> Result<(), QuoteError> {
+    let quotes = request_snapshot(server_addr, tickers)?;
+    info!(
+        "Server has quotes for {} of {} tickers:",
+        quotes.len(),
+        tickers.len()
+    );
+    for quote in quotes {
+        let price = |value| format_price(value, quote.precision);
+        info!(
+            "  {} price={} bid={}x{} ask={}x{} volume={} day_volume={} trades={} ts={}",
+            quote.ticker,
+            price(quote.price),
+            price(quote.bid),
+            quote.bid_size,
+            price(quote.ask),
+            quote.ask_size,
+            quote.volume,
+            quote.daily_volume,
+            quote.trade_count,
+            quote.timestamp
+        );
+    }
+    Ok(())
+}
+
+fn list_tickers(server_addr: SocketAddr, prefix: Option<&str>) -> Result<(), QuoteError> {
+    let tickers = request_ticker_list(server_addr, prefix)?;
+    info!("Server generates {} tickers:", tickers.len());
+    for ticker in tickers {
+        info!("  {ticker}");
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use quote_common::{BookLevel, BookSide, BookSnapshot, BookUpdate, LevelChange};
+
+/// Why a book update could not be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookError {
+    /// No snapshot of the ticker has arrived yet.
+    AwaitingSnapshot,
+    /// Updates were missed; the book is discarded until the next snapshot.
+    Gap { expected: u64, received: u64 },
+    /// Update older than the book, e.g. a reordered datagram; ignored.
+    Stale,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::AwaitingSnapshot => write!(f, "waiting for the first snapshot"),
+            BookError::Gap { expected, received } => write!(
+                f,
+                "expected update #{expected}, received #{received}; waiting for the next snapshot"
+            ),
+            BookError::Stale => write!(f, "update is older than the book"),
+        }
+    }
+}
+
+/// Depth of one ticker rebuilt from a snapshot and the updates after it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBook {
+    sequence: u64,
+    bids: Vec<BookLevel>,
+    asks: Vec<BookLevel>,
+    precision: u8,
+}
+
+impl OrderBook {
+    /// Sequence number of the last snapshot or update applied.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Bid levels, highest price first.
+    pub fn bids(&self) -> &[BookLevel] {
+        &self.bids
+    }
+
+    /// Ask levels, lowest price first.
+    pub fn asks(&self) -> &[BookLevel] {
+        &self.asks
+    }
+
+    /// Decimal places the prices are quoted with.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    fn apply(&mut self, change: &LevelChange) {
+        // Prices closer than half the last quoted decimal are the same level.
+        let tolerance = 0.5 * 10f64.powi(-i32::from(self.precision));
+        let levels = match change.side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        };
+        levels.retain(|level| (level.price - change.price).abs() >= tolerance);
+        if change.size == 0 {
+            return;
+        }
+        let better = |level: &BookLevel| match change.side {
+            BookSide::Bid => level.price > change.price,
+            BookSide::Ask => level.price < change.price,
+        };
+        let index = levels.iter().take_while(|level| better(level)).count();
+        levels.insert(
+            index,
+            BookLevel {
+                price: change.price,
+                size: change.size,
+            },
+        );
+    }
+}
+
+/// Order books of every ticker, rebuilt from depth snapshots and updates. An update
+/// applies only on top of the message with the previous sequence number; after a gap
+/// the ticker's book is dropped until the next snapshot replaces it.
+#[derive(Debug, Default)]
+pub struct BookReconstructor {
+    books: HashMap<String, OrderBook>,
+}
+
+impl BookReconstructor {
+    /// Replace the ticker's book with `snapshot`.
+    pub fn apply_snapshot(&mut self, snapshot: &BookSnapshot) -> &OrderBook {
+        let book = OrderBook {
+            sequence: snapshot.sequence,
+            bids: snapshot.bids.clone(),
+            asks: snapshot.asks.clone(),
+            precision: snapshot.precision,
+        };
+        self.books.insert(snapshot.ticker.clone(), book);
+        &self.books[&snapshot.ticker]
+    }
+
+    /// Apply `update` to the ticker's book if it directly follows the last message
+    /// applied.
+    pub fn apply_update(&mut self, update: &BookUpdate) -> Result<&OrderBook, BookError> {
+        let Some(expected) = self.books.get(&update.ticker).map(|book| book.sequence + 1) else {
+            return Err(BookError::AwaitingSnapshot);
+        };
+        if update.sequence < expected {
+            return Err(BookError::Stale);
+        }
+        if update.sequence > expected {
+            self.books.remove(&update.ticker);
+            return Err(BookError::Gap {
+                expected,
+                received: update.sequence,
+            });
+        }
+
+        let Some(book) = self.books.get_mut(&update.ticker) else {
+            return Err(BookError::AwaitingSnapshot);
+        };
+        book.sequence = update.sequence;
+        book.precision = update.precision;
+        for change in &update.changes {
+            book.apply(change);
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: f64, size: u32) -> BookLevel {
+        BookLevel { price, size }
+    }
+
+    fn snapshot(sequence: u64) -> BookSnapshot {
+        BookSnapshot {
+            ticker: "AAPL".to_string(),
+            sequence,
+            bids: vec![level(99.99, 300), level(99.98, 400)],
+            asks: vec![level(100.01, 500), level(100.02, 600)],
+            timestamp: 1_000,
+            precision: 2,
+        }
+    }
+
+    fn update(sequence: u64, changes: Vec<LevelChange>) -> BookUpdate {
+        BookUpdate {
+            ticker: "AAPL".to_string(),
+            sequence,
+            changes,
+            timestamp: 1_100,
+            precision: 2,
+        }
+    }
+
+    fn change(side: BookSide, price: f64, size: u32) -> LevelChange {
+        LevelChange { side, price, size }
+    }
+
+    #[test]
+    fn test_updates_apply_on_top_of_snapshot() {
+        let mut books = BookReconstructor::default();
+        assert_eq!(
+            books.apply_update(&update(1, Vec::new())),
+            Err(BookError::AwaitingSnapshot)
+        );
+        books.apply_snapshot(&snapshot(4));
+
+        let book = books
+            .apply_update(&update(
+                5,
+                vec![
+                    change(BookSide::Bid, 99.98, 0),
+                    change(BookSide::Bid, 100.0, 200),
+                    change(BookSide::Ask, 100.01, 100),
+                    change(BookSide::Ask, 100.03, 700),
+                ],
+            ))
+            .expect("next update applies");
+        assert_eq!(book.sequence(), 5);
+        assert_eq!(book.bids(), &[level(100.0, 200), level(99.99, 300)]);
+        assert_eq!(
+            book.asks(),
+            &[level(100.01, 100), level(100.02, 600), level(100.03, 700)]
+        );
+    }
+
+    #[test]
+    fn test_gap_discards_book_until_next_snapshot() {
+        let mut books = BookReconstructor::default();
+        books.apply_snapshot(&snapshot(4));
+
+        assert_eq!(
+            books.apply_update(&update(4, Vec::new())),
+            Err(BookError::Stale)
+        );
+        assert!(books.books.contains_key("AAPL"));
+
+        assert_eq!(
+            books.apply_update(&update(7, Vec::new())),
+            Err(BookError::Gap {
+                expected: 5,
+                received: 7
+            })
+        );
+        assert!(!books.books.contains_key("AAPL"));
+        assert_eq!(
+            books.apply_update(&update(8, Vec::new())),
+            Err(BookError::AwaitingSnapshot)
+        );
+
+        assert_eq!(books.apply_snapshot(&snapshot(9)).sequence(), 9);
+        assert!(books.apply_update(&update(10, Vec::new())).is_ok());
+    }
+}
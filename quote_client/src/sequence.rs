@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Number of sequence numbers behind the newest one that may still arrive late.
+/// Missing datagrams older than this are counted as lost for good.
+const REORDER_WINDOW: u64 = 1_024;
+
+/// How a received sequence number relates to the ones seen before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrival {
+    /// Next expected datagram (or the first one of the stream).
+    InOrder,
+    /// Arrived after skipping the given number of sequence numbers.
+    Gap(u64),
+    /// Previously missing datagram that arrived out of order.
+    Late,
+    /// Datagram already received; should be ignored.
+    Duplicate,
+    /// Datagram older than the reorder window; already counted as lost and ignored.
+    Stale,
+}
+
+/// Loss counters accumulated by a [`SequenceTracker`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LossStats {
+    /// Distinct datagrams received.
+    pub received: u64,
+    /// Datagrams missing so far (including ones that may still arrive late).
+    pub lost: u64,
+    /// Datagrams received more than once.
+    pub duplicates: u64,
+    /// Datagrams that arrived after a later one.
+    pub reordered: u64,
+}
+
+impl fmt::Display for LossStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "received={} lost={} duplicates={} reordered={}",
+            self.received, self.lost, self.duplicates, self.reordered
+        )
+    }
+}
+
+/// Tracks per-stream datagram sequence numbers to detect gaps, duplicates and reordering.
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    next_expected: Option<u64>,
+    missing: BTreeSet<u64>,
+    expired: u64,
+    received: u64,
+    duplicates: u64,
+    reordered: u64,
+}
+
+impl SequenceTracker {
+    /// Record a received sequence number and classify its arrival.
+    pub fn observe(&mut self, sequence: u64) -> Arrival {
+        let arrival = match self.next_expected {
+            None => {
+                self.next_expected = Some(sequence + 1);
+                Arrival::InOrder
+            }
+            Some(expected) if sequence == expected => {
+                self.next_expected = Some(sequence + 1);
+                Arrival::InOrder
+            }
+            Some(expected) if sequence > expected => {
+                let gap = sequence - expected;
+                // Only the tail of a huge gap can still arrive late; the rest is lost outright.
+                let tracked_from = expected.max(sequence.saturating_sub(REORDER_WINDOW));
+                self.expired += tracked_from - expected;
+                self.missing.extend(tracked_from..sequence);
+                self.next_expected = Some(sequence + 1);
+                Arrival::Gap(gap)
+            }
+            Some(expected) if sequence < expected.saturating_sub(REORDER_WINDOW) => {
+                return Arrival::Stale;
+            }
+            Some(_) if self.missing.remove(&sequence) => {
+                self.reordered += 1;
+                Arrival::Late
+            }
+            Some(_) => {
+                self.duplicates += 1;
+                return Arrival::Duplicate;
+            }
+        };
+
+        self.received += 1;
+        self.expire_old_gaps();
+        arrival
+    }
+
+    /// Current loss counters.
+    pub fn stats(&self) -> LossStats {
+        LossStats {
+            received: self.received,
+            lost: self.expired + self.missing.len() as u64,
+            duplicates: self.duplicates,
+            reordered: self.reordered,
+        }
+    }
+
+    fn expire_old_gaps(&mut self) {
+        let Some(expected) = self.next_expected else {
+            return;
+        };
+        let horizon = expected.saturating_sub(REORDER_WINDOW);
+        let still_tracked = self.missing.split_off(&horizon);
+        self.expired += self.missing.len() as u64;
+        self.missing = still_tracked;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_order_stream_has_no_loss() {
+        let mut tracker = SequenceTracker::default();
+        for sequence in 5..10 {
+            assert_eq!(tracker.observe(sequence), Arrival::InOrder);
+        }
+        assert_eq!(
+            tracker.stats(),
+            LossStats {
+                received: 5,
+                ..LossStats::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_gap_counts_missing_datagrams() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(0);
+        assert_eq!(tracker.observe(4), Arrival::Gap(3));
+        assert_eq!(tracker.stats().lost, 3);
+        assert_eq!(tracker.stats().received, 2);
+    }
+
+    #[test]
+    fn test_late_arrival_fills_gap() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(0);
+        tracker.observe(2);
+        assert_eq!(tracker.observe(1), Arrival::Late);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.lost, 0);
+        assert_eq!(stats.reordered, 1);
+        assert_eq!(stats.received, 3);
+    }
+
+    #[test]
+    fn test_duplicates_are_detected() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(0);
+        tracker.observe(1);
+        assert_eq!(tracker.observe(1), Arrival::Duplicate);
+        assert_eq!(tracker.observe(0), Arrival::Duplicate);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.duplicates, 2);
+        assert_eq!(stats.received, 2);
+    }
+
+    #[test]
+    fn test_old_gaps_expire_but_stay_counted() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(0);
+        tracker.observe(2);
+        tracker.observe(2 + REORDER_WINDOW + 1);
+
+        assert_eq!(tracker.stats().lost, 1 + REORDER_WINDOW);
+        assert_eq!(tracker.observe(1), Arrival::Stale);
+        assert_eq!(tracker.stats().lost, 1 + REORDER_WINDOW);
+        assert_eq!(tracker.stats().duplicates, 0);
+    }
+
+    #[test]
+    fn test_huge_gap_is_bounded() {
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(0);
+        assert_eq!(tracker.observe(1_000_000), Arrival::Gap(999_999));
+        assert_eq!(tracker.stats().lost, 999_999);
+        assert!(tracker.missing.len() as u64 <= REORDER_WINDOW);
+    }
+}
@@ -2,13 +2,27 @@ use std::io::{BufRead, BufReader, Write};
 use std::net::{IpAddr, SocketAddr, TcpStream};
 use std::time::Duration;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
-use quote_common::{QuoteError, RESPONSE_ERR_PREFIX, RESPONSE_OK, UDP_SCHEME_PREFIX};
+use quote_common::{
+    ENCODING_OPTION, QuoteEncoding, QuoteError, RESPONSE_ERR_PREFIX, RESPONSE_OK, RESPONSE_PARTIAL,
+    StockQuote, TCP_SCHEME_PREFIX, UDP_SCHEME_PREFIX,
+};
 
 const STREAM_PREFIX: &str = "STREAM";
+const LIST_COMMAND: &str = "LIST";
+const SNAPSHOT_PREFIX: &str = "SNAPSHOT";
 const TCP_READ_TIMEOUT_SECS: u64 = 5;
 
+/// Outcome of a successful STREAM command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StreamResponse {
+    /// Every requested ticker was subscribed.
+    Accepted,
+    /// Only the listed tickers were subscribed; the rest are unknown to the server.
+    Partial(Vec<String>),
+}
+
 /// Send a STREAM command to the server and verify the response.
 /// Returns the client's IP address as seen from the TCP connection.
 /// The UDP address is constructed using the client's IP from the TCP connection
@@ -17,9 +31,10 @@ pub fn send_stream_command(
     server_addr: SocketAddr,
     udp_port: u16,
     tickers: &[String],
+    encoding: QuoteEncoding,
 ) -> Result<IpAddr, QuoteError> {
     debug!("Connecting to TCP server {}", server_addr);
-    let mut stream = TcpStream::connect(server_addr)
+    let stream = TcpStream::connect(server_addr)
         .map_err(|err| quote_common::quote_error!(NetworkError, "TCP connect failed: {}", err))?;
 
     // Get the client's IP address from the TCP connection's local address.
@@ -34,8 +49,33 @@ pub fn send_stream_command(
 
     // Construct the UDP address using the client's IP and the provided port
     let udp_addr = SocketAddr::new(client_ip, udp_port);
-    let command = build_stream_command(udp_addr, tickers);
+    let command =
+        build_stream_command(&format!("{UDP_SCHEME_PREFIX}{udp_addr}"), tickers, encoding);
+
+    start_stream(stream, &command, tickers)?;
+    Ok(client_ip)
+}
+
+/// Send a STREAM command asking for quotes on the control connection itself.
+/// Returns a reader positioned at the first quote frame.
+pub fn open_tcp_stream(
+    server_addr: SocketAddr,
+    tickers: &[String],
+) -> Result<BufReader<TcpStream>, QuoteError> {
+    debug!("Connecting to TCP server {}", server_addr);
+    let stream = TcpStream::connect(server_addr)
+        .map_err(|err| quote_common::quote_error!(NetworkError, "TCP connect failed: {}", err))?;
 
+    let command = build_stream_command(TCP_SCHEME_PREFIX, tickers, QuoteEncoding::Json);
+    start_stream(stream, &command, tickers)
+}
+
+/// Send a STREAM command and check the server's response, warning about rejected tickers.
+fn start_stream(
+    mut stream: TcpStream,
+    command: &str,
+    tickers: &[String],
+) -> Result<BufReader<TcpStream>, QuoteError> {
     stream
         .set_read_timeout(Some(Duration::from_secs(TCP_READ_TIMEOUT_SECS)))
         .map_err(|err| {
@@ -55,19 +95,162 @@ pub fn send_stream_command(
         quote_common::quote_error!(NetworkError, "failed to read server response: {}", err)
     })?;
 
-    interpret_response(response.trim_end())?;
-    Ok(client_ip)
+    if let StreamResponse::Partial(accepted) = interpret_response(response.trim_end())? {
+        let rejected = tickers
+            .iter()
+            .filter(|ticker| !accepted.contains(ticker))
+            .cloned()
+            .collect::<Vec<_>>();
+        warn!(
+            "Server does not generate [{}]; streaming only [{}]",
+            rejected.join(","),
+            accepted.join(",")
+        );
+    }
+    Ok(reader)
+}
+
+/// Send a LIST command and return the tickers the server generates.
+/// An optional prefix restricts the listing to matching symbols.
+pub fn request_ticker_list(
+    server_addr: SocketAddr,
+    prefix: Option<&str>,
+) -> Result<Vec<String>, QuoteError> {
+    debug!("Connecting to TCP server {}", server_addr);
+    let mut stream = TcpStream::connect(server_addr)
+        .map_err(|err| quote_common::quote_error!(NetworkError, "TCP connect failed: {}", err))?;
+
+    stream
+        .set_read_timeout(Some(Duration::from_secs(TCP_READ_TIMEOUT_SECS)))
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "set_read_timeout failed: {}", err)
+        })?;
+
+    stream
+        .write_all(build_list_command(prefix).as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "failed to send LIST command: {}", err)
+        })?;
+
+    read_multiline_response(&mut BufReader::new(stream))
 }
 
-fn build_stream_command(udp_addr: SocketAddr, tickers: &[String]) -> String {
+/// Send a SNAPSHOT command and return the server's latest quote for each ticker.
+/// Tickers the server has not quoted yet are absent from the result.
+pub fn request_snapshot(
+    server_addr: SocketAddr,
+    tickers: &[String],
+) -> Result<Vec<StockQuote>, QuoteError> {
+    debug!("Connecting to TCP server {}", server_addr);
+    let mut stream = TcpStream::connect(server_addr)
+        .map_err(|err| quote_common::quote_error!(NetworkError, "TCP connect failed: {}", err))?;
+
+    stream
+        .set_read_timeout(Some(Duration::from_secs(TCP_READ_TIMEOUT_SECS)))
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "set_read_timeout failed: {}", err)
+        })?;
+
+    let command = format!("{SNAPSHOT_PREFIX} {}\n", tickers.join(","));
+    stream
+        .write_all(command.as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "failed to send SNAPSHOT command: {}", err)
+        })?;
+
+    read_snapshot_response(&mut BufReader::new(stream))
+}
+
+fn read_snapshot_response(reader: &mut impl BufRead) -> Result<Vec<StockQuote>, QuoteError> {
+    read_multiline_response(reader)?
+        .iter()
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| {
+                quote_common::quote_error!(ParseError, "invalid snapshot quote '{}': {}", line, err)
+            })
+        })
+        .collect()
+}
+
+fn build_list_command(prefix: Option<&str>) -> String {
+    match prefix {
+        Some(prefix) => format!("{LIST_COMMAND} {prefix}\n"),
+        None => format!("{LIST_COMMAND}\n"),
+    }
+}
+
+/// Read an `OK <count>` header followed by `count` body lines.
+fn read_multiline_response(reader: &mut impl BufRead) -> Result<Vec<String>, QuoteError> {
+    let mut header = String::new();
+    reader.read_line(&mut header).map_err(|err| {
+        quote_common::quote_error!(NetworkError, "failed to read server response: {}", err)
+    })?;
+    let count = parse_count_header(header.trim_end())?;
+
+    let mut lines = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut line = String::new();
+        let bytes_read = reader.read_line(&mut line).map_err(|err| {
+            quote_common::quote_error!(NetworkError, "failed to read server response: {}", err)
+        })?;
+        if bytes_read == 0 {
+            return Err(quote_common::quote_error!(
+                NetworkError,
+                "server closed connection after {} of {} lines",
+                lines.len(),
+                count
+            ));
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    Ok(lines)
+}
+
+fn parse_count_header(response: &str) -> Result<usize, QuoteError> {
+    if let Some(rest) = response.strip_prefix(RESPONSE_ERR_PREFIX) {
+        return Err(quote_common::quote_error!(InvalidCommand, "{}", rest));
+    }
+
+    response
+        .strip_prefix(RESPONSE_OK)
+        .and_then(|rest| rest.trim().parse::<usize>().ok())
+        .ok_or_else(|| {
+            quote_common::quote_error!(ParseError, "unexpected response from server: {}", response)
+        })
+}
+
+/// Build a STREAM command for a target such as `udp://host:port` or `tcp://`.
+fn build_stream_command(target: &str, tickers: &[String], encoding: QuoteEncoding) -> String {
     let ticker_list = tickers.join(",");
-    format!("{STREAM_PREFIX} {UDP_SCHEME_PREFIX}{udp_addr} {ticker_list}\n")
+    match encoding {
+        QuoteEncoding::Json => format!("{STREAM_PREFIX} {target} {ticker_list}\n"),
+        QuoteEncoding::Binary => {
+            format!("{STREAM_PREFIX} {target} {ticker_list} {ENCODING_OPTION}={encoding}\n")
+        }
+    }
 }
 
-fn interpret_response(response: &str) -> Result<(), QuoteError> {
+fn interpret_response(response: &str) -> Result<StreamResponse, QuoteError> {
     if response == RESPONSE_OK {
         info!("STREAM command accepted");
-        return Ok(());
+        return Ok(StreamResponse::Accepted);
+    }
+
+    if let Some(accepted) = response
+        .strip_prefix(RESPONSE_OK)
+        .and_then(|rest| rest.trim_start().strip_prefix(RESPONSE_PARTIAL))
+    {
+        info!("STREAM command partially accepted");
+        let accepted = accepted
+            .trim()
+            .split(',')
+            .filter(|ticker| !ticker.is_empty())
+            .map(str::to_string)
+            .collect();
+        return Ok(StreamResponse::Partial(accepted));
     }
 
     if let Some(rest) = response.strip_prefix(RESPONSE_ERR_PREFIX) {
@@ -87,16 +270,86 @@ mod tests {
 
     #[test]
     fn test_build_stream_command_formats_correctly() {
-        let addr: SocketAddr = "127.0.0.1:4000".parse().unwrap();
-        let cmd = build_stream_command(addr, &["AAPL".into(), "TSLA".into()]);
+        let cmd = build_stream_command(
+            "udp://127.0.0.1:4000",
+            &["AAPL".into(), "TSLA".into()],
+            QuoteEncoding::Json,
+        );
         assert_eq!(cmd, "STREAM udp://127.0.0.1:4000 AAPL,TSLA\n");
     }
 
+    #[test]
+    fn test_build_stream_command_requests_binary_encoding() {
+        let cmd = build_stream_command(
+            "udp://127.0.0.1:4000",
+            &["AAPL".into()],
+            QuoteEncoding::Binary,
+        );
+        assert_eq!(cmd, "STREAM udp://127.0.0.1:4000 AAPL encoding=binary\n");
+    }
+
+    #[test]
+    fn test_build_stream_command_for_tcp_delivery() {
+        let cmd = build_stream_command(TCP_SCHEME_PREFIX, &["AAPL".into()], QuoteEncoding::Json);
+        assert_eq!(cmd, "STREAM tcp:// AAPL\n");
+    }
+
+    #[test]
+    fn test_build_list_command_with_prefix() {
+        assert_eq!(build_list_command(None), "LIST\n");
+        assert_eq!(build_list_command(Some("AA")), "LIST AA\n");
+    }
+
+    #[test]
+    fn test_read_multiline_response() {
+        let mut reader = "OK 2\nAAPL\nAMZN\n".as_bytes();
+        let lines = read_multiline_response(&mut reader).expect("valid response");
+        assert_eq!(lines, vec!["AAPL", "AMZN"]);
+    }
+
+    #[test]
+    fn test_read_multiline_response_truncated() {
+        let mut reader = "OK 3\nAAPL\n".as_bytes();
+        let err = read_multiline_response(&mut reader).expect_err("should fail");
+        assert!(matches!(err, QuoteError::NetworkError { .. }));
+    }
+
+    #[test]
+    fn test_read_snapshot_response_parses_quotes() {
+        let quote = StockQuote::new("AAPL", 150.25, 3_500);
+        let response = format!(
+            "OK 1\n{}\n",
+            serde_json::to_string(&quote).expect("serialize")
+        );
+        let quotes = read_snapshot_response(&mut response.as_bytes()).expect("valid snapshot");
+        assert_eq!(quotes, vec![quote]);
+
+        let err =
+            read_snapshot_response(&mut "OK 1\nnot json\n".as_bytes()).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ParseError { .. }));
+    }
+
+    #[test]
+    fn test_parse_count_header_err() {
+        let err = parse_count_header("ERR nope").expect_err("should fail");
+        assert!(matches!(err, QuoteError::InvalidCommand { .. }));
+        assert!(parse_count_header("OK").is_err());
+    }
+
     #[test]
     fn test_interpret_response_ok() {
         assert!(interpret_response("OK").is_ok());
     }
 
+    #[test]
+    fn test_interpret_response_partial() {
+        let response = interpret_response("OK PARTIAL AAPL,MSFT").expect("partial accept");
+        assert_eq!(
+            response,
+            StreamResponse::Partial(vec!["AAPL".to_string(), "MSFT".to_string()])
+        );
+    }
+
     #[test]
     fn test_interpret_response_err() {
         let err = interpret_response("ERR invalid").expect_err("should fail");
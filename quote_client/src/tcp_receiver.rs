@@ -0,0 +1,128 @@
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+
+use quote_common::{QuoteError, RESPONSE_ERR_PREFIX, RESPONSE_OK};
+
+use crate::order_book::BookReconstructor;
+use crate::udp_receiver::handle_payload;
+
+// Constants replacing magic numbers/words in this module
+const TCP_READ_TIMEOUT_MS: u64 = 200;
+const TCP_LISTENER_THREAD_NAME: &str = "tcp-listener";
+
+/// Spawn a thread that reads quote frames from the control connection until shutdown
+/// is signalled. The shutdown flag is also raised when the server closes the connection.
+pub fn spawn_tcp_listener(
+    reader: BufReader<TcpStream>,
+    shutdown: Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>, QuoteError> {
+    reader
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(TCP_READ_TIMEOUT_MS)))
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "failed to set TCP read timeout: {}", err)
+        })?;
+
+    let handle = thread::Builder::new()
+        .name(TCP_LISTENER_THREAD_NAME.to_string())
+        .spawn(move || listen_loop(reader, shutdown))
+        .map_err(|err| {
+            quote_common::quote_error!(NetworkError, "failed to spawn TCP listener: {}", err)
+        })?;
+
+    Ok(handle)
+}
+
+fn listen_loop(mut reader: BufReader<TcpStream>, shutdown: Arc<AtomicBool>) {
+    info!("Receiving quotes over the TCP control connection");
+    let mut line = String::new();
+    let mut books = BookReconstructor::default();
+
+    while !shutdown.load(Ordering::SeqCst) {
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                warn!("Server closed the TCP stream");
+                shutdown.store(true, Ordering::SeqCst);
+            }
+            Ok(_) => {
+                if let Err(err) = handle_frame(line.trim_end(), &mut books) {
+                    warn!("{err}");
+                }
+                line.clear();
+            }
+            // A timed out read keeps any partial frame in the buffer for the next attempt.
+            Err(err)
+                if err.kind() == std::io::ErrorKind::WouldBlock
+                    || err.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(err) => {
+                warn!("TCP receive error: {}", err);
+                shutdown.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    debug!("TCP listener shutting down");
+}
+
+/// Handle one line from the connection: a message frame or a late command response.
+fn handle_frame(frame: &str, books: &mut BookReconstructor) -> Result<(), String> {
+    if let Some(message) = frame.strip_prefix(RESPONSE_ERR_PREFIX) {
+        return Err(format!("Server error: {message}"));
+    }
+    if frame.starts_with(RESPONSE_OK) {
+        debug!("Server response: {frame}");
+        return Ok(());
+    }
+    handle_payload(frame.as_bytes(), books)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    use quote_common::StockQuote;
+
+    #[test]
+    fn test_handle_frame_accepts_quote_and_responses() {
+        let quote = StockQuote::new("AAPL", 150.12, 1_000);
+        let frame = serde_json::to_string(&quote).expect("serialize");
+        let mut books = BookReconstructor::default();
+        assert!(handle_frame(&frame, &mut books).is_ok());
+        assert!(handle_frame("OK", &mut books).is_ok());
+
+        let err = handle_frame("ERR no active stream", &mut books).expect_err("should fail");
+        assert!(err.contains("no active stream"));
+    }
+
+    #[test]
+    fn test_listener_stops_when_server_closes_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let client =
+            TcpStream::connect(listener.local_addr().expect("local addr")).expect("connect client");
+        let (mut server_side, _) = listener.accept().expect("accept");
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = spawn_tcp_listener(BufReader::new(client), Arc::clone(&shutdown))
+            .expect("spawn listener");
+
+        let quote = StockQuote::new("AAPL", 150.12, 1_000);
+        let frame = serde_json::to_string(&quote).expect("serialize");
+        server_side
+            .write_all(format!("{frame}\n").as_bytes())
+            .expect("write frame");
+        drop(server_side);
+
+        handle.join().expect("join listener");
+        assert!(shutdown.load(Ordering::SeqCst));
+    }
+}
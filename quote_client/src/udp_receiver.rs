@@ -1,28 +1,38 @@
+use std::collections::HashSet;
 use std::net::{SocketAddr, UdpSocket};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use log::{debug, info, warn};
 
 use quote_common::{
-    BUFFER_SIZE, PING_INTERVAL_SECS, PING_PAYLOAD, QuoteError, StockQuote, UNKNOWN_ADDR_PLACEHOLDER,
+    BUFFER_SIZE, BookLevel, CorporateAction, Feed, MarketEvent, OhlcvBar, PING_INTERVAL_SECS,
+    PING_PAYLOAD, QuoteError, StockQuote, StreamMessage, Trade, UNKNOWN_ADDR_PLACEHOLDER,
+    decode_message, decode_packet, format_price, subscription_key,
 };
 
+use crate::order_book::{BookError, BookReconstructor, OrderBook};
+use crate::sequence::{Arrival, SequenceTracker};
+
 // Constants replacing magic numbers/words in this module
 const UDP_READ_TIMEOUT_MS: u64 = 200;
 const PING_LOOP_SLEEP_MS: u64 = 100;
 const WOULD_BLOCK_BACKOFF_MS: u64 = 50;
 const UDP_RECV_ERROR_BACKOFF_MS: u64 = 100;
+const LOSS_REPORT_INTERVAL_SECS: u64 = 10;
 const UDP_LISTENER_THREAD_NAME: &str = "udp-listener";
 const UDP_PING_THREAD_NAME: &str = "udp-ping";
 
 /// Spawn a thread that listens for UDP quotes until shutdown is signalled.
+/// With a filter of subscription keys, other messages are dropped (used for multicast
+/// groups, which carry every ticker the server publishes to them).
 pub fn spawn_listener(
     socket: UdpSocket,
+    filter: Option<HashSet<String>>,
     shutdown: Arc<AtomicBool>,
 ) -> Result<thread::JoinHandle<()>, QuoteError> {
     socket.set_nonblocking(true).map_err(|err| {
@@ -40,7 +50,7 @@ pub fn spawn_listener(
 
     let handle = thread::Builder::new()
         .name(UDP_LISTENER_THREAD_NAME.to_string())
-        .spawn(move || listen_loop(socket, shutdown))
+        .spawn(move || listen_loop(socket, filter, shutdown))
         .map_err(|err| {
             quote_common::quote_error!(NetworkError, "failed to spawn UDP listener: {}", err)
         })?;
@@ -90,7 +100,7 @@ fn ping_loop(socket: UdpSocket, server_addr: SocketAddr, shutdown: Arc<AtomicBoo
     debug!("Ping thread shutting down");
 }
 
-fn listen_loop(socket: UdpSocket, shutdown: Arc<AtomicBool>) {
+fn listen_loop(socket: UdpSocket, filter: Option<HashSet<String>>, shutdown: Arc<AtomicBool>) {
     let mut buffer = [0u8; BUFFER_SIZE];
     info!(
         "Listening for UDP quotes on {}",
@@ -100,10 +110,22 @@ fn listen_loop(socket: UdpSocket, shutdown: Arc<AtomicBool>) {
             .unwrap_or_else(|_| UNKNOWN_ADDR_PLACEHOLDER.into())
     );
 
+    let mut tracker = SequenceTracker::default();
+    let mut books = BookReconstructor::default();
+    let report_interval = Duration::from_secs(LOSS_REPORT_INTERVAL_SECS);
+    let mut last_report = Instant::now();
+
     while !shutdown.load(Ordering::SeqCst) {
+        if last_report.elapsed() >= report_interval {
+            info!("Stream statistics: {}", tracker.stats());
+            last_report = Instant::now();
+        }
+
         match socket.recv(&mut buffer) {
             Ok(size) => {
-                if let Err(err) = handle_payload(&buffer[..size]) {
+                if let Err(err) =
+                    handle_datagram(&buffer[..size], filter.as_ref(), &mut tracker, &mut books)
+                {
                     warn!("{err}");
                 }
             }
@@ -118,34 +140,307 @@ fn listen_loop(socket: UdpSocket, shutdown: Arc<AtomicBool>) {
         }
     }
 
+    info!("Final stream statistics: {}", tracker.stats());
     debug!("UDP listener shutting down");
 }
 
-fn handle_payload(payload: &[u8]) -> Result<(), String> {
-    let quote: StockQuote = serde_json::from_slice(payload)
-        .map_err(|err| format!("Failed to parse quote JSON: {err}"))?;
+/// Decode a datagram and log its messages, returning how many passed the filter.
+fn handle_datagram(
+    datagram: &[u8],
+    filter: Option<&HashSet<String>>,
+    tracker: &mut SequenceTracker,
+    books: &mut BookReconstructor,
+) -> Result<usize, String> {
+    let (sequence, entries) =
+        decode_packet(datagram).map_err(|err| format!("Failed to parse datagram: {err}"))?;
+
+    match tracker.observe(sequence) {
+        Arrival::InOrder => {}
+        Arrival::Gap(missing) => warn!("Missed {missing} datagram(s) before #{sequence}"),
+        Arrival::Late => debug!("Datagram #{sequence} arrived out of order"),
+        Arrival::Duplicate | Arrival::Stale => {
+            debug!("Ignoring repeated or stale datagram #{sequence}");
+            return Ok(0);
+        }
+    }
+
+    let mut delivered = 0;
+    for payload in entries {
+        let message = decode_payload(payload)?;
+        let key = message.subscription_key();
+        if filter.is_none_or(|keys| key.is_none_or(|key| keys.contains(&key))) {
+            log_message(&message, books);
+            delivered += 1;
+        }
+    }
+    Ok(delivered)
+}
+
+/// Decode a single encoded message and log it according to its kind, applying depth
+/// messages to `books` first.
+pub fn handle_payload(payload: &[u8], books: &mut BookReconstructor) -> Result<(), String> {
+    log_message(&decode_payload(payload)?, books);
+    Ok(())
+}
+
+fn decode_payload(payload: &[u8]) -> Result<StreamMessage, String> {
+    decode_message(payload).map_err(|err| format!("Failed to parse message: {err}"))
+}
+
+fn log_message(message: &StreamMessage, books: &mut BookReconstructor) {
+    match message {
+        StreamMessage::Trade(trade) => log_trade(trade),
+        StreamMessage::Quote(quote) => log_quote(quote),
+        StreamMessage::Bar(bar) => log_bar(bar),
+        StreamMessage::BookSnapshot(snapshot) => {
+            log_book(&snapshot.ticker, books.apply_snapshot(snapshot))
+        }
+        StreamMessage::BookUpdate(update) => match books.apply_update(update) {
+            Ok(book) => log_book(&update.ticker, book),
+            Err(err @ BookError::Gap { .. }) => warn!("Book [{}] {}", update.ticker, err),
+            Err(err) => debug!(
+                "Ignoring book update [{}] #{}: {}",
+                update.ticker, update.sequence, err
+            ),
+        },
+        StreamMessage::Event(event) => log_event(event),
+        StreamMessage::CorporateAction(action) => log_corporate_action(action),
+        StreamMessage::Session(event) => {
+            info!(
+                "Market session is now {} (ts={})",
+                event.state, event.timestamp
+            )
+        }
+        StreamMessage::Heartbeat(heartbeat) => debug!("Heartbeat (ts={})", heartbeat.timestamp),
+        StreamMessage::Error(error) => warn!(
+            "Server reported an error: {} (ts={})",
+            error.message, error.timestamp
+        ),
+    }
+}
+
+fn log_trade(trade: &Trade) {
     info!(
-        "Quote [{}] price=${:.2} volume={} ts={}",
-        quote.ticker, quote.price, quote.volume, quote.timestamp
+        "Trade [{}] {} @ ${} ts={}",
+        trade.ticker,
+        trade.volume,
+        format_price(trade.price, trade.precision),
+        trade.timestamp
     );
-    Ok(())
+}
+
+fn log_event(event: &MarketEvent) {
+    let price = format_price(event.price, event.precision);
+    let reason = event
+        .reason
+        .map(|reason| format!(" reason={reason}"))
+        .unwrap_or_default();
+    match event.resume_at {
+        Some(resume_at) => info!(
+            "Event [{}] {} price=${} ts={} resume_at={}{}",
+            event.ticker, event.kind, price, event.timestamp, resume_at, reason
+        ),
+        None => info!(
+            "Event [{}] {} price=${} ts={}{}",
+            event.ticker, event.kind, price, event.timestamp, reason
+        ),
+    }
+}
+
+fn log_corporate_action(action: &CorporateAction) {
+    info!(
+        "Corporate action [{}] {} price=${} -> ${} ts={}",
+        action.ticker,
+        action.action,
+        format_price(action.previous_price, action.precision),
+        format_price(action.price, action.precision),
+        action.timestamp
+    );
+}
+
+fn log_bar(bar: &OhlcvBar) {
+    let price = |value| format_price(value, bar.precision);
+    info!(
+        "Bar [{}] start={} O=${} H=${} L=${} C=${} volume={}",
+        subscription_key(&bar.ticker, Feed::Bars(bar.interval)),
+        bar.start,
+        price(bar.open),
+        price(bar.high),
+        price(bar.low),
+        price(bar.close),
+        bar.volume
+    );
+}
+
+fn log_book(ticker: &str, book: &OrderBook) {
+    let best = |levels: &[BookLevel]| {
+        levels.first().map_or_else(
+            || "-".to_string(),
+            |level| {
+                format!(
+                    "${}x{}",
+                    format_price(level.price, book.precision()),
+                    level.size
+                )
+            },
+        )
+    };
+    info!(
+        "Book [{}] #{} bid={} ask={} levels={}/{}",
+        ticker,
+        book.sequence(),
+        best(book.bids()),
+        best(book.asks()),
+        book.bids().len(),
+        book.asks().len()
+    );
+}
+
+fn log_quote(quote: &StockQuote) {
+    let price = |value| format_price(value, quote.precision);
+    if quote.halted {
+        info!(
+            "Quote [{}] HALTED price=${} day_volume={} trades={} ts={}",
+            quote.ticker,
+            price(quote.price),
+            quote.daily_volume,
+            quote.trade_count,
+            quote.timestamp
+        );
+    } else if quote.has_book() {
+        info!(
+            "Quote [{}] price=${} bid=${}x{} ask=${}x{} volume={} day_volume={} trades={} ts={}",
+            quote.ticker,
+            price(quote.price),
+            price(quote.bid),
+            quote.bid_size,
+            price(quote.ask),
+            quote.ask_size,
+            quote.volume,
+            quote.daily_volume,
+            quote.trade_count,
+            quote.timestamp
+        );
+    } else {
+        info!(
+            "Quote [{}] price=${} volume={} day_volume={} trades={} ts={}",
+            quote.ticker,
+            price(quote.price),
+            quote.volume,
+            quote.daily_volume,
+            quote.trade_count,
+            quote.timestamp
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quote_common::{Heartbeat, PacketBuilder, QuoteEncoding, encode_message, encode_quote};
 
     #[test]
     fn test_handle_payload_logs_valid_quote() {
         let quote = StockQuote::new("AAPL", 150.12, 1_000);
         let payload = serde_json::to_vec(&quote).expect("serialize");
-        assert!(handle_payload(&payload).is_ok());
+        assert!(handle_payload(&payload, &mut BookReconstructor::default()).is_ok());
     }
 
     #[test]
     fn test_handle_payload_rejects_invalid_json() {
-        let err = handle_payload(br#"{"ticker": 123}"#).expect_err("should fail");
-        assert!(err.contains("Failed to parse quote JSON"));
+        let err = handle_payload(br#"{"ticker": 123}"#, &mut BookReconstructor::default())
+            .expect_err("should fail");
+        assert!(err.contains("Failed to parse message"));
+    }
+
+    #[test]
+    fn test_handle_datagram_filters_bar_subscriptions() {
+        let quote = StockQuote::new("AAPL", 150.0, 1_000);
+        let bar = OhlcvBar::open(&quote.last_trade(), "1m".parse().unwrap());
+        let mut builder = PacketBuilder::new(BUFFER_SIZE);
+        for message in [
+            quote.last_trade().into(),
+            quote.into(),
+            bar.into(),
+            Heartbeat::now().into(),
+        ] {
+            builder.push(&encode_message(&message, QuoteEncoding::Binary).expect("encode"));
+        }
+        let mut tracker = SequenceTracker::default();
+        let mut books = BookReconstructor::default();
+
+        // Heartbeats are not tied to a ticker and pass every filter.
+        let filter = HashSet::from(["AAPL@1m".to_string()]);
+        let delivered =
+            handle_datagram(&builder.finish(0), Some(&filter), &mut tracker, &mut books)
+                .expect("valid datagram");
+        assert_eq!(delivered, 2);
+    }
+
+    #[test]
+    fn test_handle_payload_accepts_binary_quote() {
+        let quote = StockQuote::new("AAPL", 150.12, 1_000);
+        let payload = encode_quote(&quote, QuoteEncoding::Binary).expect("encode");
+        assert!(handle_payload(&payload, &mut BookReconstructor::default()).is_ok());
+    }
+
+    #[test]
+    fn test_handle_datagram_tracks_sequence() {
+        let quote = StockQuote::new("AAPL", 150.12, 1_000);
+        let payload = encode_quote(&quote, QuoteEncoding::Json).expect("encode");
+        let mut tracker = SequenceTracker::default();
+        let mut books = BookReconstructor::default();
+
+        for sequence in [0, 2, 2] {
+            let mut builder = PacketBuilder::new(BUFFER_SIZE);
+            builder.push(&payload);
+            builder.push(&payload);
+            handle_datagram(&builder.finish(sequence), None, &mut tracker, &mut books)
+                .expect("valid datagram");
+        }
+
+        let stats = tracker.stats();
+        assert_eq!(stats.received, 2);
+        assert_eq!(stats.lost, 1);
+        assert_eq!(stats.duplicates, 1);
+    }
+
+    #[test]
+    fn test_handle_datagram_filters_tickers() {
+        let mut builder = PacketBuilder::new(BUFFER_SIZE);
+        for quote in [
+            StockQuote::new("AAPL", 150.0, 1_000),
+            StockQuote::new("MSFT", 200.0, 2_000),
+        ] {
+            builder.push(&encode_quote(&quote, QuoteEncoding::Json).expect("encode"));
+        }
+        let mut tracker = SequenceTracker::default();
+        let mut books = BookReconstructor::default();
+
+        let filter = HashSet::from(["AAPL".to_string()]);
+        let delivered =
+            handle_datagram(&builder.finish(0), Some(&filter), &mut tracker, &mut books)
+                .expect("valid datagram");
+        assert_eq!(delivered, 1);
+
+        builder.push(
+            &encode_quote(&StockQuote::new("MSFT", 200.0, 2_000), QuoteEncoding::Json).unwrap(),
+        );
+        let delivered = handle_datagram(&builder.finish(1), None, &mut tracker, &mut books)
+            .expect("valid datagram");
+        assert_eq!(delivered, 1);
+    }
+
+    #[test]
+    fn test_handle_datagram_rejects_missing_envelope() {
+        let quote = StockQuote::new("AAPL", 150.12, 1_000);
+        let payload = encode_quote(&quote, QuoteEncoding::Json).expect("encode");
+        let mut tracker = SequenceTracker::default();
+        let mut books = BookReconstructor::default();
+
+        let err =
+            handle_datagram(&payload, None, &mut tracker, &mut books).expect_err("should fail");
+        assert!(err.contains("Failed to parse datagram"));
     }
 
     #[test]
@@ -0,0 +1,684 @@
+//! Wire encodings for quotes sent over UDP.
+//!
+//! Every datagram starts with a small envelope carrying the per-stream sequence
+//! number and the number of quotes batched into it:
+//!
+//! | offset | size | field                          |
+//! |--------|------|--------------------------------|
+//! | 0      | 1    | magic (`PACKET_MAGIC`, `'Q'`)  |
+//! | 1      | 1    | envelope version (`PACKET_VERSION`) |
+//! | 2      | 8    | sequence number (`u64`, LE)    |
+//! | 10     | 2    | entry count (`u16`, LE)        |
+//! | 12     | ..   | entries                        |
+//!
+//! Each entry is a `u16` little-endian length followed by one encoded quote.
+//!
+//! Quotes are sent either as JSON or in a compact binary layout. Both can be
+//! decoded with [`decode_quote`], which tells them apart by the first byte:
+//! JSON payloads always start with `{`, binary payloads with the version byte.
+//! Every other message kind has no binary layout and is sent as JSON, tagged with
+//! its `type`, in either encoding; [`decode_message`] returns every message kind.
+//! JSON quotes without a `type` field, as sent by older producers, decode as quotes.
+//!
+//! Binary layout (all integers and floats little-endian):
+//!
+//! | offset | size | field                      |
+//! |--------|------|----------------------------|
+//! | 0      | 1    | version (`BINARY_QUOTE_VERSION`) |
+//! | 1      | 1    | ticker length `n`          |
+//! | 2      | n    | ticker (ASCII)             |
+//! | 2+n    | 8    | price (`f64`)              |
+//! | 10+n   | 4    | volume (`u32`)             |
+//! | 14+n   | 8    | timestamp (`i64`, ms)      |
+//! | 22+n   | 8    | bid (`f64`)                |
+//! | 30+n   | 8    | ask (`f64`)                |
+//! | 38+n   | 4    | bid size (`u32`)           |
+//! | 42+n   | 4    | ask size (`u32`)           |
+//! | 46+n   | 1    | price precision (`u8`)     |
+//! | 47+n   | 8    | daily volume (`u64`)       |
+//! | 55+n   | 8    | daily trade count (`u64`)  |
+//! | 63+n   | 1    | flags (bit 0: halted)      |
+//!
+//! Older payloads still decode: version 1 ends after the timestamp (no spread),
+//! version 2 after the ask size, version 3 after the precision and version 4 after
+//! the daily trade count. Missing fields get the default precision, zero daily
+//! totals and no flags.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::{DEFAULT_PRICE_PRECISION, QuoteError, StockQuote, StreamMessage};
+
+/// First byte of every datagram envelope.
+pub const PACKET_MAGIC: u8 = b'Q';
+/// Current datagram envelope version.
+pub const PACKET_VERSION: u8 = 2;
+/// Size of the datagram envelope preceding the entries.
+pub const PACKET_HEADER_LEN: usize = 1 + 1 + 8 + 2;
+/// Size of the length prefix in front of every entry.
+pub const PACKET_ENTRY_OVERHEAD: usize = 2;
+/// Version byte leading every binary-encoded quote.
+pub const BINARY_QUOTE_VERSION: u8 = 5;
+/// Version byte of binary quotes without bid/ask fields, accepted when decoding.
+const LEGACY_BINARY_QUOTE_VERSION: u8 = 1;
+/// Version byte of binary quotes without a precision byte, accepted when decoding.
+const BOOK_BINARY_QUOTE_VERSION: u8 = 2;
+/// Version byte of binary quotes without daily totals, accepted when decoding.
+const PRECISION_BINARY_QUOTE_VERSION: u8 = 3;
+/// Version byte of binary quotes without a flags byte, accepted when decoding.
+const DAILY_TOTALS_BINARY_QUOTE_VERSION: u8 = 4;
+/// Flag bit set in binary quotes of halted tickers.
+const HALTED_FLAG: u8 = 0b0000_0001;
+/// First byte of every JSON-encoded quote.
+const JSON_OBJECT_START: u8 = b'{';
+/// Size of the binary layout excluding the ticker bytes.
+const BINARY_FIXED_LEN: usize = 1 + 1 + 8 + 4 + 8 + 8 + 8 + 4 + 4 + 1 + 8 + 8 + 1;
+
+/// Encoding used for quotes in UDP datagrams, negotiated in the STREAM command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteEncoding {
+    /// JSON-serialized `StockQuote` (default).
+    #[default]
+    Json,
+    /// Compact little-endian binary layout.
+    Binary,
+}
+
+impl FromStr for QuoteEncoding {
+    type Err = QuoteError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(QuoteEncoding::Json),
+            "binary" => Ok(QuoteEncoding::Binary),
+            other => Err(crate::quote_error!(
+                ParseError,
+                "unknown quote encoding: {}",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for QuoteEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuoteEncoding::Json => write!(f, "json"),
+            QuoteEncoding::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// Accumulates encoded quotes into a single datagram no larger than a size limit.
+#[derive(Debug)]
+pub struct PacketBuilder {
+    max_size: usize,
+    entries: Vec<u8>,
+    count: u16,
+}
+
+impl PacketBuilder {
+    /// Create a builder producing datagrams of at most `max_size` bytes.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            entries: Vec::with_capacity(max_size.saturating_sub(PACKET_HEADER_LEN)),
+            count: 0,
+        }
+    }
+
+    /// Whether a payload of the given length still fits into the current datagram.
+    pub fn fits(&self, payload_len: usize) -> bool {
+        self.count < u16::MAX
+            && payload_len <= usize::from(u16::MAX)
+            && PACKET_HEADER_LEN + self.entries.len() + PACKET_ENTRY_OVERHEAD + payload_len
+                <= self.max_size
+    }
+
+    /// Append an encoded quote, returning `false` (and leaving the batch untouched)
+    /// if it does not fit.
+    pub fn push(&mut self, payload: &[u8]) -> bool {
+        if !self.fits(payload.len()) {
+            return false;
+        }
+        // `fits` guarantees the length is representable as u16.
+        let len = payload.len() as u16;
+        self.entries.extend_from_slice(&len.to_le_bytes());
+        self.entries.extend_from_slice(payload);
+        self.count += 1;
+        true
+    }
+
+    /// Number of quotes in the current datagram.
+    pub fn len(&self) -> usize {
+        usize::from(self.count)
+    }
+
+    /// Whether the current datagram holds no quotes.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Produce the datagram with the given sequence number and reset the builder.
+    pub fn finish(&mut self, sequence: u64) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PACKET_HEADER_LEN + self.entries.len());
+        bytes.push(PACKET_MAGIC);
+        bytes.push(PACKET_VERSION);
+        bytes.extend_from_slice(&sequence.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.append(&mut self.entries);
+        self.count = 0;
+        bytes
+    }
+}
+
+/// Split a datagram into its sequence number and encoded quotes.
+pub fn decode_packet(bytes: &[u8]) -> Result<(u64, Vec<&[u8]>), QuoteError> {
+    let mut reader = ByteReader::new(bytes);
+    let magic = reader.read_u8()?;
+    if magic != PACKET_MAGIC {
+        return Err(crate::quote_error!(
+            SerializationError,
+            "datagram does not start with packet magic (found {:#04x})",
+            magic
+        ));
+    }
+    let version = reader.read_u8()?;
+    if version != PACKET_VERSION {
+        return Err(crate::quote_error!(
+            SerializationError,
+            "unsupported packet version {}",
+            version
+        ));
+    }
+    let sequence = u64::from_le_bytes(reader.read_array()?);
+    let count = u16::from_le_bytes(reader.read_array()?);
+
+    let mut entries = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let len = u16::from_le_bytes(reader.read_array()?);
+        entries.push(reader.take(usize::from(len))?);
+    }
+
+    if !reader.is_empty() {
+        return Err(crate::quote_error!(
+            SerializationError,
+            "unexpected trailing bytes after {} packet entries",
+            count
+        ));
+    }
+
+    Ok((sequence, entries))
+}
+
+/// Encode a quote using the requested encoding.
+pub fn encode_quote(quote: &StockQuote, encoding: QuoteEncoding) -> Result<Vec<u8>, QuoteError> {
+    match encoding {
+        QuoteEncoding::Json => serde_json::to_vec(quote).map_err(|err| {
+            crate::quote_error!(
+                SerializationError,
+                "failed to serialize quote for {}: {}",
+                quote.ticker,
+                err
+            )
+        }),
+        QuoteEncoding::Binary => encode_quote_binary(quote),
+    }
+}
+
+/// Decode a quote in either encoding, detected from the first byte.
+pub fn decode_quote(payload: &[u8]) -> Result<StockQuote, QuoteError> {
+    match payload.first() {
+        Some(&JSON_OBJECT_START) => serde_json::from_slice(payload).map_err(|err| {
+            crate::quote_error!(SerializationError, "failed to parse quote JSON: {}", err)
+        }),
+        Some(
+            &BINARY_QUOTE_VERSION
+            | &DAILY_TOTALS_BINARY_QUOTE_VERSION
+            | &PRECISION_BINARY_QUOTE_VERSION
+            | &BOOK_BINARY_QUOTE_VERSION
+            | &LEGACY_BINARY_QUOTE_VERSION,
+        ) => decode_quote_binary(payload),
+        Some(other) => Err(crate::quote_error!(
+            SerializationError,
+            "unrecognized quote payload starting with byte {:#04x}",
+            other
+        )),
+        None => Err(crate::quote_error!(
+            SerializationError,
+            "empty quote payload"
+        )),
+    }
+}
+
+/// Encode a stream message. Quotes use the requested encoding; other messages are always JSON.
+pub fn encode_message(
+    message: &StreamMessage,
+    encoding: QuoteEncoding,
+) -> Result<Vec<u8>, QuoteError> {
+    match (message, encoding) {
+        (StreamMessage::Quote(quote), QuoteEncoding::Binary) => encode_quote_binary(quote),
+        _ => serde_json::to_vec(message).map_err(|err| {
+            crate::quote_error!(
+                SerializationError,
+                "failed to serialize {}: {}",
+                message,
+                err
+            )
+        }),
+    }
+}
+
+/// Decode any message kind in either encoding.
+pub fn decode_message(payload: &[u8]) -> Result<StreamMessage, QuoteError> {
+    match payload.first() {
+        Some(&JSON_OBJECT_START) => decode_message_json(payload),
+        _ => decode_quote(payload).map(StreamMessage::Quote),
+    }
+}
+
+/// Decode a JSON message, falling back to a bare quote when the `type` tag is missing.
+fn decode_message_json(payload: &[u8]) -> Result<StreamMessage, QuoteError> {
+    serde_json::from_slice(payload).or_else(|err| {
+        let untagged = serde_json::from_slice::<MessageTag>(payload)
+            .is_ok_and(|tag| tag.message_type.is_none());
+        if untagged {
+            return decode_quote(payload).map(StreamMessage::Quote);
+        }
+        Err(crate::quote_error!(
+            SerializationError,
+            "failed to parse message JSON: {}",
+            err
+        ))
+    })
+}
+
+/// Just the `type` field of a JSON message.
+#[derive(Deserialize)]
+struct MessageTag {
+    #[serde(rename = "type")]
+    message_type: Option<String>,
+}
+
+/// Encode a quote in the compact binary layout.
+pub fn encode_quote_binary(quote: &StockQuote) -> Result<Vec<u8>, QuoteError> {
+    let ticker = quote.ticker.as_bytes();
+    let ticker_len = u8::try_from(ticker.len()).map_err(|_| {
+        crate::quote_error!(
+            SerializationError,
+            "ticker '{}' is too long for binary encoding",
+            quote.ticker
+        )
+    })?;
+
+    let mut bytes = Vec::with_capacity(BINARY_FIXED_LEN + ticker.len());
+    bytes.push(BINARY_QUOTE_VERSION);
+    bytes.push(ticker_len);
+    bytes.extend_from_slice(ticker);
+    bytes.extend_from_slice(&quote.price.to_le_bytes());
+    bytes.extend_from_slice(&quote.volume.to_le_bytes());
+    bytes.extend_from_slice(&quote.timestamp.to_le_bytes());
+    bytes.extend_from_slice(&quote.bid.to_le_bytes());
+    bytes.extend_from_slice(&quote.ask.to_le_bytes());
+    bytes.extend_from_slice(&quote.bid_size.to_le_bytes());
+    bytes.extend_from_slice(&quote.ask_size.to_le_bytes());
+    bytes.push(quote.precision);
+    bytes.extend_from_slice(&quote.daily_volume.to_le_bytes());
+    bytes.extend_from_slice(&quote.trade_count.to_le_bytes());
+    bytes.push(if quote.halted { HALTED_FLAG } else { 0 });
+    Ok(bytes)
+}
+
+/// Decode a quote from the compact binary layout.
+pub fn decode_quote_binary(bytes: &[u8]) -> Result<StockQuote, QuoteError> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.read_u8()?;
+    if !matches!(
+        version,
+        BINARY_QUOTE_VERSION
+            | DAILY_TOTALS_BINARY_QUOTE_VERSION
+            | PRECISION_BINARY_QUOTE_VERSION
+            | BOOK_BINARY_QUOTE_VERSION
+            | LEGACY_BINARY_QUOTE_VERSION
+    ) {
+        return Err(crate::quote_error!(
+            SerializationError,
+            "unsupported binary quote version {}",
+            version
+        ));
+    }
+
+    let ticker_len = usize::from(reader.read_u8()?);
+    let ticker = std::str::from_utf8(reader.take(ticker_len)?)
+        .map_err(|err| crate::quote_error!(SerializationError, "invalid ticker bytes: {}", err))?
+        .to_string();
+    let price = f64::from_le_bytes(reader.read_array()?);
+    let volume = u32::from_le_bytes(reader.read_array()?);
+    let timestamp = i64::from_le_bytes(reader.read_array()?);
+    let mut quote = StockQuote {
+        ticker,
+        price,
+        volume,
+        timestamp,
+        bid: 0.0,
+        ask: 0.0,
+        bid_size: 0,
+        ask_size: 0,
+        precision: DEFAULT_PRICE_PRECISION,
+        daily_volume: 0,
+        trade_count: 0,
+        halted: false,
+    };
+    if version != LEGACY_BINARY_QUOTE_VERSION {
+        quote.bid = f64::from_le_bytes(reader.read_array()?);
+        quote.ask = f64::from_le_bytes(reader.read_array()?);
+        quote.bid_size = u32::from_le_bytes(reader.read_array()?);
+        quote.ask_size = u32::from_le_bytes(reader.read_array()?);
+    }
+    if matches!(
+        version,
+        BINARY_QUOTE_VERSION | DAILY_TOTALS_BINARY_QUOTE_VERSION | PRECISION_BINARY_QUOTE_VERSION
+    ) {
+        quote.precision = reader.read_u8()?;
+    }
+    if matches!(
+        version,
+        BINARY_QUOTE_VERSION | DAILY_TOTALS_BINARY_QUOTE_VERSION
+    ) {
+        quote.daily_volume = u64::from_le_bytes(reader.read_array()?);
+        quote.trade_count = u64::from_le_bytes(reader.read_array()?);
+    }
+    if version == BINARY_QUOTE_VERSION {
+        quote.halted = reader.read_u8()? & HALTED_FLAG != 0;
+    }
+
+    if !reader.is_empty() {
+        return Err(crate::quote_error!(
+            SerializationError,
+            "unexpected trailing bytes after binary quote"
+        ));
+    }
+
+    Ok(quote)
+}
+
+/// Cursor over a byte slice that fails instead of panicking on short input.
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], QuoteError> {
+        if self.bytes.len() < len {
+            return Err(crate::quote_error!(
+                SerializationError,
+                "truncated binary payload: needed {} more bytes, found {}",
+                len,
+                self.bytes.len()
+            ));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, QuoteError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], QuoteError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_quote() -> StockQuote {
+        StockQuote {
+            ticker: "AAPL".to_string(),
+            price: 150.25,
+            volume: 3_500,
+            timestamp: 1_699_564_800_000,
+            bid: 150.24,
+            ask: 150.27,
+            bid_size: 300,
+            ask_size: 500,
+            precision: 4,
+            daily_volume: 1_250_000,
+            trade_count: 412,
+            halted: false,
+        }
+    }
+
+    #[test]
+    fn test_binary_quote_roundtrip() {
+        let quote = sample_quote();
+        let bytes = encode_quote_binary(&quote).expect("encode");
+        assert_eq!(bytes.len(), BINARY_FIXED_LEN + quote.ticker.len());
+        assert_eq!(bytes[0], BINARY_QUOTE_VERSION);
+        assert_eq!(decode_quote_binary(&bytes).expect("decode"), quote);
+    }
+
+    #[test]
+    fn test_decode_legacy_binary_quote_without_book() {
+        let quote = sample_quote();
+        let mut bytes = encode_quote_binary(&quote).expect("encode");
+        bytes.truncate(bytes.len() - (8 + 8 + 4 + 4 + 1 + 8 + 8 + 1));
+        bytes[0] = LEGACY_BINARY_QUOTE_VERSION;
+
+        let decoded = decode_quote(&bytes).expect("decode legacy");
+        assert_eq!(decoded.price, quote.price);
+        assert_eq!(decoded.timestamp, quote.timestamp);
+        assert!(!decoded.has_book());
+        assert_eq!(decoded.precision, DEFAULT_PRICE_PRECISION);
+    }
+
+    #[test]
+    fn test_decode_binary_quote_without_precision() {
+        let quote = sample_quote();
+        let mut bytes = encode_quote_binary(&quote).expect("encode");
+        bytes.truncate(bytes.len() - (1 + 8 + 8 + 1));
+        bytes[0] = BOOK_BINARY_QUOTE_VERSION;
+
+        let decoded = decode_quote(&bytes).expect("decode version 2");
+        assert_eq!(
+            decoded,
+            StockQuote {
+                precision: DEFAULT_PRICE_PRECISION,
+                daily_volume: 0,
+                trade_count: 0,
+                ..quote
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_binary_quote_without_daily_totals() {
+        let quote = sample_quote();
+        let mut bytes = encode_quote_binary(&quote).expect("encode");
+        bytes.truncate(bytes.len() - (8 + 8 + 1));
+        bytes[0] = PRECISION_BINARY_QUOTE_VERSION;
+
+        let decoded = decode_quote(&bytes).expect("decode version 3");
+        assert_eq!(
+            decoded,
+            StockQuote {
+                daily_volume: 0,
+                trade_count: 0,
+                ..quote
+            }
+        );
+    }
+
+    #[test]
+    fn test_halted_flag_roundtrips_and_defaults_for_version_4() {
+        let quote = StockQuote {
+            halted: true,
+            ..sample_quote()
+        };
+        let mut bytes = encode_quote_binary(&quote).expect("encode");
+        assert_eq!(decode_quote(&bytes).expect("decode"), quote);
+
+        bytes.truncate(bytes.len() - 1);
+        bytes[0] = DAILY_TOTALS_BINARY_QUOTE_VERSION;
+        let decoded = decode_quote(&bytes).expect("decode version 4");
+        assert_eq!(
+            decoded,
+            StockQuote {
+                halted: false,
+                ..quote
+            }
+        );
+    }
+
+    #[test]
+    fn test_message_roundtrip_in_both_encodings() {
+        let trade = sample_quote().last_trade();
+        let mut bar = crate::OhlcvBar::open(&trade, "1m".parse().expect("interval"));
+        bar.update(&trade);
+        for encoding in [QuoteEncoding::Json, QuoteEncoding::Binary] {
+            for message in [
+                StreamMessage::Quote(sample_quote()),
+                StreamMessage::Trade(trade.clone()),
+                StreamMessage::Bar(bar.clone()),
+                StreamMessage::Heartbeat(crate::Heartbeat { timestamp: 7 }),
+            ] {
+                let bytes = encode_message(&message, encoding).expect("encode");
+                assert_eq!(decode_message(&bytes).expect("decode"), message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_message_accepts_untagged_quote_json() {
+        let bare = encode_quote(&sample_quote(), QuoteEncoding::Json).expect("encode");
+        assert_eq!(
+            decode_message(&bare).expect("decode bare quote"),
+            StreamMessage::Quote(sample_quote())
+        );
+
+        let unknown = br#"{"type":"auction","ticker":"AAPL","price":1.0,"volume":1,"timestamp":1}"#;
+        let err = decode_message(unknown).expect_err("unknown type");
+        assert!(matches!(err, QuoteError::SerializationError { .. }));
+    }
+
+    #[test]
+    fn test_binary_quote_is_smaller_than_json() {
+        let quote = sample_quote();
+        let binary = encode_quote(&quote, QuoteEncoding::Binary).expect("encode binary");
+        let json = encode_quote(&quote, QuoteEncoding::Json).expect("encode json");
+        assert!(binary.len() < json.len());
+    }
+
+    #[test]
+    fn test_decode_quote_detects_encoding() {
+        let quote = sample_quote();
+        for encoding in [QuoteEncoding::Json, QuoteEncoding::Binary] {
+            let bytes = encode_quote(&quote, encoding).expect("encode");
+            assert_eq!(decode_quote(&bytes).expect("decode"), quote);
+        }
+    }
+
+    #[test]
+    fn test_decode_quote_binary_truncated() {
+        let bytes = encode_quote_binary(&sample_quote()).expect("encode");
+        let err = decode_quote_binary(&bytes[..bytes.len() - 1]).expect_err("should fail");
+        assert!(matches!(err, QuoteError::SerializationError { .. }));
+    }
+
+    #[test]
+    fn test_decode_quote_rejects_unknown_version() {
+        let mut bytes = encode_quote_binary(&sample_quote()).expect("encode");
+        bytes[0] = 0x7f;
+        assert!(decode_quote(&bytes).is_err());
+        assert!(decode_quote(&[]).is_err());
+    }
+
+    #[test]
+    fn test_encode_quote_binary_rejects_long_ticker() {
+        let mut quote = sample_quote();
+        quote.ticker = "X".repeat(300);
+        assert!(encode_quote_binary(&quote).is_err());
+    }
+
+    #[test]
+    fn test_packet_roundtrip() {
+        let json = encode_quote(&sample_quote(), QuoteEncoding::Json).expect("encode json");
+        let binary = encode_quote(&sample_quote(), QuoteEncoding::Binary).expect("encode binary");
+        let mut builder = PacketBuilder::new(1_200);
+        assert!(builder.push(&json));
+        assert!(builder.push(&binary));
+        assert_eq!(builder.len(), 2);
+
+        let packet = builder.finish(42);
+        assert!(builder.is_empty());
+        assert_eq!(
+            packet.len(),
+            PACKET_HEADER_LEN + 2 * PACKET_ENTRY_OVERHEAD + json.len() + binary.len()
+        );
+
+        let (sequence, entries) = decode_packet(&packet).expect("decode packet");
+        assert_eq!(sequence, 42);
+        assert_eq!(entries.len(), 2);
+        for entry in entries {
+            assert_eq!(decode_quote(entry).expect("decode quote"), sample_quote());
+        }
+    }
+
+    #[test]
+    fn test_packet_builder_respects_max_size() {
+        let payload = encode_quote(&sample_quote(), QuoteEncoding::Binary).expect("encode");
+        let max_size = PACKET_HEADER_LEN + 3 * (PACKET_ENTRY_OVERHEAD + payload.len());
+        let mut builder = PacketBuilder::new(max_size);
+
+        let mut pushed = 0;
+        while builder.push(&payload) {
+            pushed += 1;
+        }
+        assert_eq!(pushed, 3);
+        assert_eq!(builder.finish(0).len(), max_size);
+    }
+
+    #[test]
+    fn test_decode_packet_rejects_bare_quote() {
+        let payload = encode_quote(&sample_quote(), QuoteEncoding::Json).expect("encode");
+        assert!(decode_packet(&payload).is_err());
+        assert!(decode_packet(&[PACKET_MAGIC, PACKET_VERSION, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_decode_packet_rejects_truncated_entry() {
+        let payload = encode_quote(&sample_quote(), QuoteEncoding::Json).expect("encode");
+        let mut builder = PacketBuilder::new(1_200);
+        builder.push(&payload);
+        let packet = builder.finish(7);
+        assert!(decode_packet(&packet[..packet.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_quote_encoding_from_str() {
+        assert_eq!(
+            "json".parse::<QuoteEncoding>().unwrap(),
+            QuoteEncoding::Json
+        );
+        assert_eq!(
+            "BINARY".parse::<QuoteEncoding>().unwrap(),
+            QuoteEncoding::Binary
+        );
+        assert!("xml".parse::<QuoteEncoding>().is_err());
+    }
+}
@@ -1,5 +1,20 @@
 //! Shared types and utilities for the quote streaming system.
 
+mod codec;
+mod message;
+
+pub use codec::{
+    BINARY_QUOTE_VERSION, PACKET_ENTRY_OVERHEAD, PACKET_HEADER_LEN, PACKET_MAGIC, PACKET_VERSION,
+    PacketBuilder, QuoteEncoding, decode_message, decode_packet, decode_quote, decode_quote_binary,
+    encode_message, encode_quote, encode_quote_binary,
+};
+pub use message::{
+    BAR_KEY_SEPARATOR, BarInterval, BookLevel, BookSide, BookSnapshot, BookUpdate, CorporateAction,
+    CorporateActionKind, DEPTH_FEED, ErrorNotice, Feed, HaltReason, Heartbeat, LevelChange,
+    MarketEvent, MarketEventKind, OhlcvBar, SessionEvent, SessionState, StreamMessage, Trade,
+    parse_subscription_key, subscription_key,
+};
+
 use chrono::Utc;
 use crossbeam::channel;
 use log::info;
@@ -14,22 +29,86 @@ use std::sync::{
 pub const DEFAULT_QUOTE_RATE_MS: u64 = 1_000;
 /// Default keepalive timeout in seconds on the server.
 pub const DEFAULT_KEEPALIVE_TIMEOUT_SECS: u64 = 5;
+/// Default maximum number of concurrent TCP control sessions on the server.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+/// Default time in seconds a TCP client has to send a complete command line.
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 10;
 /// Interval in seconds for client PING messages.
 pub const PING_INTERVAL_SECS: u64 = 2;
 /// Default initial price when configuration omits a ticker.
 pub const DEFAULT_INITIAL_PRICE: f64 = 100.0;
-/// Popular tickers receive higher default volume ranges.
-pub const POPULAR_TICKERS: &[&str] = &["AAPL", "MSFT", "TSLA"];
+/// Default annualized drift of the simulated price process.
+pub const DEFAULT_ANNUAL_DRIFT: f64 = 0.05;
+/// Default annualized volatility of the simulated price process.
+pub const DEFAULT_ANNUAL_VOLATILITY: f64 = 0.25;
+/// Default time zone of the market calendar.
+pub const DEFAULT_MARKET_TIMEZONE: &str = "America/New_York";
+/// Default local start of pre-market trading (`HH:MM`).
+pub const DEFAULT_PRE_MARKET_OPEN: &str = "04:00";
+/// Default local start of regular trading hours.
+pub const DEFAULT_REGULAR_OPEN: &str = "09:30";
+/// Default local end of regular trading hours.
+pub const DEFAULT_REGULAR_CLOSE: &str = "16:00";
+/// Default local end of after-hours trading.
+pub const DEFAULT_AFTER_HOURS_CLOSE: &str = "20:00";
+/// Default share of regular volume and volatility seen in extended hours.
+pub const DEFAULT_EXTENDED_HOURS_ACTIVITY: f64 = 0.25;
+/// Default replay speed: recorded ticks are spaced as they originally arrived.
+pub const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+/// Default minimum price increment (one cent).
+pub const DEFAULT_TICK_SIZE: f64 = 0.01;
+/// Default number of decimal places prices are quoted and displayed with.
+pub const DEFAULT_PRICE_PRECISION: u8 = 2;
+/// Largest supported price precision (satoshi-sized ticks).
+pub const MAX_PRICE_PRECISION: u8 = 8;
+/// Default shares traded per regular session by tickers without an override.
+pub const DEFAULT_AVERAGE_DAILY_VOLUME: u64 = 10_000_000;
+/// Default volume rate at the regular open and close relative to midday.
+pub const DEFAULT_OPEN_CLOSE_VOLUME_RATIO: f64 = 3.0;
+/// Default extra volume per standard deviation of a trade's price move.
+pub const DEFAULT_VOLUME_SPIKE_SENSITIVITY: f64 = 1.0;
+/// Default distance of the price band limits from the reference price, in percent.
+pub const DEFAULT_PRICE_BAND_PERCENT: f64 = 5.0;
+/// Default seconds between refreshes of a ticker's band reference price.
+pub const DEFAULT_BAND_REFERENCE_SECS: u64 = 300;
+/// Default seconds a ticker stays halted after reaching a band limit.
+pub const DEFAULT_LIMIT_HALT_SECS: u64 = 300;
 
 /// Default buffer size for UDP packets.
 pub const BUFFER_SIZE: usize = 2048;
+/// Default upper bound in bytes for a batched UDP datagram (fits a typical Ethernet MTU).
+pub const DEFAULT_UDP_MAX_PAYLOAD: usize = 1_200;
+/// Smallest accepted UDP datagram size limit.
+pub const MIN_UDP_MAX_PAYLOAD: usize = 256;
+/// Default time in milliseconds a partially filled UDP batch may wait before being sent.
+pub const DEFAULT_UDP_FLUSH_INTERVAL_MS: u64 = 5;
+/// Longest accepted batch wait in milliseconds; idle streams heartbeat every second anyway.
+pub const MAX_UDP_FLUSH_INTERVAL_MS: u64 = 1_000;
+/// Bar intervals aggregated when the configuration does not list any.
+pub const DEFAULT_BAR_INTERVALS: &[&str] = &["1s", "1m", "5m"];
+/// Order-book levels simulated per side when the configuration does not say.
+pub const DEFAULT_BOOK_LEVELS: usize = 5;
+/// Most order-book levels per side; a full snapshot must fit in one datagram.
+pub const MAX_BOOK_LEVELS: usize = 10;
+/// Default milliseconds between full order-book snapshots of a ticker.
+pub const DEFAULT_BOOK_SNAPSHOT_INTERVAL_MS: u64 = 1_000;
+/// Default time-to-live for multicast datagrams (stay on the local network).
+pub const DEFAULT_MULTICAST_TTL: u32 = 1;
+/// Seconds a stream may stay idle before the server sends a heartbeat.
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 1;
 
 /// Protocol: UDP ping payload.
 pub const PING_PAYLOAD: &[u8] = b"PING";
+/// Protocol: STREAM option selecting the UDP quote encoding (`encoding=json|binary`).
+pub const ENCODING_OPTION: &str = "encoding";
 /// Protocol: UDP scheme prefix used in STREAM command.
 pub const UDP_SCHEME_PREFIX: &str = "udp://";
+/// Protocol: STREAM target delivering quotes on the control connection itself.
+pub const TCP_SCHEME_PREFIX: &str = "tcp://";
 /// Protocol: OK response token.
 pub const RESPONSE_OK: &str = "OK";
+/// Protocol: marker following `OK` when only some requested tickers were accepted.
+pub const RESPONSE_PARTIAL: &str = "PARTIAL";
 /// Protocol: ERR response prefix.
 pub const RESPONSE_ERR_PREFIX: &str = "ERR ";
 /// Placeholder used when socket addresses are unavailable.
@@ -123,29 +202,101 @@ pub fn setup_shutdown_flag() -> Result<Arc<AtomicBool>, QuoteError> {
 // Data Types
 // ============================================================================
 
-/// Representation of a stock quote transmitted over UDP.
+/// Top of the book of one ticker together with its last trade.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StockQuote {
     /// Uppercase ticker symbol.
     pub ticker: String,
-    /// Last traded price (two decimal places when formatted for output).
+    /// Last traded price, a whole number of the instrument's ticks.
     pub price: f64,
-    /// Trade volume in shares.
+    /// Shares in the last trade.
     pub volume: u32,
     /// Unix timestamp in milliseconds (UTC).
     pub timestamp: i64,
+    /// Best bid price; `0.0` when the producer does not quote a spread.
+    #[serde(default)]
+    pub bid: f64,
+    /// Best ask price; `0.0` when the producer does not quote a spread.
+    #[serde(default)]
+    pub ask: f64,
+    /// Shares available at the bid.
+    #[serde(default)]
+    pub bid_size: u32,
+    /// Shares available at the ask.
+    #[serde(default)]
+    pub ask_size: u32,
+    /// Decimal places the prices of this instrument are quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+    /// Shares traded in the ticker so far today, including the last trade.
+    #[serde(default)]
+    pub daily_volume: u64,
+    /// Trades in the ticker so far today, including the last trade.
+    #[serde(default)]
+    pub trade_count: u64,
+    /// Whether trading in the ticker is halted; a halted quote repeats the last price
+    /// without a trade or a book.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub halted: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Precision assumed for messages from producers that do not send one.
+pub(crate) fn default_price_precision() -> u8 {
+    DEFAULT_PRICE_PRECISION
+}
+
+/// Format `price` with `precision` decimal places.
+pub fn format_price(price: f64, precision: u8) -> String {
+    format!("{:.*}", usize::from(precision), price)
 }
 
 impl StockQuote {
-    /// Convenience constructor producing a quote with current UTC timestamp.
+    /// Convenience constructor producing a quote with current UTC timestamp and no spread.
     pub fn new(ticker: impl Into<String>, price: f64, volume: u32) -> Self {
         Self {
             ticker: ticker.into(),
             price,
             volume,
             timestamp: Utc::now().timestamp_millis(),
+            bid: 0.0,
+            ask: 0.0,
+            bid_size: 0,
+            ask_size: 0,
+            precision: DEFAULT_PRICE_PRECISION,
+            daily_volume: 0,
+            trade_count: 0,
+            halted: false,
         }
     }
+
+    /// Attach the top of the book to the quote.
+    pub fn with_book(mut self, bid: f64, ask: f64, bid_size: u32, ask_size: u32) -> Self {
+        self.bid = bid;
+        self.ask = ask;
+        self.bid_size = bid_size;
+        self.ask_size = ask_size;
+        self
+    }
+
+    /// The trade behind the quote's last price and volume.
+    pub fn last_trade(&self) -> Trade {
+        Trade {
+            ticker: self.ticker.clone(),
+            price: self.price,
+            volume: self.volume,
+            timestamp: self.timestamp,
+            precision: self.precision,
+        }
+    }
+
+    /// Whether the quote carries a bid/ask spread.
+    pub fn has_book(&self) -> bool {
+        self.ask > 0.0
+    }
 }
 
 /// Location information for where an error occurred.
@@ -415,18 +566,38 @@ mod tests {
             price: 150.25,
             volume: 3_500,
             timestamp: 1_699_564_800_000,
+            bid: 150.24,
+            ask: 150.27,
+            bid_size: 300,
+            ask_size: 500,
+            precision: 2,
+            daily_volume: 1_250_000,
+            trade_count: 412,
+            halted: false,
         };
 
         let json = serde_json::to_string(&quote).expect("serialize quote");
         assert_eq!(
             json,
-            r#"{"ticker":"AAPL","price":150.25,"volume":3500,"timestamp":1699564800000}"#
+            r#"{"ticker":"AAPL","price":150.25,"volume":3500,"timestamp":1699564800000,"bid":150.24,"ask":150.27,"bid_size":300,"ask_size":500,"precision":2,"daily_volume":1250000,"trade_count":412}"#
         );
 
         let restored: StockQuote = serde_json::from_str(&json).expect("deserialize quote");
         assert_eq!(restored, quote);
     }
 
+    #[test]
+    fn test_stock_quote_json_without_book_fields() {
+        let json = r#"{"ticker":"AAPL","price":150.25,"volume":3500,"timestamp":1699564800000}"#;
+        let quote: StockQuote = serde_json::from_str(json).expect("deserialize legacy quote");
+        assert_eq!(quote.price, 150.25);
+        assert!(!quote.has_book());
+        assert_eq!((quote.bid_size, quote.ask_size), (0, 0));
+        assert_eq!(quote.precision, DEFAULT_PRICE_PRECISION);
+        assert_eq!((quote.daily_volume, quote.trade_count), (0, 0));
+        assert!(!quote.halted);
+    }
+
     #[test]
     fn test_error_location_capture() {
         let io_err = io::Error::other("network failure");
@@ -522,6 +693,14 @@ mod tests {
             price: 999999.99,            // Large price value
             volume: 4294967295,          // Max u32
             timestamp: 9223372036854775807, // Max i64
+            bid: 999999.98,
+            ask: 999999.99,
+            bid_size: 4294967295,
+            ask_size: 4294967295,
+            precision: MAX_PRICE_PRECISION,
+            daily_volume: u64::MAX,
+            trade_count: u64::MAX,
+            halted: true,
         };
 
         let json = serde_json::to_vec(&max_quote).expect("serialize max quote");
@@ -530,8 +709,8 @@ mod tests {
         println!("Max JSON size: {} bytes", json.len());
         println!("Max JSON: {}", json_str);
 
-        // This test documents the maximum size - currently 86 bytes
+        // This test documents the maximum size - currently 263 bytes
         // Buffer should be at least this size, with some safety margin
-        assert!(json.len() <= 128, "JSON should not exceed 128 bytes");
+        assert!(json.len() <= 272, "JSON should not exceed 272 bytes");
     }
 }
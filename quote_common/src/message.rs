@@ -0,0 +1,799 @@
+//! Messages delivered to streaming clients: trades, quotes, aggregated OHLCV bars,
+//! order-book depth, per-ticker market events and corporate actions, market session
+//! changes, heartbeats and errors.
+//!
+//! Every message is a JSON object whose `type` field names its kind.
+//!
+//! Clients subscribe with keys: a bare ticker (`AAPL`) selects trades, quotes,
+//! events and corporate actions, a ticker with a bar interval (`AAPL@1m`) selects completed bars for that
+//! interval and `AAPL@book` selects depth snapshots and updates. Session changes,
+//! heartbeats and errors are not tied to a ticker and reach every subscriber.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{QuoteError, StockQuote, default_price_precision};
+
+/// Separates the ticker from the bar interval or depth feed in a subscription key.
+pub const BAR_KEY_SEPARATOR: char = '@';
+/// Feed name selecting order-book depth in a subscription key (`AAPL@book`).
+pub const DEPTH_FEED: &str = "book";
+
+const SECS_PER_MINUTE: u32 = 60;
+const SECS_PER_HOUR: u32 = 60 * SECS_PER_MINUTE;
+const MILLIS_PER_SEC: i64 = 1_000;
+
+/// Length of an OHLCV bar, written as `<n>s`, `<n>m` or `<n>h` (e.g. `1s`, `1m`, `5m`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BarInterval {
+    secs: u32,
+}
+
+impl BarInterval {
+    /// Interval of `secs` seconds; `None` for zero.
+    pub fn from_secs(secs: u32) -> Option<Self> {
+        (secs > 0).then_some(Self { secs })
+    }
+
+    /// Interval length in milliseconds.
+    pub fn as_millis(&self) -> i64 {
+        i64::from(self.secs) * MILLIS_PER_SEC
+    }
+
+    /// Start of the bar containing `timestamp` (Unix milliseconds).
+    pub fn bar_start(&self, timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(self.as_millis())
+    }
+}
+
+impl FromStr for BarInterval {
+    type Err = QuoteError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_ascii_lowercase();
+        let invalid = || crate::quote_error!(ParseError, "invalid bar interval: {}", value);
+
+        let (count, multiplier) = [('s', 1), ('m', SECS_PER_MINUTE), ('h', SECS_PER_HOUR)]
+            .into_iter()
+            .find_map(|(unit, multiplier)| {
+                value.strip_suffix(unit).map(|count| (count, multiplier))
+            })
+            .ok_or_else(invalid)?;
+        count
+            .parse::<u32>()
+            .ok()
+            .and_then(|count| count.checked_mul(multiplier))
+            .and_then(Self::from_secs)
+            .ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for BarInterval {
+    type Error = QuoteError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<BarInterval> for String {
+    fn from(interval: BarInterval) -> Self {
+        interval.to_string()
+    }
+}
+
+impl fmt::Display for BarInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.secs.is_multiple_of(SECS_PER_HOUR) {
+            write!(f, "{}h", self.secs / SECS_PER_HOUR)
+        } else if self.secs.is_multiple_of(SECS_PER_MINUTE) {
+            write!(f, "{}m", self.secs / SECS_PER_MINUTE)
+        } else {
+            write!(f, "{}s", self.secs)
+        }
+    }
+}
+
+/// What a subscription key selects for its ticker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feed {
+    /// Trades, quotes and events (`AAPL`).
+    Quotes,
+    /// Completed bars of one interval (`AAPL@1m`).
+    Bars(BarInterval),
+    /// Order-book snapshots and updates (`AAPL@book`).
+    Depth,
+}
+
+/// Shares of one ticker that changed hands at one price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// Execution price.
+    pub price: f64,
+    /// Shares traded.
+    pub volume: u32,
+    /// Unix timestamp in milliseconds (UTC).
+    pub timestamp: i64,
+    /// Decimal places the price is quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+/// Open/high/low/close/volume of one ticker over one completed interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhlcvBar {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// Bar length.
+    pub interval: BarInterval,
+    /// Unix timestamp in milliseconds at which the bar opens (aligned to the interval).
+    pub start: i64,
+    /// First price in the interval.
+    pub open: f64,
+    /// Highest price in the interval.
+    pub high: f64,
+    /// Lowest price in the interval.
+    pub low: f64,
+    /// Last price in the interval.
+    pub close: f64,
+    /// Total volume of the quotes in the interval.
+    pub volume: u64,
+    /// Decimal places the prices are quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+impl OhlcvBar {
+    /// Open a bar with the first trade of its interval.
+    pub fn open(trade: &Trade, interval: BarInterval) -> Self {
+        Self {
+            ticker: trade.ticker.clone(),
+            interval,
+            start: interval.bar_start(trade.timestamp),
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: u64::from(trade.volume),
+            precision: trade.precision,
+        }
+    }
+
+    /// Fold a later trade from the same interval into the bar.
+    pub fn update(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += u64::from(trade.volume);
+    }
+}
+
+/// Phase of the trading day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Extended trading before the regular open.
+    PreMarket,
+    /// Regular trading hours.
+    Regular,
+    /// Extended trading after the regular close.
+    AfterHours,
+    /// No trading: overnight, weekends and holidays.
+    Closed,
+}
+
+impl fmt::Display for SessionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SessionState::PreMarket => "pre_market",
+            SessionState::Regular => "regular",
+            SessionState::AfterHours => "after_hours",
+            SessionState::Closed => "closed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The market entered a new session state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionEvent {
+    /// State the market is in from `timestamp` on.
+    pub state: SessionState,
+    /// Unix timestamp in milliseconds of the change.
+    pub timestamp: i64,
+}
+
+/// What happened to a ticker in a [`MarketEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketEventKind {
+    /// The price jumped by a percentage.
+    Shock,
+    /// The price was set to a fixed value.
+    PriceSet,
+    /// Trading stopped; quotes are marked halted and carry no trades until it resumes.
+    Halt,
+    /// Trading resumed after a halt.
+    Resume,
+}
+
+impl fmt::Display for MarketEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MarketEventKind::Shock => "shock",
+            MarketEventKind::PriceSet => "price_set",
+            MarketEventKind::Halt => "halt",
+            MarketEventKind::Resume => "resume",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why the generator halted a ticker on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltReason {
+    /// The price reached the upper limit of its band.
+    LimitUp,
+    /// The price reached the lower limit of its band.
+    LimitDown,
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HaltReason::LimitUp => "limit_up",
+            HaltReason::LimitDown => "limit_down",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An out-of-band change to one ticker, such as an injected price shock or a halt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketEvent {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// What happened.
+    pub kind: MarketEventKind,
+    /// Price after the event.
+    pub price: f64,
+    /// Unix timestamp in milliseconds of the event.
+    pub timestamp: i64,
+    /// Unix timestamp in milliseconds at which a timed halt ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_at: Option<i64>,
+    /// Band limit that triggered a halt; `None` for halts requested by `ADMIN`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<HaltReason>,
+    /// Decimal places the price is quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+/// What a corporate action does to a ticker's shares, tagged in an `action` field.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum CorporateActionKind {
+    /// Every `old_shares` shares become `new_shares`; a 4:1 split quarters the price.
+    Split { new_shares: u32, old_shares: u32 },
+    /// `amount` is paid per share and the price drops by it on the ex-dividend date.
+    Dividend { amount: f64 },
+}
+
+impl CorporateActionKind {
+    /// Price of one share after the action, given its price before.
+    pub fn adjust(&self, price: f64) -> f64 {
+        match self {
+            CorporateActionKind::Split {
+                new_shares,
+                old_shares,
+            } => price * f64::from(*old_shares) / f64::from(*new_shares),
+            CorporateActionKind::Dividend { amount } => price - amount,
+        }
+    }
+}
+
+impl fmt::Display for CorporateActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorporateActionKind::Split {
+                new_shares,
+                old_shares,
+            } => write!(f, "{new_shares}:{old_shares} split"),
+            CorporateActionKind::Dividend { amount } => write!(f, "{amount} dividend"),
+        }
+    }
+}
+
+/// A split or dividend taking effect for one ticker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorporateAction {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// The split ratio or dividend amount.
+    #[serde(flatten)]
+    pub action: CorporateActionKind,
+    /// Price just before the action.
+    pub previous_price: f64,
+    /// Price after the action.
+    pub price: f64,
+    /// Unix timestamp in milliseconds at which the action was applied.
+    pub timestamp: i64,
+    /// Decimal places the prices are quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+/// Side of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookSide {
+    /// Resting buy orders, best (highest) price first.
+    Bid,
+    /// Resting sell orders, best (lowest) price first.
+    Ask,
+}
+
+impl fmt::Display for BookSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookSide::Bid => f.write_str("bid"),
+            BookSide::Ask => f.write_str("ask"),
+        }
+    }
+}
+
+/// Shares resting at one price.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    /// Price of the level.
+    pub price: f64,
+    /// Shares resting at `price`.
+    pub size: u32,
+}
+
+/// Every level of one ticker's order book, best price first on each side.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// Position in the ticker's book sequence; the next update carries `sequence + 1`.
+    pub sequence: u64,
+    /// Bid levels, highest price first.
+    pub bids: Vec<BookLevel>,
+    /// Ask levels, lowest price first.
+    pub asks: Vec<BookLevel>,
+    /// Unix timestamp in milliseconds (UTC).
+    pub timestamp: i64,
+    /// Decimal places the prices are quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+/// New size of one price level; a size of zero removes the level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LevelChange {
+    /// Side of the book the level is on.
+    pub side: BookSide,
+    /// Price of the level.
+    pub price: f64,
+    /// Shares now resting at `price`.
+    pub size: u32,
+}
+
+/// Changes to one ticker's order book since the snapshot or update with the
+/// previous `sequence`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookUpdate {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// Position in the ticker's book sequence.
+    pub sequence: u64,
+    /// Levels that changed, were added or were removed.
+    pub changes: Vec<LevelChange>,
+    /// Unix timestamp in milliseconds (UTC).
+    pub timestamp: i64,
+    /// Decimal places the prices are quoted with.
+    #[serde(default = "default_price_precision")]
+    pub precision: u8,
+}
+
+/// Sent on an otherwise idle stream so clients can tell a quiet market from a dead server.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// Unix timestamp in milliseconds at which the server sent the heartbeat.
+    pub timestamp: i64,
+}
+
+impl Heartbeat {
+    /// Heartbeat stamped with the current UTC time.
+    pub fn now() -> Self {
+        Self {
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// A problem with the stream itself, such as the server closing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorNotice {
+    /// Human-readable description.
+    pub message: String,
+    /// Unix timestamp in milliseconds of the error.
+    pub timestamp: i64,
+}
+
+impl ErrorNotice {
+    /// Error stamped with the current UTC time.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Anything the server streams to a client, tagged with its kind in a `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    /// A trade print.
+    Trade(Trade),
+    /// A top-of-book update with the last trade.
+    Quote(StockQuote),
+    /// A completed OHLCV bar.
+    Bar(OhlcvBar),
+    /// The full depth of a ticker's order book.
+    BookSnapshot(BookSnapshot),
+    /// Incremental changes to a ticker's order book.
+    BookUpdate(BookUpdate),
+    /// A market session change.
+    Session(SessionEvent),
+    /// A market event affecting one ticker.
+    Event(MarketEvent),
+    /// A split or dividend of one ticker.
+    CorporateAction(CorporateAction),
+    /// Proof of life on an idle stream.
+    Heartbeat(Heartbeat),
+    /// A stream-level error.
+    Error(ErrorNotice),
+}
+
+impl StreamMessage {
+    /// Subscription key that selects this message (`AAPL`, `AAPL@1m` or `AAPL@book`),
+    /// or `None` for messages delivered to every subscriber.
+    pub fn subscription_key(&self) -> Option<String> {
+        match self {
+            StreamMessage::Trade(trade) => Some(trade.ticker.clone()),
+            StreamMessage::Quote(quote) => Some(quote.ticker.clone()),
+            StreamMessage::Bar(bar) => {
+                Some(subscription_key(&bar.ticker, Feed::Bars(bar.interval)))
+            }
+            StreamMessage::BookSnapshot(snapshot) => {
+                Some(subscription_key(&snapshot.ticker, Feed::Depth))
+            }
+            StreamMessage::BookUpdate(update) => {
+                Some(subscription_key(&update.ticker, Feed::Depth))
+            }
+            StreamMessage::Event(event) => Some(event.ticker.clone()),
+            StreamMessage::CorporateAction(action) => Some(action.ticker.clone()),
+            StreamMessage::Session(_) | StreamMessage::Heartbeat(_) | StreamMessage::Error(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for StreamMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamMessage::Trade(trade) => write!(f, "trade {}", trade.ticker),
+            StreamMessage::Quote(quote) => write!(f, "quote {}", quote.ticker),
+            StreamMessage::Bar(bar) => write!(
+                f,
+                "bar {}",
+                subscription_key(&bar.ticker, Feed::Bars(bar.interval))
+            ),
+            StreamMessage::BookSnapshot(snapshot) => {
+                write!(f, "book snapshot {}", snapshot.ticker)
+            }
+            StreamMessage::BookUpdate(update) => write!(f, "book update {}", update.ticker),
+            StreamMessage::Session(event) => write!(f, "session change to {}", event.state),
+            StreamMessage::Event(event) => write!(f, "{} event for {}", event.kind, event.ticker),
+            StreamMessage::CorporateAction(action) => {
+                write!(f, "{} for {}", action.action, action.ticker)
+            }
+            StreamMessage::Heartbeat(_) => write!(f, "heartbeat"),
+            StreamMessage::Error(error) => write!(f, "error '{}'", error.message),
+        }
+    }
+}
+
+impl From<Trade> for StreamMessage {
+    fn from(trade: Trade) -> Self {
+        StreamMessage::Trade(trade)
+    }
+}
+
+impl From<StockQuote> for StreamMessage {
+    fn from(quote: StockQuote) -> Self {
+        StreamMessage::Quote(quote)
+    }
+}
+
+impl From<OhlcvBar> for StreamMessage {
+    fn from(bar: OhlcvBar) -> Self {
+        StreamMessage::Bar(bar)
+    }
+}
+
+impl From<BookSnapshot> for StreamMessage {
+    fn from(snapshot: BookSnapshot) -> Self {
+        StreamMessage::BookSnapshot(snapshot)
+    }
+}
+
+impl From<BookUpdate> for StreamMessage {
+    fn from(update: BookUpdate) -> Self {
+        StreamMessage::BookUpdate(update)
+    }
+}
+
+impl From<SessionEvent> for StreamMessage {
+    fn from(event: SessionEvent) -> Self {
+        StreamMessage::Session(event)
+    }
+}
+
+impl From<MarketEvent> for StreamMessage {
+    fn from(event: MarketEvent) -> Self {
+        StreamMessage::Event(event)
+    }
+}
+
+impl From<CorporateAction> for StreamMessage {
+    fn from(action: CorporateAction) -> Self {
+        StreamMessage::CorporateAction(action)
+    }
+}
+
+impl From<Heartbeat> for StreamMessage {
+    fn from(heartbeat: Heartbeat) -> Self {
+        StreamMessage::Heartbeat(heartbeat)
+    }
+}
+
+impl From<ErrorNotice> for StreamMessage {
+    fn from(error: ErrorNotice) -> Self {
+        StreamMessage::Error(error)
+    }
+}
+
+/// Build the subscription key selecting `feed` of a ticker.
+pub fn subscription_key(ticker: &str, feed: Feed) -> String {
+    match feed {
+        Feed::Quotes => ticker.to_string(),
+        Feed::Bars(interval) => format!("{ticker}{BAR_KEY_SEPARATOR}{interval}"),
+        Feed::Depth => format!("{ticker}{BAR_KEY_SEPARATOR}{DEPTH_FEED}"),
+    }
+}
+
+/// Split a subscription key into its uppercase ticker and the feed it selects.
+pub fn parse_subscription_key(raw: &str) -> Result<(String, Feed), QuoteError> {
+    match raw.trim().split_once(BAR_KEY_SEPARATOR) {
+        Some((ticker, feed)) if feed.trim().eq_ignore_ascii_case(DEPTH_FEED) => {
+            Ok((ticker.trim().to_uppercase(), Feed::Depth))
+        }
+        Some((ticker, interval)) => {
+            Ok((ticker.trim().to_uppercase(), Feed::Bars(interval.parse()?)))
+        }
+        None => Ok((raw.trim().to_uppercase(), Feed::Quotes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_interval_parse_and_display() {
+        for (raw, secs, canonical) in [
+            ("1s", 1, "1s"),
+            ("1M", 60, "1m"),
+            ("5m", 300, "5m"),
+            ("90s", 90, "90s"),
+            ("60m", 3_600, "1h"),
+        ] {
+            let interval: BarInterval = raw.parse().expect("valid interval");
+            assert_eq!(interval.as_millis(), secs * 1_000);
+            assert_eq!(interval.to_string(), canonical);
+        }
+        for raw in ["", "0s", "m", "5x", "-1m"] {
+            assert!(raw.parse::<BarInterval>().is_err(), "{raw} should fail");
+        }
+    }
+
+    #[test]
+    fn test_bar_start_aligns_to_interval() {
+        let minute: BarInterval = "1m".parse().unwrap();
+        assert_eq!(minute.bar_start(1_700_000_059_999), 1_700_000_040_000);
+        assert_eq!(minute.bar_start(1_700_000_040_000), 1_700_000_040_000);
+    }
+
+    #[test]
+    fn test_stream_message_json_is_tagged_by_kind() {
+        let quote = StockQuote::new("AAPL", 150.0, 1_000);
+        let mut bar = OhlcvBar::open(&quote.last_trade(), "1m".parse().unwrap());
+        bar.update(&StockQuote::new("AAPL", 151.0, 500).last_trade());
+        assert_eq!((bar.high, bar.close, bar.volume), (151.0, 151.0, 1_500));
+
+        let trade = Trade {
+            ticker: "AAPL".to_string(),
+            price: 150.0,
+            volume: 300,
+            timestamp: 1_000,
+            precision: 2,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(trade)).expect("serialize");
+        assert_eq!(
+            json,
+            r#"{"type":"trade","ticker":"AAPL","price":150.0,"volume":300,"timestamp":1000,"precision":2}"#
+        );
+
+        for (message, tag) in [
+            (
+                StreamMessage::from(bar),
+                r#"{"type":"bar","ticker":"AAPL","interval":"1m""#,
+            ),
+            (
+                StreamMessage::from(quote),
+                r#"{"type":"quote","ticker":"AAPL""#,
+            ),
+            (
+                StreamMessage::from(Heartbeat { timestamp: 5 }),
+                r#"{"type":"heartbeat","timestamp":5"#,
+            ),
+            (
+                StreamMessage::from(ErrorNotice::new("stream closed")),
+                r#"{"type":"error","message":"stream closed""#,
+            ),
+        ] {
+            let json = serde_json::to_string(&message).expect("serialize");
+            assert!(json.starts_with(tag), "{json} should start with {tag}");
+            assert_eq!(
+                serde_json::from_str::<StreamMessage>(&json).expect("deserialize"),
+                message
+            );
+        }
+        assert_eq!(
+            StreamMessage::from(Heartbeat::now()).subscription_key(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_session_event_json_is_tagged() {
+        let event = SessionEvent {
+            state: SessionState::PreMarket,
+            timestamp: 1_700_000_000_000,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(event)).expect("serialize");
+        assert_eq!(
+            json,
+            r#"{"type":"session","state":"pre_market","timestamp":1700000000000}"#
+        );
+        let decoded: StreamMessage = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(decoded, StreamMessage::Session(event));
+        assert_eq!(decoded.subscription_key(), None);
+    }
+
+    #[test]
+    fn test_market_event_json_is_tagged_and_keyed_by_ticker() {
+        let event = MarketEvent {
+            ticker: "AAPL".to_string(),
+            kind: MarketEventKind::Halt,
+            price: 150.0,
+            timestamp: 1_000,
+            resume_at: Some(31_000),
+            reason: Some(HaltReason::LimitDown),
+            precision: 2,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(event.clone())).expect("serialize");
+        assert_eq!(
+            json,
+            r#"{"type":"event","ticker":"AAPL","kind":"halt","price":150.0,"timestamp":1000,"resume_at":31000,"reason":"limit_down","precision":2}"#
+        );
+        let decoded: StreamMessage = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(decoded.subscription_key().as_deref(), Some("AAPL"));
+        assert_eq!(decoded, StreamMessage::Event(event));
+    }
+
+    #[test]
+    fn test_corporate_action_json_is_flat_and_keyed_by_ticker() {
+        let split = CorporateActionKind::Split {
+            new_shares: 4,
+            old_shares: 1,
+        };
+        assert_eq!(split.adjust(600.0), 150.0);
+        assert_eq!(
+            CorporateActionKind::Dividend { amount: 0.5 }.adjust(100.0),
+            99.5
+        );
+
+        let action = CorporateAction {
+            ticker: "AAPL".to_string(),
+            action: split,
+            previous_price: 600.0,
+            price: 150.0,
+            timestamp: 1_000,
+            precision: 2,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(action.clone())).expect("serialize");
+        assert_eq!(
+            json,
+            r#"{"type":"corporate_action","ticker":"AAPL","action":"split","new_shares":4,"old_shares":1,"previous_price":600.0,"price":150.0,"timestamp":1000,"precision":2}"#
+        );
+        let decoded: StreamMessage = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(decoded.subscription_key().as_deref(), Some("AAPL"));
+        assert_eq!(decoded, StreamMessage::CorporateAction(action));
+        assert_eq!(decoded.to_string(), "4:1 split for AAPL");
+    }
+
+    #[test]
+    fn test_book_messages_are_tagged_and_keyed_by_depth_feed() {
+        let update = BookUpdate {
+            ticker: "AAPL".to_string(),
+            sequence: 8,
+            changes: vec![LevelChange {
+                side: BookSide::Bid,
+                price: 150.0,
+                size: 0,
+            }],
+            timestamp: 1_000,
+            precision: 2,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(update.clone())).expect("serialize");
+        assert_eq!(
+            json,
+            r#"{"type":"book_update","ticker":"AAPL","sequence":8,"changes":[{"side":"bid","price":150.0,"size":0}],"timestamp":1000,"precision":2}"#
+        );
+        let decoded: StreamMessage = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(decoded.subscription_key().as_deref(), Some("AAPL@book"));
+        assert_eq!(decoded, StreamMessage::BookUpdate(update));
+
+        let snapshot = BookSnapshot {
+            ticker: "AAPL".to_string(),
+            sequence: 7,
+            bids: vec![BookLevel {
+                price: 150.0,
+                size: 300,
+            }],
+            asks: Vec::new(),
+            timestamp: 1_000,
+            precision: 2,
+        };
+        let json = serde_json::to_string(&StreamMessage::from(snapshot)).expect("serialize");
+        assert!(json.starts_with(r#"{"type":"book_snapshot","ticker":"AAPL","sequence":7"#));
+    }
+
+    #[test]
+    fn test_parse_subscription_key() {
+        assert_eq!(
+            parse_subscription_key(" aapl ").unwrap(),
+            ("AAPL".to_string(), Feed::Quotes)
+        );
+        let (ticker, feed) = parse_subscription_key("msft@5M").unwrap();
+        assert_eq!(subscription_key(&ticker, feed), "MSFT@5m");
+        let (ticker, feed) = parse_subscription_key("msft@BOOK").unwrap();
+        assert_eq!(feed, Feed::Depth);
+        assert_eq!(subscription_key(&ticker, feed), "MSFT@book");
+        assert!(parse_subscription_key("AAPL@soon").is_err());
+    }
+}
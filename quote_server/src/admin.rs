@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use quote_common::QuoteError;
+
+const SHOCK_ACTION: &str = "SHOCK";
+const SET_ACTION: &str = "SET";
+const HALT_ACTION: &str = "HALT";
+const RESUME_ACTION: &str = "RESUME";
+/// Smallest accepted shock: a fall of 100% or more would leave no price.
+const MIN_SHOCK_PERCENT: f64 = -100.0;
+/// Longest timed halt, one year; longer halts are left open until `RESUME` instead.
+const MAX_HALT_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Scenario injected into the quote generator through an `ADMIN` control command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    /// Move the price of `ticker` by `percent` (e.g. `-15.0` for a 15% drop).
+    Shock { ticker: String, percent: f64 },
+    /// Set the price of `ticker`.
+    SetPrice { ticker: String, price: f64 },
+    /// Stop quoting `ticker`, for `duration` or until resumed.
+    Halt {
+        ticker: String,
+        duration: Option<Duration>,
+    },
+    /// Resume quoting a halted `ticker`.
+    Resume { ticker: String },
+}
+
+impl AdminCommand {
+    /// Ticker the command applies to.
+    pub fn ticker(&self) -> &str {
+        match self {
+            AdminCommand::Shock { ticker, .. }
+            | AdminCommand::SetPrice { ticker, .. }
+            | AdminCommand::Halt { ticker, .. }
+            | AdminCommand::Resume { ticker } => ticker,
+        }
+    }
+}
+
+/// Parse the arguments of an `ADMIN` command:
+/// `SHOCK <ticker> <percent>[%]`, `SET <ticker> <price>`, `HALT <ticker> [seconds]`
+/// or `RESUME <ticker>`.
+pub fn parse_admin_command(args: &str) -> Result<AdminCommand, QuoteError> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let invalid =
+        |usage: &str| quote_common::quote_error!(InvalidCommand, "usage: ADMIN {}", usage);
+
+    match tokens.as_slice() {
+        [action, ticker, percent] if action.eq_ignore_ascii_case(SHOCK_ACTION) => {
+            let percent = percent
+                .strip_suffix('%')
+                .unwrap_or(percent)
+                .parse::<f64>()
+                .ok()
+                .filter(|percent| percent.is_finite() && *percent > MIN_SHOCK_PERCENT)
+                .ok_or_else(|| invalid("SHOCK <ticker> <percent above -100>"))?;
+            Ok(AdminCommand::Shock {
+                ticker: ticker.to_uppercase(),
+                percent,
+            })
+        }
+        [action, ticker, price] if action.eq_ignore_ascii_case(SET_ACTION) => {
+            let price = price
+                .parse::<f64>()
+                .ok()
+                .filter(|price| price.is_finite() && *price > 0.0)
+                .ok_or_else(|| invalid("SET <ticker> <positive price>"))?;
+            Ok(AdminCommand::SetPrice {
+                ticker: ticker.to_uppercase(),
+                price,
+            })
+        }
+        [action, ticker, rest @ ..] if action.eq_ignore_ascii_case(HALT_ACTION) => {
+            let duration = match rest {
+                [] => None,
+                [secs] => Some(Duration::from_secs(
+                    secs.parse::<u64>()
+                        .ok()
+                        .filter(|secs| (1..=MAX_HALT_SECS).contains(secs))
+                        .ok_or_else(|| invalid("HALT <ticker> [seconds up to 31536000]"))?,
+                )),
+                _ => return Err(invalid("HALT <ticker> [seconds]")),
+            };
+            Ok(AdminCommand::Halt {
+                ticker: ticker.to_uppercase(),
+                duration,
+            })
+        }
+        [action, ticker] if action.eq_ignore_ascii_case(RESUME_ACTION) => {
+            Ok(AdminCommand::Resume {
+                ticker: ticker.to_uppercase(),
+            })
+        }
+        _ => Err(invalid(
+            "SHOCK <ticker> <percent> | SET <ticker> <price> | HALT <ticker> [seconds] | RESUME <ticker>",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_admin_command_actions() {
+        assert_eq!(
+            parse_admin_command("SHOCK tsla -15%").unwrap(),
+            AdminCommand::Shock {
+                ticker: "TSLA".to_string(),
+                percent: -15.0
+            }
+        );
+        assert_eq!(
+            parse_admin_command("set AAPL 151.25").unwrap(),
+            AdminCommand::SetPrice {
+                ticker: "AAPL".to_string(),
+                price: 151.25
+            }
+        );
+        assert_eq!(
+            parse_admin_command("HALT AAPL 30").unwrap(),
+            AdminCommand::Halt {
+                ticker: "AAPL".to_string(),
+                duration: Some(Duration::from_secs(30))
+            }
+        );
+        assert_eq!(
+            parse_admin_command("HALT AAPL").unwrap(),
+            AdminCommand::Halt {
+                ticker: "AAPL".to_string(),
+                duration: None
+            }
+        );
+        assert_eq!(parse_admin_command("RESUME aapl").unwrap().ticker(), "AAPL");
+    }
+
+    #[test]
+    fn test_parse_admin_command_rejects_invalid_arguments() {
+        for args in [
+            "",
+            "SHOCK TSLA",
+            "SHOCK TSLA -100",
+            "SHOCK TSLA lots",
+            "SET AAPL 0",
+            "HALT AAPL 0",
+            "HALT AAPL 31536001",
+            "HALT AAPL 18446744073709551615",
+            "HALT AAPL 5 10",
+            "CRASH AAPL",
+        ] {
+            let err = parse_admin_command(args).expect_err(args);
+            assert!(matches!(err, QuoteError::InvalidCommand { .. }), "{args}");
+        }
+    }
+}
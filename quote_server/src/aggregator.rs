@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::thread;
+
+use crossbeam::channel::{self, Receiver, Sender};
+use log::info;
+
+use quote_common::{BarInterval, OhlcvBar, QuoteError, StreamMessage, Trade};
+
+const AGGREGATOR_THREAD_NAME: &str = "bar-aggregator";
+
+/// Builds OHLCV bars per ticker and interval from the trade stream.
+/// Bars are bucketed by trade timestamp, so a bar completes when the first trade
+/// of a later interval arrives for its ticker.
+pub struct BarAggregator {
+    intervals: Vec<BarInterval>,
+    open_bars: HashMap<(String, BarInterval), OhlcvBar>,
+}
+
+impl BarAggregator {
+    /// Create an aggregator building bars for each of `intervals`.
+    pub fn new(intervals: Vec<BarInterval>) -> Self {
+        Self {
+            intervals,
+            open_bars: HashMap::new(),
+        }
+    }
+
+    /// Fold `trade` into the open bars of its ticker, returning the bars it completes.
+    pub fn on_trade(&mut self, trade: &Trade) -> Vec<OhlcvBar> {
+        let mut completed = Vec::new();
+        for &interval in &self.intervals {
+            let start = interval.bar_start(trade.timestamp);
+            match self.open_bars.get_mut(&(trade.ticker.clone(), interval)) {
+                Some(bar) if bar.start == start => bar.update(trade),
+                // Trades from an earlier interval than the open bar are too late to count.
+                Some(bar) if bar.start > start => {}
+                Some(bar) => {
+                    completed.push(std::mem::replace(bar, OhlcvBar::open(trade, interval)))
+                }
+                None => {
+                    self.open_bars.insert(
+                        (trade.ticker.clone(), interval),
+                        OhlcvBar::open(trade, interval),
+                    );
+                }
+            }
+        }
+        completed
+    }
+
+    fn run(mut self, input_rx: Receiver<StreamMessage>, message_tx: Sender<StreamMessage>) {
+        for input in input_rx.iter() {
+            let completed = match &input {
+                StreamMessage::Trade(trade) => self.on_trade(trade),
+                _ => Vec::new(),
+            };
+            // Completed bars cover earlier time than the trade that closed them.
+            let messages = completed
+                .into_iter()
+                .map(StreamMessage::Bar)
+                .chain(std::iter::once(input));
+            for message in messages {
+                if message_tx.send(message).is_err() {
+                    info!("Bar aggregator shutting down (no active receivers)");
+                    return;
+                }
+            }
+        }
+        info!("Bar aggregator shutting down (quote source finished)");
+    }
+}
+
+/// Spawn the aggregation stage: every generator message is forwarded and completed bars
+/// are interleaved as separate messages. With no intervals, messages pass through unchanged.
+pub fn start_aggregator(
+    input_rx: Receiver<StreamMessage>,
+    intervals: Vec<BarInterval>,
+) -> Result<(Receiver<StreamMessage>, thread::JoinHandle<()>), QuoteError> {
+    let aggregator = BarAggregator::new(intervals);
+    let (message_tx, message_rx) = channel::unbounded();
+    let handle = thread::Builder::new()
+        .name(AGGREGATOR_THREAD_NAME.to_string())
+        .spawn(move || aggregator.run(input_rx, message_tx))
+        .map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to spawn bar aggregator thread")
+        })?;
+
+    Ok((message_rx, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use quote_common::StockQuote;
+
+    fn quote_at(ticker: &str, price: f64, volume: u32, timestamp: i64) -> StockQuote {
+        StockQuote {
+            timestamp,
+            ..StockQuote::new(ticker, price, volume)
+        }
+    }
+
+    fn trade_at(ticker: &str, price: f64, volume: u32, timestamp: i64) -> Trade {
+        quote_at(ticker, price, volume, timestamp).last_trade()
+    }
+
+    #[test]
+    fn test_aggregator_completes_bars_on_interval_boundary() {
+        let second: BarInterval = "1s".parse().unwrap();
+        let minute: BarInterval = "1m".parse().unwrap();
+        let mut aggregator = BarAggregator::new(vec![second, minute]);
+
+        assert!(
+            aggregator
+                .on_trade(&trade_at("AAPL", 100.0, 10, 60_000))
+                .is_empty()
+        );
+        assert!(
+            aggregator
+                .on_trade(&trade_at("AAPL", 103.0, 20, 60_400))
+                .is_empty()
+        );
+        assert!(
+            aggregator
+                .on_trade(&trade_at("AAPL", 99.0, 30, 60_900))
+                .is_empty()
+        );
+        assert!(
+            aggregator
+                .on_trade(&trade_at("MSFT", 50.0, 5, 61_000))
+                .is_empty()
+        );
+
+        let completed = aggregator.on_trade(&trade_at("AAPL", 101.0, 40, 61_200));
+        assert_eq!(
+            completed,
+            vec![OhlcvBar {
+                ticker: "AAPL".to_string(),
+                interval: second,
+                start: 60_000,
+                open: 100.0,
+                high: 103.0,
+                low: 99.0,
+                close: 99.0,
+                volume: 60,
+                precision: 2,
+            }]
+        );
+
+        let completed = aggregator.on_trade(&trade_at("AAPL", 102.0, 1, 120_000));
+        let minute_bar = completed
+            .iter()
+            .find(|bar| bar.interval == minute)
+            .expect("minute bar completed");
+        assert_eq!(minute_bar.start, 60_000);
+        assert_eq!((minute_bar.open, minute_bar.close), (100.0, 101.0));
+        assert_eq!(minute_bar.volume, 100);
+    }
+
+    #[test]
+    fn test_start_aggregator_forwards_messages_and_bars_from_trades() {
+        let (input_tx, input_rx) = channel::unbounded();
+        let (message_rx, handle) =
+            start_aggregator(input_rx, vec!["1s".parse().unwrap()]).expect("start aggregator");
+
+        input_tx
+            .send(trade_at("AAPL", 100.0, 10, 1_000).into())
+            .unwrap();
+        // Quotes never open or close a bar.
+        input_tx
+            .send(quote_at("AAPL", 100.0, 10, 2_000).into())
+            .unwrap();
+        input_tx
+            .send(trade_at("AAPL", 101.0, 10, 2_000).into())
+            .unwrap();
+        drop(input_tx);
+
+        let kinds: Vec<String> = message_rx
+            .iter()
+            .map(|message| message.to_string())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec!["trade AAPL", "quote AAPL", "bar AAPL@1s", "trade AAPL"]
+        );
+        handle.join().expect("aggregator thread should exit");
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use quote_common::SessionState;
+
+/// Activity scale during regular hours; extended hours use the configured fraction of it.
+const REGULAR_ACTIVITY: f64 = 1.0;
+/// How far ahead to look for the next trading day before giving up.
+const MAX_CALENDAR_SEARCH_DAYS: usize = 366;
+
+/// Trading sessions of an exchange in its local time zone.
+/// Weekdays trade unless listed as holidays; weekends are always closed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketCalendar {
+    /// Time zone the session times are written in.
+    pub timezone: Tz,
+    /// Start of pre-market trading; equal to `regular_open` when there is none.
+    pub pre_market_open: NaiveTime,
+    /// Start of regular trading hours.
+    pub regular_open: NaiveTime,
+    /// End of regular trading hours.
+    pub regular_close: NaiveTime,
+    /// End of after-hours trading; equal to `regular_close` when there is none.
+    pub after_hours_close: NaiveTime,
+    /// Weekdays on which the market stays closed.
+    pub holidays: HashSet<NaiveDate>,
+    /// Fraction of regular volume and volatility traded in pre-market and after hours.
+    pub extended_hours_activity: f64,
+}
+
+impl MarketCalendar {
+    /// Session state at `timestamp` (Unix milliseconds).
+    pub fn state_at(&self, timestamp: i64) -> SessionState {
+        let Some(local) = self.local_date_time(timestamp) else {
+            return SessionState::Closed;
+        };
+        if !self.is_trading_day(local.date_naive()) {
+            return SessionState::Closed;
+        }
+
+        let time = local.time();
+        if time < self.pre_market_open {
+            SessionState::Closed
+        } else if time < self.regular_open {
+            SessionState::PreMarket
+        } else if time < self.regular_close {
+            SessionState::Regular
+        } else if time < self.after_hours_close {
+            SessionState::AfterHours
+        } else {
+            SessionState::Closed
+        }
+    }
+
+    /// First instant after `timestamp` at which trading starts, or `None` if no
+    /// trading day follows within a year.
+    pub fn next_open_after(&self, timestamp: i64) -> Option<i64> {
+        let utc = Utc.timestamp_millis_opt(timestamp).single()?;
+        let today = utc.with_timezone(&self.timezone).date_naive();
+        today
+            .iter_days()
+            .take(MAX_CALENDAR_SEARCH_DAYS)
+            .filter(|date| self.is_trading_day(*date))
+            .filter_map(|date| {
+                self.timezone
+                    .from_local_datetime(&date.and_time(self.pre_market_open))
+                    .earliest()
+            })
+            .map(|open| open.timestamp_millis())
+            .find(|&open| open > timestamp)
+    }
+
+    /// How far through the regular session `timestamp` lies, from 0 at the open to 1
+    /// at the close; `None` outside regular hours.
+    pub fn regular_progress(&self, timestamp: i64) -> Option<f64> {
+        if self.state_at(timestamp) != SessionState::Regular {
+            return None;
+        }
+        let local = self.local_date_time(timestamp)?.time();
+        let elapsed_ms = (local - self.regular_open).num_milliseconds();
+        Some(elapsed_ms as f64 / self.regular_session_ms() as f64)
+    }
+
+    /// Length of the regular session in milliseconds.
+    pub fn regular_session_ms(&self) -> i64 {
+        (self.regular_close - self.regular_open).num_milliseconds()
+    }
+
+    /// Exchange-local date of `timestamp`.
+    pub fn local_date(&self, timestamp: i64) -> Option<NaiveDate> {
+        Some(self.local_date_time(timestamp)?.date_naive())
+    }
+
+    /// Scale applied to volume and volatility in `state`.
+    pub fn activity(&self, state: SessionState) -> f64 {
+        match state {
+            SessionState::PreMarket | SessionState::AfterHours => self.extended_hours_activity,
+            SessionState::Regular | SessionState::Closed => REGULAR_ACTIVITY,
+        }
+    }
+
+    fn local_date_time(&self, timestamp: i64) -> Option<DateTime<Tz>> {
+        let utc = Utc.timestamp_millis_opt(timestamp).single()?;
+        Some(utc.with_timezone(&self.timezone))
+    }
+
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nyse() -> MarketCalendar {
+        MarketCalendar {
+            timezone: chrono_tz::America::New_York,
+            pre_market_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            regular_open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            regular_close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            after_hours_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            holidays: HashSet::from([NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()]),
+            extended_hours_activity: 0.25,
+        }
+    }
+
+    fn new_york_ms(date: (i32, u32, u32), time: (u32, u32)) -> i64 {
+        chrono_tz::America::New_York
+            .with_ymd_and_hms(date.0, date.1, date.2, time.0, time.1, 0)
+            .single()
+            .expect("unambiguous local time")
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn test_state_at_follows_sessions_in_local_time() {
+        let calendar = nyse();
+        // Wednesday 2024-07-03, during daylight saving time.
+        for (time, state) in [
+            ((3, 59), SessionState::Closed),
+            ((4, 0), SessionState::PreMarket),
+            ((9, 30), SessionState::Regular),
+            ((15, 59), SessionState::Regular),
+            ((16, 0), SessionState::AfterHours),
+            ((20, 0), SessionState::Closed),
+        ] {
+            assert_eq!(
+                calendar.state_at(new_york_ms((2024, 7, 3), time)),
+                state,
+                "{time:?}"
+            );
+        }
+        // Winter time shifts the UTC offset but not the local schedule.
+        assert_eq!(
+            calendar.state_at(new_york_ms((2024, 1, 3), (9, 30))),
+            SessionState::Regular
+        );
+    }
+
+    #[test]
+    fn test_regular_progress_runs_from_open_to_close() {
+        let calendar = nyse();
+        assert_eq!(calendar.regular_session_ms(), 390 * 60 * 1_000);
+        for (time, progress) in [
+            ((9, 0), None),
+            ((9, 30), Some(0.0)),
+            ((12, 45), Some(0.5)),
+            ((16, 0), None),
+        ] {
+            assert_eq!(
+                calendar.regular_progress(new_york_ms((2024, 7, 3), time)),
+                progress,
+                "{time:?}"
+            );
+        }
+        // Late evening in New York is already the next day in UTC.
+        assert_eq!(
+            calendar.local_date(new_york_ms((2024, 7, 3), (21, 0))),
+            NaiveDate::from_ymd_opt(2024, 7, 3)
+        );
+    }
+
+    #[test]
+    fn test_holidays_and_weekends_are_closed() {
+        let calendar = nyse();
+        assert_eq!(
+            calendar.state_at(new_york_ms((2024, 7, 4), (12, 0))),
+            SessionState::Closed
+        );
+        assert_eq!(
+            calendar.state_at(new_york_ms((2024, 7, 6), (12, 0))),
+            SessionState::Closed
+        );
+
+        // From the Wednesday evening close, the next open skips the holiday.
+        assert_eq!(
+            calendar.next_open_after(new_york_ms((2024, 7, 3), (20, 0))),
+            Some(new_york_ms((2024, 7, 5), (4, 0)))
+        );
+        // Friday evening reopens on Monday.
+        assert_eq!(
+            calendar.next_open_after(new_york_ms((2024, 7, 5), (21, 0))),
+            Some(new_york_ms((2024, 7, 8), (4, 0)))
+        );
+    }
+}
@@ -1,9 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::net::SocketAddr;
-use std::path::Path;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use quote_common::{DEFAULT_KEEPALIVE_TIMEOUT_SECS, DEFAULT_QUOTE_RATE_MS, QuoteError};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use quote_common::{
+    BUFFER_SIZE, BarInterval, CorporateActionKind, DEFAULT_AFTER_HOURS_CLOSE, DEFAULT_ANNUAL_DRIFT,
+    DEFAULT_ANNUAL_VOLATILITY, DEFAULT_AVERAGE_DAILY_VOLUME, DEFAULT_BAND_REFERENCE_SECS,
+    DEFAULT_BAR_INTERVALS, DEFAULT_BOOK_LEVELS, DEFAULT_BOOK_SNAPSHOT_INTERVAL_MS,
+    DEFAULT_COMMAND_TIMEOUT_SECS, DEFAULT_EXTENDED_HOURS_ACTIVITY, DEFAULT_KEEPALIVE_TIMEOUT_SECS,
+    DEFAULT_LIMIT_HALT_SECS, DEFAULT_MARKET_TIMEZONE, DEFAULT_MAX_CONNECTIONS,
+    DEFAULT_MULTICAST_TTL, DEFAULT_OPEN_CLOSE_VOLUME_RATIO, DEFAULT_PRE_MARKET_OPEN,
+    DEFAULT_PRICE_BAND_PERCENT, DEFAULT_PRICE_PRECISION, DEFAULT_QUOTE_RATE_MS,
+    DEFAULT_REGULAR_CLOSE, DEFAULT_REGULAR_OPEN, DEFAULT_REPLAY_SPEED, DEFAULT_TICK_SIZE,
+    DEFAULT_UDP_FLUSH_INTERVAL_MS, DEFAULT_UDP_MAX_PAYLOAD, DEFAULT_VOLUME_SPIKE_SENSITIVITY,
+    MAX_BOOK_LEVELS, MAX_PRICE_PRECISION, MAX_UDP_FLUSH_INTERVAL_MS, MIN_UDP_MAX_PAYLOAD,
+    QuoteEncoding, QuoteError, parse_subscription_key, subscription_key,
+};
+
+use crate::calendar::MarketCalendar;
+use crate::replay::ReplayFormat;
+
+/// Format of session times in `[market_calendar]`.
+const SESSION_TIME_FORMAT: &str = "%H:%M";
+/// Format of holiday dates in `[market_calendar]`.
+const HOLIDAY_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Fraction of a tick within which a price counts as lying on the tick grid.
+const TICK_TOLERANCE: f64 = 1e-6;
 
 /// Server configuration loaded from TOML file.
 #[derive(Debug, Clone)]
@@ -12,12 +37,324 @@ pub struct ServerConfig {
     pub tcp_addr: SocketAddr,
     /// Path to file containing ticker symbols (one per line).
     pub tickers_file: String,
-    /// Quote generation interval in milliseconds.
-    pub quote_rate_ms: u64,
+    /// Quote update intervals, with per-ticker overrides.
+    pub quote_rates: QuoteRates,
     /// Keepalive timeout in seconds before disconnecting inactive clients.
     pub keepalive_timeout_secs: u64,
     /// Initial prices for tickers, keyed by uppercase ticker symbol.
     pub initial_prices: HashMap<String, f64>,
+    /// Maximum number of concurrent TCP control sessions.
+    pub max_connections: usize,
+    /// Seconds a TCP client has to send a complete command line.
+    pub command_timeout_secs: u64,
+    /// Maximum size in bytes of a batched UDP datagram.
+    pub udp_max_payload: usize,
+    /// Milliseconds a partially filled UDP datagram may wait for more quotes.
+    pub udp_flush_interval_ms: u64,
+    /// Multicast groups published regardless of TCP subscriptions.
+    pub multicast: MulticastConfig,
+    /// Drift and volatility of the simulated prices.
+    pub price_model: PriceModel,
+    /// Average daily volume and its intraday shape.
+    pub volume_model: VolumeModel,
+    /// Limit-up/limit-down bands; `None` lets prices move freely.
+    pub price_bands: Option<PriceBands>,
+    /// Tick sizes and display precision of the quoted prices.
+    pub instruments: Instruments,
+    /// Random seed; when set, prices and volumes repeat from run to run.
+    pub seed: Option<u64>,
+    /// Unix milliseconds of the first quote; when set, timestamps follow the quote
+    /// schedule instead of the wall clock.
+    pub simulated_clock_start_ms: Option<i64>,
+    /// OHLCV bar intervals built from the quote stream, shortest first.
+    pub bar_intervals: Vec<BarInterval>,
+    /// Depth of the simulated order books.
+    pub order_book: OrderBookConfig,
+    /// Trading sessions; `None` generates quotes around the clock.
+    pub market_calendar: Option<MarketCalendar>,
+    /// Whether TCP clients may send `ADMIN` commands that move prices and halt tickers.
+    pub admin_commands: bool,
+    /// Splits and dividends applied by the generator, in effective-time order.
+    pub corporate_actions: Vec<CorporateActionConfig>,
+    /// Recorded ticks to publish instead of generated quotes.
+    pub replay: Option<ReplayConfig>,
+}
+
+/// Historical tick file replayed in place of the quote generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayConfig {
+    /// Path to the CSV or JSON Lines file of ticks.
+    pub file: PathBuf,
+    /// Layout of `file`, taken from its extension.
+    pub format: ReplayFormat,
+    /// Playback speed; 2.0 replays an hour of ticks in 30 minutes.
+    pub speed: f64,
+    /// Whether to start over once the last tick has been sent.
+    pub looping: bool,
+}
+
+/// A split or dividend scheduled for one ticker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorporateActionConfig {
+    /// Uppercase ticker symbol.
+    pub ticker: String,
+    /// The split ratio or dividend amount.
+    pub action: CorporateActionKind,
+    /// Unix milliseconds from which the action applies.
+    pub effective_ms: i64,
+}
+
+/// Simulated order-book depth published to `TICKER@book` subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderBookConfig {
+    /// Price levels per side; zero disables order books.
+    pub levels: usize,
+    /// Milliseconds of quote time between full snapshots of a ticker's book.
+    pub snapshot_interval_ms: u64,
+}
+
+impl Default for OrderBookConfig {
+    fn default() -> Self {
+        Self {
+            levels: DEFAULT_BOOK_LEVELS,
+            snapshot_interval_ms: DEFAULT_BOOK_SNAPSHOT_INTERVAL_MS,
+        }
+    }
+}
+
+impl OrderBookConfig {
+    /// Whether order books are simulated and can be subscribed to.
+    pub fn enabled(&self) -> bool {
+        self.levels > 0
+    }
+}
+
+/// Milliseconds between quotes, with per-ticker overrides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteRates {
+    /// Interval for tickers without an override.
+    pub default_ms: u64,
+    /// Overrides keyed by uppercase ticker symbol.
+    pub tickers: HashMap<String, u64>,
+}
+
+impl Default for QuoteRates {
+    fn default() -> Self {
+        Self {
+            default_ms: DEFAULT_QUOTE_RATE_MS,
+            tickers: HashMap::new(),
+        }
+    }
+}
+
+impl QuoteRates {
+    /// Interval between quotes of `ticker`.
+    pub fn interval(&self, ticker: &str) -> Duration {
+        Duration::from_millis(self.tickers.get(ticker).copied().unwrap_or(self.default_ms))
+    }
+}
+
+/// Annualized parameters of a geometric Brownian motion price process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceParams {
+    /// Expected annual log return (0.05 = 5% per year).
+    pub drift: f64,
+    /// Annual standard deviation of log returns (0.25 = 25% per year).
+    pub volatility: f64,
+}
+
+impl Default for PriceParams {
+    fn default() -> Self {
+        Self {
+            drift: DEFAULT_ANNUAL_DRIFT,
+            volatility: DEFAULT_ANNUAL_VOLATILITY,
+        }
+    }
+}
+
+/// Price process parameters with optional per-ticker overrides and the factor
+/// structure that correlates price moves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceModel {
+    /// Parameters for tickers without an override.
+    pub default: PriceParams,
+    /// Overrides keyed by uppercase ticker symbol.
+    pub tickers: HashMap<String, PriceParams>,
+    /// Correlation of returns between any two tickers through the shared market factor.
+    pub market_correlation: f64,
+    /// Sectors whose tickers share an additional factor.
+    pub sectors: Vec<Sector>,
+}
+
+/// Tickers that move together beyond the market factor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sector {
+    /// Name from the configuration, used in logs and errors.
+    pub name: String,
+    /// Correlation added between tickers of the sector on top of `market_correlation`.
+    pub correlation: f64,
+    /// Member tickers (uppercase).
+    pub tickers: Vec<String>,
+}
+
+impl PriceModel {
+    /// Parameters used for `ticker`.
+    pub fn params(&self, ticker: &str) -> PriceParams {
+        self.tickers.get(ticker).copied().unwrap_or(self.default)
+    }
+
+    /// Index into `sectors` of the sector `ticker` belongs to.
+    pub fn sector_index(&self, ticker: &str) -> Option<usize> {
+        self.sectors
+            .iter()
+            .position(|sector| sector.tickers.iter().any(|member| member == ticker))
+    }
+}
+
+/// Trading volume of generated quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeModel {
+    /// Shares traded per day by tickers without an override.
+    pub average_daily_volume: u64,
+    /// Average daily volume keyed by uppercase ticker symbol.
+    pub tickers: HashMap<String, u64>,
+    /// Volume rate at the regular open and close relative to midday; 1 is flat.
+    pub open_close_ratio: f64,
+    /// Extra volume per standard deviation of a trade's price move; 0 disables spikes.
+    pub spike_sensitivity: f64,
+}
+
+impl Default for VolumeModel {
+    fn default() -> Self {
+        Self {
+            average_daily_volume: DEFAULT_AVERAGE_DAILY_VOLUME,
+            tickers: HashMap::new(),
+            open_close_ratio: DEFAULT_OPEN_CLOSE_VOLUME_RATIO,
+            spike_sensitivity: DEFAULT_VOLUME_SPIKE_SENSITIVITY,
+        }
+    }
+}
+
+impl VolumeModel {
+    /// Average daily volume of `ticker`.
+    pub fn daily_volume(&self, ticker: &str) -> u64 {
+        self.tickers
+            .get(ticker)
+            .copied()
+            .unwrap_or(self.average_daily_volume)
+    }
+}
+
+/// Limit-up/limit-down bands around a reference price; a price moving beyond a limit
+/// halts the ticker there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceBands {
+    /// Distance of both limits from the reference price, in percent.
+    pub percent: f64,
+    /// Band widths in percent keyed by uppercase ticker symbol.
+    pub tickers: HashMap<String, f64>,
+    /// Time after which the reference price is reset to the last price.
+    pub reference_interval: Duration,
+    /// How long a ticker stays halted after reaching a limit.
+    pub halt_duration: Duration,
+}
+
+impl Default for PriceBands {
+    fn default() -> Self {
+        Self {
+            percent: DEFAULT_PRICE_BAND_PERCENT,
+            tickers: HashMap::new(),
+            reference_interval: Duration::from_secs(DEFAULT_BAND_REFERENCE_SECS),
+            halt_duration: Duration::from_secs(DEFAULT_LIMIT_HALT_SECS),
+        }
+    }
+}
+
+impl PriceBands {
+    /// Band width of `ticker` in percent.
+    pub fn percent(&self, ticker: &str) -> f64 {
+        self.tickers.get(ticker).copied().unwrap_or(self.percent)
+    }
+}
+
+/// Minimum price increment and quoted decimal places of an instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstrumentSpec {
+    /// Smallest price change; quoted prices are whole multiples of it.
+    pub tick_size: f64,
+    /// Decimal places prices are quoted with; at least those of `tick_size`.
+    pub precision: u8,
+}
+
+impl Default for InstrumentSpec {
+    fn default() -> Self {
+        Self {
+            tick_size: DEFAULT_TICK_SIZE,
+            precision: DEFAULT_PRICE_PRECISION,
+        }
+    }
+}
+
+impl InstrumentSpec {
+    /// Nearest tick to `price`, never below one tick.
+    pub fn round(&self, price: f64) -> f64 {
+        self.on_grid((price / self.tick_size).round())
+            .max(self.tick_size)
+    }
+
+    /// Highest tick at or below `price`.
+    pub fn floor(&self, price: f64) -> f64 {
+        self.on_grid((price / self.tick_size + TICK_TOLERANCE).floor())
+    }
+
+    /// Lowest tick at or above `price`.
+    pub fn ceil(&self, price: f64) -> f64 {
+        self.on_grid((price / self.tick_size - TICK_TOLERANCE).ceil())
+    }
+
+    /// Price of `ticks` whole ticks, rounded to `precision` to drop binary noise.
+    fn on_grid(&self, ticks: f64) -> f64 {
+        let scale = 10f64.powi(i32::from(self.precision));
+        (ticks * self.tick_size * scale).round() / scale
+    }
+}
+
+/// Instrument specifications with per-ticker overrides.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Instruments {
+    /// Specification of tickers without an override.
+    pub default: InstrumentSpec,
+    /// Overrides keyed by uppercase ticker symbol.
+    pub tickers: HashMap<String, InstrumentSpec>,
+}
+
+impl Instruments {
+    /// Specification used for `ticker`.
+    pub fn spec(&self, ticker: &str) -> InstrumentSpec {
+        self.tickers.get(ticker).copied().unwrap_or(self.default)
+    }
+}
+
+/// Multicast publishing settings; no groups means multicast is disabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MulticastConfig {
+    /// Local interface address multicast datagrams are sent from.
+    pub interface: Ipv4Addr,
+    /// Time-to-live of multicast datagrams.
+    pub ttl: u32,
+    /// Groups to publish, each with its own ticker set.
+    pub groups: Vec<MulticastGroup>,
+}
+
+/// One multicast group and the tickers published to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MulticastGroup {
+    /// Multicast group address and port.
+    pub group: SocketAddrV4,
+    /// Tickers published to the group (uppercase).
+    pub tickers: Vec<String>,
+    /// Encoding of quotes sent to the group.
+    pub encoding: QuoteEncoding,
 }
 
 /// Load server configuration from a TOML file.
@@ -72,11 +409,7 @@ pub fn load_config(path: &Path) -> Result<ServerConfig, QuoteError> {
         })?
         .to_string();
 
-    let quote_rate_ms = parsed
-        .get("quote_rate_ms")
-        .and_then(|v| v.as_integer())
-        .map(|i| i as u64)
-        .unwrap_or(DEFAULT_QUOTE_RATE_MS);
+    let quote_rates = parse_quote_rates(&parsed, path)?;
 
     let keepalive_timeout_secs = parsed
         .get("keepalive_timeout_secs")
@@ -84,6 +417,100 @@ pub fn load_config(path: &Path) -> Result<ServerConfig, QuoteError> {
         .map(|i| i as u64)
         .unwrap_or(DEFAULT_KEEPALIVE_TIMEOUT_SECS);
 
+    let max_connections = parsed
+        .get("max_connections")
+        .and_then(|v| v.as_integer())
+        .map(|i| usize::try_from(i).unwrap_or(0))
+        .unwrap_or(DEFAULT_MAX_CONNECTIONS);
+    if max_connections == 0 {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'max_connections' in '{}' must be greater than zero",
+            path.display()
+        ));
+    }
+
+    let command_timeout_secs = parsed
+        .get("command_timeout_secs")
+        .and_then(|v| v.as_integer())
+        .map(|i| u64::try_from(i).unwrap_or(0))
+        .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS);
+    if command_timeout_secs == 0 {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'command_timeout_secs' in '{}' must be greater than zero",
+            path.display()
+        ));
+    }
+
+    let udp_max_payload = parsed
+        .get("udp_max_payload")
+        .and_then(|v| v.as_integer())
+        .map(|i| i as usize)
+        .unwrap_or(DEFAULT_UDP_MAX_PAYLOAD);
+    if !(MIN_UDP_MAX_PAYLOAD..=BUFFER_SIZE).contains(&udp_max_payload) {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'udp_max_payload' in '{}' must be between {} and {} bytes",
+            path.display(),
+            MIN_UDP_MAX_PAYLOAD,
+            BUFFER_SIZE
+        ));
+    }
+
+    let udp_flush_interval_ms = parsed
+        .get("udp_flush_interval_ms")
+        .and_then(|v| v.as_integer())
+        .map(|i| u64::try_from(i).unwrap_or(u64::MAX))
+        .unwrap_or(DEFAULT_UDP_FLUSH_INTERVAL_MS);
+    if udp_flush_interval_ms > MAX_UDP_FLUSH_INTERVAL_MS {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'udp_flush_interval_ms' in '{}' must be between 0 and {} milliseconds",
+            path.display(),
+            MAX_UDP_FLUSH_INTERVAL_MS
+        ));
+    }
+
+    let multicast = parse_multicast_config(&parsed, path)?;
+    let price_model = parse_price_model(&parsed, path)?;
+    let volume_model = parse_volume_model(&parsed, path)?;
+    let price_bands = parse_price_bands(&parsed, path)?;
+    let instruments = parse_instruments(&parsed, path)?;
+    let bar_intervals = parse_bar_intervals(&parsed, path)?;
+    let order_book = parse_order_book(&parsed, path)?;
+    let market_calendar = parse_market_calendar(&parsed, path)?;
+    let replay = parse_replay(&parsed, path)?;
+    let corporate_actions = parse_corporate_actions(&parsed, path)?;
+
+    let admin_commands = parsed
+        .get("admin_commands")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if admin_commands && replay.is_some() {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'admin_commands' in '{}' cannot be combined with [replay]",
+            path.display()
+        ));
+    }
+
+    let seed = parsed
+        .get("seed")
+        .and_then(|v| v.as_integer())
+        .map(|i| i as u64);
+
+    let simulated_clock_start_ms = parsed
+        .get("simulated_clock_start_ms")
+        .and_then(|v| v.as_integer());
+    if simulated_clock_start_ms.is_some_and(|start| start < 0) {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'simulated_clock_start_ms' in '{}' must not be negative",
+            path.display()
+        ));
+    }
+
     let initial_prices = parsed
         .get("initial_prices")
         .and_then(|v| v.as_table())
@@ -98,12 +525,854 @@ pub fn load_config(path: &Path) -> Result<ServerConfig, QuoteError> {
         })
         .unwrap_or_default();
 
-    Ok(ServerConfig {
-        tcp_addr,
-        tickers_file,
-        quote_rate_ms,
-        keepalive_timeout_secs,
-        initial_prices,
+    Ok(ServerConfig {
+        tcp_addr,
+        tickers_file,
+        quote_rates,
+        keepalive_timeout_secs,
+        initial_prices,
+        max_connections,
+        command_timeout_secs,
+        udp_max_payload,
+        udp_flush_interval_ms,
+        multicast,
+        price_model,
+        volume_model,
+        price_bands,
+        instruments,
+        seed,
+        simulated_clock_start_ms,
+        bar_intervals,
+        order_book,
+        market_calendar,
+        admin_commands,
+        corporate_actions,
+        replay,
+    })
+}
+
+/// Parse `quote_rate_ms` and the `[quote_rates]` table of per-ticker intervals.
+/// Every interval must be at least one millisecond.
+fn parse_quote_rates(parsed: &toml::Table, path: &Path) -> Result<QuoteRates, QuoteError> {
+    let positive = |value: &toml::Value, key: &str| {
+        value
+            .as_integer()
+            .filter(|ms| *ms > 0)
+            .map(|ms| ms as u64)
+            .ok_or_else(|| {
+                quote_common::quote_error!(
+                    ConfigError,
+                    "'{}' in '{}' must be a positive number of milliseconds",
+                    key,
+                    path.display()
+                )
+            })
+    };
+
+    let default_ms = match parsed.get("quote_rate_ms") {
+        Some(value) => positive(value, "quote_rate_ms")?,
+        None => DEFAULT_QUOTE_RATE_MS,
+    };
+
+    let mut tickers = HashMap::new();
+    if let Some(table) = parsed.get("quote_rates").and_then(|v| v.as_table()) {
+        for (ticker, value) in table {
+            let interval_ms = positive(value, &format!("quote_rates.{ticker}"))?;
+            tickers.insert(ticker.to_uppercase(), interval_ms);
+        }
+    }
+
+    Ok(QuoteRates {
+        default_ms,
+        tickers,
+    })
+}
+
+/// Parse the optional `[replay]` table. `file` is required; relative paths are kept as
+/// written, like `tickers_file`.
+fn parse_replay(parsed: &toml::Table, path: &Path) -> Result<Option<ReplayConfig>, QuoteError> {
+    let Some(table) = parsed.get("replay") else {
+        return Ok(None);
+    };
+    let invalid = |field: &str, expected: &str| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'replay.{}' in '{}' must be {}",
+            field,
+            path.display(),
+            expected
+        )
+    };
+
+    let file = table
+        .get("file")
+        .and_then(|v| v.as_str())
+        .map(PathBuf::from)
+        .ok_or_else(|| invalid("file", "the path of a .csv or .jsonl file"))?;
+    let format = ReplayFormat::from_path(&file)
+        .ok_or_else(|| invalid("file", "the path of a .csv or .jsonl file"))?;
+
+    let speed = match table.get("speed") {
+        Some(value) => value
+            .as_float()
+            .or_else(|| value.as_integer().map(|i| i as f64))
+            .filter(|speed| speed.is_finite() && *speed > 0.0)
+            .ok_or_else(|| invalid("speed", "a positive number"))?,
+        None => DEFAULT_REPLAY_SPEED,
+    };
+
+    let looping = match table.get("loop") {
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| invalid("loop", "true or false"))?,
+        None => false,
+    };
+
+    Ok(Some(ReplayConfig {
+        file,
+        format,
+        speed,
+        looping,
+    }))
+}
+
+/// Parse the optional `[order_book]` table; omitted fields keep their defaults.
+fn parse_order_book(parsed: &toml::Table, path: &Path) -> Result<OrderBookConfig, QuoteError> {
+    let Some(table) = parsed.get("order_book") else {
+        return Ok(OrderBookConfig::default());
+    };
+    let invalid = |field: &str, expected: String| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'order_book.{}' in '{}' must be {}",
+            field,
+            path.display(),
+            expected
+        )
+    };
+
+    let levels = match table.get("levels") {
+        Some(value) => value
+            .as_integer()
+            .filter(|levels| (0..=MAX_BOOK_LEVELS as i64).contains(levels))
+            .map(|levels| levels as usize)
+            .ok_or_else(|| invalid("levels", format!("between 0 and {MAX_BOOK_LEVELS}")))?,
+        None => DEFAULT_BOOK_LEVELS,
+    };
+
+    let snapshot_interval_ms = match table.get("snapshot_interval_ms") {
+        Some(value) => value
+            .as_integer()
+            .filter(|ms| *ms > 0)
+            .map(|ms| ms as u64)
+            .ok_or_else(|| {
+                invalid(
+                    "snapshot_interval_ms",
+                    "a positive number of milliseconds".to_string(),
+                )
+            })?,
+        None => DEFAULT_BOOK_SNAPSHOT_INTERVAL_MS,
+    };
+
+    Ok(OrderBookConfig {
+        levels,
+        snapshot_interval_ms,
+    })
+}
+
+/// Parse the `[[corporate_actions]]` tables, each holding a `ticker`, either a
+/// `split` ratio such as `"4:1"` or a cash `dividend` per share, and an `effective`
+/// time given as an RFC 3339 date-time with offset or as Unix milliseconds.
+fn parse_corporate_actions(
+    parsed: &toml::Table,
+    path: &Path,
+) -> Result<Vec<CorporateActionConfig>, QuoteError> {
+    let tables = parsed
+        .get("corporate_actions")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut actions = Vec::with_capacity(tables.len());
+    for (index, table) in tables.iter().enumerate() {
+        let invalid = |field: &str, expected: &str| {
+            quote_common::quote_error!(
+                ConfigError,
+                "'corporate_actions[{}].{}' in '{}' must be {}",
+                index,
+                field,
+                path.display(),
+                expected
+            )
+        };
+
+        let ticker = table
+            .get("ticker")
+            .and_then(|v| v.as_str())
+            .map(|ticker| ticker.trim().to_uppercase())
+            .filter(|ticker| {
+                !ticker.is_empty() && ticker.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .ok_or_else(|| invalid("ticker", "an alphanumeric ticker symbol"))?;
+
+        let action = match (table.get("split"), table.get("dividend")) {
+            (Some(split), None) => split
+                .as_str()
+                .and_then(parse_split_ratio)
+                .ok_or_else(|| invalid("split", "a ratio of new to old shares such as \"4:1\""))?,
+            (None, Some(dividend)) => dividend
+                .as_float()
+                .or_else(|| dividend.as_integer().map(|amount| amount as f64))
+                .filter(|amount| amount.is_finite() && *amount > 0.0)
+                .map(|amount| CorporateActionKind::Dividend { amount })
+                .ok_or_else(|| invalid("dividend", "a positive cash amount per share"))?,
+            _ => {
+                return Err(quote_common::quote_error!(
+                    ConfigError,
+                    "'corporate_actions[{}]' in '{}' must set exactly one of 'split' and 'dividend'",
+                    index,
+                    path.display()
+                ));
+            }
+        };
+
+        let effective_ms = match table.get("effective") {
+            Some(toml::Value::Integer(ms)) if *ms >= 0 => Some(*ms),
+            Some(toml::Value::Datetime(datetime)) => {
+                DateTime::parse_from_rfc3339(&datetime.to_string())
+                    .ok()
+                    .map(|datetime| datetime.timestamp_millis())
+            }
+            Some(toml::Value::String(datetime)) => DateTime::parse_from_rfc3339(datetime)
+                .ok()
+                .map(|datetime| datetime.timestamp_millis()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            invalid(
+                "effective",
+                "an RFC 3339 date-time with offset or Unix milliseconds",
+            )
+        })?;
+
+        actions.push(CorporateActionConfig {
+            ticker,
+            action,
+            effective_ms,
+        });
+    }
+
+    // Sorting is stable, so actions effective together apply in file order.
+    actions.sort_by_key(|action| action.effective_ms);
+    Ok(actions)
+}
+
+/// Parse a split ratio `"NEW:OLD"` of two different positive share counts.
+fn parse_split_ratio(ratio: &str) -> Option<CorporateActionKind> {
+    let (new_shares, old_shares) = ratio.split_once(':')?;
+    let new_shares = new_shares.trim().parse::<u32>().ok()?;
+    let old_shares = old_shares.trim().parse::<u32>().ok()?;
+    (new_shares > 0 && old_shares > 0 && new_shares != old_shares).then_some(
+        CorporateActionKind::Split {
+            new_shares,
+            old_shares,
+        },
+    )
+}
+
+/// Parse the optional `[market_calendar]` table. Omitted fields default to the
+/// New York Stock Exchange schedule with no holidays.
+fn parse_market_calendar(
+    parsed: &toml::Table,
+    path: &Path,
+) -> Result<Option<MarketCalendar>, QuoteError> {
+    let Some(table) = parsed.get("market_calendar") else {
+        return Ok(None);
+    };
+    let invalid = |field: &str, expected: &str| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'market_calendar.{}' in '{}' must be {}",
+            field,
+            path.display(),
+            expected
+        )
+    };
+
+    let timezone_str = match table.get("timezone") {
+        Some(value) => value
+            .as_str()
+            .ok_or_else(|| invalid("timezone", "a time zone name"))?,
+        None => DEFAULT_MARKET_TIMEZONE,
+    };
+    let timezone = timezone_str.parse::<Tz>().map_err(|_| {
+        quote_common::quote_error!(
+            ConfigError,
+            "unknown time zone '{}' in '{}'",
+            timezone_str,
+            path.display()
+        )
+    })?;
+
+    let session_time = |field: &str, default: &str| -> Result<NaiveTime, QuoteError> {
+        let raw = match table.get(field) {
+            Some(value) => value.as_str().unwrap_or_default(),
+            None => default,
+        };
+        NaiveTime::parse_from_str(raw, SESSION_TIME_FORMAT)
+            .map_err(|_| invalid(field, "a time written as HH:MM"))
+    };
+    let pre_market_open = session_time("pre_market_open", DEFAULT_PRE_MARKET_OPEN)?;
+    let regular_open = session_time("regular_open", DEFAULT_REGULAR_OPEN)?;
+    let regular_close = session_time("regular_close", DEFAULT_REGULAR_CLOSE)?;
+    let after_hours_close = session_time("after_hours_close", DEFAULT_AFTER_HOURS_CLOSE)?;
+    if !(pre_market_open <= regular_open
+        && regular_open < regular_close
+        && regular_close <= after_hours_close)
+    {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "sessions in '{}' must satisfy pre_market_open <= regular_open < regular_close <= after_hours_close",
+            path.display()
+        ));
+    }
+
+    let mut holidays = HashSet::new();
+    if let Some(value) = table.get("holidays") {
+        let list = value
+            .as_array()
+            .ok_or_else(|| invalid("holidays", "a list of YYYY-MM-DD dates"))?;
+        for item in list {
+            let date = item
+                .as_str()
+                .and_then(|raw| NaiveDate::parse_from_str(raw, HOLIDAY_DATE_FORMAT).ok())
+                .ok_or_else(|| invalid("holidays", "a list of YYYY-MM-DD dates"))?;
+            holidays.insert(date);
+        }
+    }
+
+    let extended_hours_activity = match table.get("extended_hours_activity") {
+        Some(value) => value
+            .as_float()
+            .or_else(|| value.as_integer().map(|i| i as f64))
+            .filter(|activity| (0.0..=1.0).contains(activity))
+            .ok_or_else(|| invalid("extended_hours_activity", "a number between 0 and 1"))?,
+        None => DEFAULT_EXTENDED_HOURS_ACTIVITY,
+    };
+
+    Ok(Some(MarketCalendar {
+        timezone,
+        pre_market_open,
+        regular_open,
+        regular_close,
+        after_hours_close,
+        holidays,
+        extended_hours_activity,
+    }))
+}
+
+/// Parse `bar_intervals` (e.g. `["1s", "1m", "5m"]`); an empty list disables bars.
+fn parse_bar_intervals(parsed: &toml::Table, path: &Path) -> Result<Vec<BarInterval>, QuoteError> {
+    let raw = match parsed.get("bar_intervals") {
+        Some(value) => value
+            .as_array()
+            .ok_or_else(|| {
+                quote_common::quote_error!(
+                    ConfigError,
+                    "'bar_intervals' in '{}' must be a list of strings",
+                    path.display()
+                )
+            })?
+            .iter()
+            .map(|item| item.as_str().unwrap_or_default().to_string())
+            .collect::<Vec<_>>(),
+        None => DEFAULT_BAR_INTERVALS
+            .iter()
+            .map(|interval| interval.to_string())
+            .collect(),
+    };
+
+    let mut intervals = Vec::with_capacity(raw.len());
+    for interval in raw {
+        intervals.push(interval.parse::<BarInterval>().map_err(|_| {
+            quote_common::quote_error!(
+                ConfigError,
+                "invalid bar interval '{}' in '{}'",
+                interval,
+                path.display()
+            )
+        })?);
+    }
+    intervals.sort();
+    intervals.dedup();
+    Ok(intervals)
+}
+
+/// Parse the `[price_model]` table and its `[price_model.tickers]` overrides.
+/// Fields missing from an override fall back to the table's defaults.
+fn parse_price_model(parsed: &toml::Table, path: &Path) -> Result<PriceModel, QuoteError> {
+    let Some(table) = parsed.get("price_model") else {
+        return Ok(PriceModel::default());
+    };
+
+    let default = parse_price_params(table, PriceParams::default(), "price_model", path)?;
+
+    let mut tickers = HashMap::new();
+    if let Some(overrides) = table.get("tickers").and_then(|v| v.as_table()) {
+        for (ticker, value) in overrides {
+            let context = format!("price_model.tickers.{ticker}");
+            let params = parse_price_params(value, default, &context, path)?;
+            tickers.insert(ticker.to_uppercase(), params);
+        }
+    }
+
+    let market_correlation = parse_correlation(table, "market_correlation", "price_model", path)?;
+    let sectors = parse_sectors(table, market_correlation, path)?;
+
+    Ok(PriceModel {
+        default,
+        tickers,
+        market_correlation,
+        sectors,
+    })
+}
+
+/// Parse the optional `[volume_model]` table and its `[volume_model.tickers]` average
+/// daily volumes; omitted fields keep their defaults.
+fn parse_volume_model(parsed: &toml::Table, path: &Path) -> Result<VolumeModel, QuoteError> {
+    let Some(table) = parsed.get("volume_model") else {
+        return Ok(VolumeModel::default());
+    };
+    let invalid = |field: &str, expected: &str| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'volume_model.{}' in '{}' must be {}",
+            field,
+            path.display(),
+            expected
+        )
+    };
+    let shares = |value: &toml::Value, field: &str| {
+        value
+            .as_integer()
+            .filter(|shares| *shares > 0)
+            .map(|shares| shares as u64)
+            .ok_or_else(|| invalid(field, "a positive number of shares"))
+    };
+    let factor = |field: &str, min: f64, default: f64| match table.get(field) {
+        Some(value) => value
+            .as_float()
+            .or_else(|| value.as_integer().map(|factor| factor as f64))
+            .filter(|factor| factor.is_finite() && *factor >= min)
+            .ok_or_else(|| invalid(field, &format!("a number of at least {min}"))),
+        None => Ok(default),
+    };
+
+    let average_daily_volume = match table.get("average_daily_volume") {
+        Some(value) => shares(value, "average_daily_volume")?,
+        None => DEFAULT_AVERAGE_DAILY_VOLUME,
+    };
+
+    let mut tickers = HashMap::new();
+    if let Some(overrides) = table.get("tickers").and_then(|v| v.as_table()) {
+        for (ticker, value) in overrides {
+            let volume = shares(value, &format!("tickers.{ticker}"))?;
+            tickers.insert(ticker.to_uppercase(), volume);
+        }
+    }
+
+    Ok(VolumeModel {
+        average_daily_volume,
+        tickers,
+        open_close_ratio: factor("open_close_ratio", 1.0, DEFAULT_OPEN_CLOSE_VOLUME_RATIO)?,
+        spike_sensitivity: factor("spike_sensitivity", 0.0, DEFAULT_VOLUME_SPIKE_SENSITIVITY)?,
+    })
+}
+
+/// Parse the optional `[price_bands]` table and its `[price_bands.tickers]` band
+/// widths; omitted fields keep their defaults.
+fn parse_price_bands(parsed: &toml::Table, path: &Path) -> Result<Option<PriceBands>, QuoteError> {
+    let Some(table) = parsed.get("price_bands") else {
+        return Ok(None);
+    };
+    let invalid = |field: &str, expected: &str| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'price_bands.{}' in '{}' must be {}",
+            field,
+            path.display(),
+            expected
+        )
+    };
+    let percent = |value: &toml::Value, field: &str| {
+        value
+            .as_float()
+            .or_else(|| value.as_integer().map(|percent| percent as f64))
+            .filter(|percent| *percent > 0.0 && *percent < 100.0)
+            .ok_or_else(|| invalid(field, "a percentage between 0 and 100"))
+    };
+    let seconds = |field: &str, default: u64| match table.get(field) {
+        Some(value) => value
+            .as_integer()
+            .filter(|secs| *secs > 0)
+            .map(|secs| Duration::from_secs(secs as u64))
+            .ok_or_else(|| invalid(field, "a positive number of seconds")),
+        None => Ok(Duration::from_secs(default)),
+    };
+
+    let default_percent = match table.get("percent") {
+        Some(value) => percent(value, "percent")?,
+        None => DEFAULT_PRICE_BAND_PERCENT,
+    };
+
+    let mut tickers = HashMap::new();
+    if let Some(overrides) = table.get("tickers").and_then(|v| v.as_table()) {
+        for (ticker, value) in overrides {
+            let width = percent(value, &format!("tickers.{ticker}"))?;
+            tickers.insert(ticker.to_uppercase(), width);
+        }
+    }
+
+    Ok(Some(PriceBands {
+        percent: default_percent,
+        tickers,
+        reference_interval: seconds("reference_secs", DEFAULT_BAND_REFERENCE_SECS)?,
+        halt_duration: seconds("halt_secs", DEFAULT_LIMIT_HALT_SECS)?,
+    }))
+}
+
+/// Parse `[price_model.sectors.<name>]` tables, each with a `correlation` and `tickers`.
+/// A ticker may belong to one sector only.
+fn parse_sectors(
+    table: &toml::Value,
+    market_correlation: f64,
+    path: &Path,
+) -> Result<Vec<Sector>, QuoteError> {
+    let Some(sectors_table) = table.get("sectors").and_then(|v| v.as_table()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut sectors = Vec::with_capacity(sectors_table.len());
+    let mut assigned = HashMap::<String, String>::new();
+    for (name, value) in sectors_table {
+        let context = format!("price_model.sectors.{name}");
+        let correlation = parse_correlation(value, "correlation", &context, path)?;
+        if market_correlation + correlation > 1.0 {
+            return Err(quote_common::quote_error!(
+                ConfigError,
+                "'{}.correlation' plus 'price_model.market_correlation' in '{}' must not exceed 1",
+                context,
+                path.display()
+            ));
+        }
+
+        let members = value
+            .get("tickers")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                quote_common::quote_error!(
+                    ConfigError,
+                    "'{}.tickers' in '{}' must be a list of tickers",
+                    context,
+                    path.display()
+                )
+            })?;
+        let mut tickers = Vec::with_capacity(members.len());
+        for member in members {
+            let ticker = member.as_str().unwrap_or_default().trim().to_uppercase();
+            if let Some(other) = assigned.insert(ticker.clone(), name.clone()) {
+                return Err(quote_common::quote_error!(
+                    ConfigError,
+                    "ticker '{}' is listed in sectors '{}' and '{}' in '{}'",
+                    ticker,
+                    other,
+                    name,
+                    path.display()
+                ));
+            }
+            tickers.push(ticker);
+        }
+
+        sectors.push(Sector {
+            name: name.clone(),
+            correlation,
+            tickers,
+        });
+    }
+    Ok(sectors)
+}
+
+/// Read an optional correlation coefficient between 0 and 1; a missing one is 0.
+fn parse_correlation(
+    value: &toml::Value,
+    key: &str,
+    context: &str,
+    path: &Path,
+) -> Result<f64, QuoteError> {
+    match value.get(key) {
+        None => Ok(0.0),
+        Some(v) => v
+            .as_float()
+            .or_else(|| v.as_integer().map(|i| i as f64))
+            .filter(|correlation| (0.0..=1.0).contains(correlation))
+            .ok_or_else(|| {
+                quote_common::quote_error!(
+                    ConfigError,
+                    "'{}.{}' in '{}' must be a number between 0 and 1",
+                    context,
+                    key,
+                    path.display()
+                )
+            }),
+    }
+}
+
+fn parse_price_params(
+    value: &toml::Value,
+    fallback: PriceParams,
+    context: &str,
+    path: &Path,
+) -> Result<PriceParams, QuoteError> {
+    let field = |name: &str, fallback: f64| -> Result<f64, QuoteError> {
+        match value.get(name) {
+            None => Ok(fallback),
+            Some(v) => v
+                .as_float()
+                .or_else(|| v.as_integer().map(|i| i as f64))
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| {
+                    quote_common::quote_error!(
+                        ConfigError,
+                        "'{}.{}' in '{}' must be a number",
+                        context,
+                        name,
+                        path.display()
+                    )
+                }),
+        }
+    };
+
+    let drift = field("drift", fallback.drift)?;
+    let volatility = field("volatility", fallback.volatility)?;
+    if volatility < 0.0 {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "'{}.volatility' in '{}' must not be negative",
+            context,
+            path.display()
+        ));
+    }
+
+    Ok(PriceParams { drift, volatility })
+}
+
+/// Parse the `[instruments]` table and its `[instruments.tickers]` overrides.
+/// Fields missing from an override fall back to the table's defaults.
+fn parse_instruments(parsed: &toml::Table, path: &Path) -> Result<Instruments, QuoteError> {
+    let Some(table) = parsed.get("instruments") else {
+        return Ok(Instruments::default());
+    };
+
+    let default = parse_instrument_spec(table, InstrumentSpec::default(), "instruments", path)?;
+
+    let mut tickers = HashMap::new();
+    if let Some(overrides) = table.get("tickers").and_then(|v| v.as_table()) {
+        for (ticker, value) in overrides {
+            let context = format!("instruments.tickers.{ticker}");
+            let spec = parse_instrument_spec(value, default, &context, path)?;
+            tickers.insert(ticker.to_uppercase(), spec);
+        }
+    }
+
+    Ok(Instruments { default, tickers })
+}
+
+/// Read `tick_size` and `precision`. A precision left out defaults to the decimal
+/// places of a `tick_size` given alongside it, otherwise to `fallback`.
+fn parse_instrument_spec(
+    value: &toml::Value,
+    fallback: InstrumentSpec,
+    context: &str,
+    path: &Path,
+) -> Result<InstrumentSpec, QuoteError> {
+    let invalid = |field: &str, expected: String| {
+        quote_common::quote_error!(
+            ConfigError,
+            "'{}.{}' in '{}' must be {}",
+            context,
+            field,
+            path.display(),
+            expected
+        )
+    };
+
+    let tick_size = match value.get("tick_size") {
+        None => fallback.tick_size,
+        Some(v) => v
+            .as_float()
+            .or_else(|| v.as_integer().map(|i| i as f64))
+            .filter(|tick| tick.is_finite() && *tick > 0.0)
+            .ok_or_else(|| invalid("tick_size", "a positive number".to_string()))?,
+    };
+    let tick_decimals = decimal_places(tick_size).ok_or_else(|| {
+        invalid(
+            "tick_size",
+            format!("a number with at most {MAX_PRICE_PRECISION} decimal places"),
+        )
+    })?;
+
+    let precision = match value.get("precision") {
+        Some(v) => v
+            .as_integer()
+            .and_then(|i| u8::try_from(i).ok())
+            .filter(|precision| *precision <= MAX_PRICE_PRECISION)
+            .ok_or_else(|| {
+                invalid(
+                    "precision",
+                    format!("a whole number from 0 to {MAX_PRICE_PRECISION}"),
+                )
+            })?,
+        None if value.get("tick_size").is_some() => tick_decimals,
+        None => fallback.precision,
+    };
+    if precision < tick_decimals {
+        return Err(invalid(
+            "precision",
+            format!("at least {tick_decimals}, the decimal places of its tick size"),
+        ));
+    }
+
+    Ok(InstrumentSpec {
+        tick_size,
+        precision,
+    })
+}
+
+/// Fewest decimal places that write `value` exactly, if at most `MAX_PRICE_PRECISION`.
+fn decimal_places(value: f64) -> Option<u8> {
+    (0..=MAX_PRICE_PRECISION).find(|&places| {
+        let scale = 10f64.powi(i32::from(places));
+        ((value * scale).round() / scale - value).abs() <= value * TICK_TOLERANCE
+    })
+}
+
+/// Parse `multicast_interface`, `multicast_ttl` and the `[[multicast_groups]]` tables.
+fn parse_multicast_config(
+    parsed: &toml::Table,
+    path: &Path,
+) -> Result<MulticastConfig, QuoteError> {
+    let interface = match parsed.get("multicast_interface").and_then(|v| v.as_str()) {
+        Some(interface) => interface.parse::<Ipv4Addr>().map_err(|err| {
+            quote_common::quote_error!(
+                ConfigError,
+                "invalid multicast interface '{}' in '{}': {}",
+                interface,
+                path.display(),
+                err
+            )
+        })?,
+        None => Ipv4Addr::UNSPECIFIED,
+    };
+
+    let ttl = parsed
+        .get("multicast_ttl")
+        .and_then(|v| v.as_integer())
+        .map(|i| u8::try_from(i).map(u32::from))
+        .unwrap_or(Ok(DEFAULT_MULTICAST_TTL))
+        .map_err(|_| {
+            quote_common::quote_error!(
+                ConfigError,
+                "'multicast_ttl' in '{}' must be between 0 and 255",
+                path.display()
+            )
+        })?;
+
+    let tables = parsed
+        .get("multicast_groups")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut groups = Vec::with_capacity(tables.len());
+    for table in tables {
+        groups.push(parse_multicast_group(table, path)?);
+    }
+
+    Ok(MulticastConfig {
+        interface,
+        ttl,
+        groups,
+    })
+}
+
+fn parse_multicast_group(value: &toml::Value, path: &Path) -> Result<MulticastGroup, QuoteError> {
+    let group_str = value.get("group").and_then(|v| v.as_str()).ok_or_else(|| {
+        quote_common::quote_error!(
+            ConfigError,
+            "multicast group in '{}' is missing 'group'",
+            path.display()
+        )
+    })?;
+    let group = group_str
+        .parse::<SocketAddrV4>()
+        .ok()
+        .filter(|group| group.ip().is_multicast())
+        .ok_or_else(|| {
+            quote_common::quote_error!(
+                ConfigError,
+                "'{}' in '{}' is not an IPv4 multicast address with port",
+                group_str,
+                path.display()
+            )
+        })?;
+
+    let mut tickers = Vec::new();
+    let raw_tickers = value
+        .get("tickers")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for raw in raw_tickers.iter().filter_map(|item| item.as_str()) {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let (ticker, feed) = parse_subscription_key(raw).map_err(|_| {
+            quote_common::quote_error!(
+                ConfigError,
+                "invalid ticker '{}' for multicast group {} in '{}'",
+                raw,
+                group,
+                path.display()
+            )
+        })?;
+        tickers.push(subscription_key(&ticker, feed));
+    }
+    if tickers.is_empty() {
+        return Err(quote_common::quote_error!(
+            ConfigError,
+            "multicast group {} in '{}' has no tickers",
+            group,
+            path.display()
+        ));
+    }
+
+    let encoding = match value.get("encoding").and_then(|v| v.as_str()) {
+        Some(encoding) => encoding.parse::<QuoteEncoding>().map_err(|_| {
+            quote_common::quote_error!(
+                ConfigError,
+                "unsupported encoding '{}' for multicast group {} in '{}'",
+                encoding,
+                group,
+                path.display()
+            )
+        })?,
+        None => QuoteEncoding::default(),
+    };
+
+    Ok(MulticastGroup {
+        group,
+        tickers,
+        encoding,
     })
 }
 
@@ -162,6 +1431,14 @@ mod tests {
         writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
         writeln!(file, "quote_rate_ms = 500").unwrap();
         writeln!(file, "keepalive_timeout_secs = 10").unwrap();
+        writeln!(file, "max_connections = 8").unwrap();
+        writeln!(file, "command_timeout_secs = 3").unwrap();
+        writeln!(file, "udp_max_payload = 1400").unwrap();
+        writeln!(file, "udp_flush_interval_ms = 20").unwrap();
+        writeln!(file, "seed = 42").unwrap();
+        writeln!(file, "bar_intervals = [\"5m\", \"15s\", \"5m\"]").unwrap();
+        writeln!(file, "simulated_clock_start_ms = 1700000000000").unwrap();
+        writeln!(file, "admin_commands = true").unwrap();
         writeln!(file, "[initial_prices]").unwrap();
         writeln!(file, "AAPL = 150.0").unwrap();
         writeln!(file, "TSLA = 250.5").unwrap();
@@ -173,8 +1450,19 @@ mod tests {
             "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
         );
         assert_eq!(config.tickers_file, "tickers.txt");
-        assert_eq!(config.quote_rate_ms, 500);
+        assert_eq!(config.quote_rates.default_ms, 500);
         assert_eq!(config.keepalive_timeout_secs, 10);
+        assert_eq!(config.max_connections, 8);
+        assert_eq!(config.command_timeout_secs, 3);
+        assert_eq!(config.udp_max_payload, 1400);
+        assert_eq!(config.udp_flush_interval_ms, 20);
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(
+            config.bar_intervals,
+            vec!["15s".parse().unwrap(), "5m".parse().unwrap()]
+        );
+        assert_eq!(config.simulated_clock_start_ms, Some(1_700_000_000_000));
+        assert!(config.admin_commands);
         assert_eq!(config.initial_prices.get("AAPL"), Some(&150.0));
         assert_eq!(config.initial_prices.get("TSLA"), Some(&250.5));
 
@@ -190,12 +1478,589 @@ mod tests {
         drop(file);
 
         let config = load_config(&path).expect("load config");
-        assert_eq!(config.quote_rate_ms, DEFAULT_QUOTE_RATE_MS);
+        assert_eq!(config.quote_rates, QuoteRates::default());
         assert_eq!(
             config.keepalive_timeout_secs,
             DEFAULT_KEEPALIVE_TIMEOUT_SECS
         );
         assert!(config.initial_prices.is_empty());
+        assert_eq!(config.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(config.command_timeout_secs, DEFAULT_COMMAND_TIMEOUT_SECS);
+        assert_eq!(config.udp_max_payload, DEFAULT_UDP_MAX_PAYLOAD);
+        assert_eq!(config.udp_flush_interval_ms, DEFAULT_UDP_FLUSH_INTERVAL_MS);
+        assert_eq!(config.multicast.interface, Ipv4Addr::UNSPECIFIED);
+        assert_eq!(config.multicast.ttl, DEFAULT_MULTICAST_TTL);
+        assert!(config.multicast.groups.is_empty());
+        assert_eq!(config.price_model, PriceModel::default());
+        assert_eq!(config.instruments, Instruments::default());
+        assert_eq!(config.seed, None);
+        assert_eq!(
+            config
+                .bar_intervals
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            DEFAULT_BAR_INTERVALS
+        );
+        assert_eq!(config.simulated_clock_start_ms, None);
+        assert_eq!(config.market_calendar, None);
+        assert!(!config.admin_commands);
+        assert_eq!(config.replay, None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_market_calendar() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[market_calendar]").unwrap();
+        writeln!(file, "timezone = \"Europe/London\"").unwrap();
+        writeln!(file, "pre_market_open = \"08:00\"").unwrap();
+        writeln!(file, "regular_open = \"08:00\"").unwrap();
+        writeln!(file, "regular_close = \"16:30\"").unwrap();
+        writeln!(file, "holidays = [\"2025-12-25\", \"2025-12-26\"]").unwrap();
+        writeln!(file, "extended_hours_activity = 0.5").unwrap();
+        drop(file);
+
+        let calendar = load_config(&path)
+            .expect("load config")
+            .market_calendar
+            .expect("calendar configured");
+        assert_eq!(calendar.timezone, chrono_tz::Europe::London);
+        assert_eq!(calendar.pre_market_open, calendar.regular_open);
+        assert_eq!(
+            calendar.regular_close,
+            NaiveTime::from_hms_opt(16, 30, 0).unwrap()
+        );
+        assert_eq!(
+            calendar.after_hours_close,
+            NaiveTime::from_hms_opt(20, 0, 0).unwrap()
+        );
+        assert!(
+            calendar
+                .holidays
+                .contains(&NaiveDate::from_ymd_opt(2025, 12, 26).unwrap())
+        );
+        assert_eq!(calendar.extended_hours_activity, 0.5);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_replay() {
+        let path = unique_temp_path("config");
+        fs::write(
+            &path,
+            "tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n[replay]\nfile = \"ticks.jsonl\"\nspeed = 4\nloop = true\n",
+        )
+        .expect("write temp file");
+
+        let replay = load_config(&path)
+            .expect("load config")
+            .replay
+            .expect("replay configured");
+        assert_eq!(
+            replay,
+            ReplayConfig {
+                file: PathBuf::from("ticks.jsonl"),
+                format: ReplayFormat::JsonLines,
+                speed: 4.0,
+                looping: true,
+            }
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_invalid_replay() {
+        for (body, expected) in [
+            ("[replay]\nspeed = 2.0", "replay.file"),
+            ("[replay]\nfile = \"ticks.txt\"", "replay.file"),
+            ("[replay]\nfile = \"ticks.csv\"\nspeed = 0", "replay.speed"),
+            (
+                "admin_commands = true\n[replay]\nfile = \"ticks.csv\"",
+                "admin_commands",
+            ),
+        ] {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .expect("write temp file");
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(expected), "{body}: {err}");
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_rejects_invalid_market_calendar() {
+        for (line, expected) in [
+            ("timezone = \"Mars/Olympus\"", "unknown time zone"),
+            ("regular_open = \"9:30am\"", "regular_open"),
+            ("regular_close = \"09:00\"", "must satisfy"),
+            ("holidays = [\"25/12/2025\"]", "holidays"),
+            ("extended_hours_activity = 1.5", "extended_hours_activity"),
+        ] {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!(
+                    "tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n[market_calendar]\n{line}\n"
+                ),
+            )
+            .expect("write temp file");
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(expected), "{line}: {err}");
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_quote_rates() {
+        let path = unique_temp_path("config");
+        fs::write(
+            &path,
+            "tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\nquote_rate_ms = 2000\n[quote_rates]\naapl = 50\n",
+        )
+        .expect("write temp file");
+
+        let rates = load_config(&path).expect("load config").quote_rates;
+        assert_eq!(rates.interval("AAPL"), Duration::from_millis(50));
+        assert_eq!(rates.interval("XYZ"), Duration::from_secs(2));
+        fs::remove_file(&path).unwrap();
+
+        for body in ["quote_rate_ms = 0", "[quote_rates]\nAAPL = -5"] {
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .expect("write temp file");
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains("positive number"), "{body}: {err}");
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_price_model_overrides() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[price_model]").unwrap();
+        writeln!(file, "drift = 0.02").unwrap();
+        writeln!(file, "volatility = 0.15").unwrap();
+        writeln!(file, "[price_model.tickers]").unwrap();
+        writeln!(file, "tsla = {{ drift = 0.1, volatility = 0.6 }}").unwrap();
+        writeln!(file, "DUK = {{ volatility = 0 }}").unwrap();
+        drop(file);
+
+        let config = load_config(&path).expect("load config");
+        let model = &config.price_model;
+        assert_eq!(
+            model.params("AAPL"),
+            PriceParams {
+                drift: 0.02,
+                volatility: 0.15
+            }
+        );
+        assert_eq!(
+            model.params("TSLA"),
+            PriceParams {
+                drift: 0.1,
+                volatility: 0.6
+            }
+        );
+        assert_eq!(
+            model.params("DUK"),
+            PriceParams {
+                drift: 0.02,
+                volatility: 0.0
+            }
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_instruments() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[instruments]").unwrap();
+        writeln!(file, "precision = 3").unwrap();
+        writeln!(file, "[instruments.tickers]").unwrap();
+        writeln!(file, "eurusd = {{ tick_size = 0.00001 }}").unwrap();
+        writeln!(file, "BRK = {{ tick_size = 1, precision = 2 }}").unwrap();
+        drop(file);
+
+        let instruments = load_config(&path).expect("load config").instruments;
+        let spec = |tick_size, precision| InstrumentSpec {
+            tick_size,
+            precision,
+        };
+        assert_eq!(instruments.spec("AAPL"), spec(0.01, 3));
+        assert_eq!(instruments.spec("EURUSD"), spec(0.00001, 5));
+        assert_eq!(instruments.spec("BRK"), spec(1.0, 2));
+
+        let eurusd = instruments.spec("EURUSD");
+        assert_eq!(eurusd.round(1.085_427_3), 1.085_43);
+        assert_eq!(eurusd.round(0.0), 0.000_01);
+        let nickel = spec(0.05, 2);
+        assert_eq!(nickel.round(1.024), 1.0);
+        assert_eq!(nickel.floor(1.07), 1.05);
+        assert_eq!(nickel.ceil(1.07), 1.1);
+        assert_eq!(nickel.ceil(1.1), 1.1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_invalid_instruments() {
+        for (body, key) in [
+            ("tick_size = 0", "'instruments.tick_size'"),
+            ("tick_size = 0.000000001", "'instruments.tick_size'"),
+            ("precision = 9", "'instruments.precision'"),
+            (
+                "[instruments.tickers]\nEURUSD = { tick_size = 0.0001, precision = 2 }",
+                "'instruments.tickers.EURUSD.precision'",
+            ),
+        ] {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!(
+                    "tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n[instruments]\n{body}\n"
+                ),
+            )
+            .unwrap();
+            let err = load_config(&path).expect_err(body);
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(key), "{err} should name {key}");
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_order_book() {
+        let load = |body: &str| {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .unwrap();
+            let result = load_config(&path);
+            fs::remove_file(path).unwrap();
+            result
+        };
+
+        let order_book = load("").expect("defaults").order_book;
+        assert_eq!(order_book, OrderBookConfig::default());
+        assert!(order_book.enabled());
+
+        let order_book = load("[order_book]\nlevels = 3\nsnapshot_interval_ms = 250")
+            .expect("custom depth")
+            .order_book;
+        assert_eq!(
+            order_book,
+            OrderBookConfig {
+                levels: 3,
+                snapshot_interval_ms: 250
+            }
+        );
+        assert!(
+            !load("[order_book]\nlevels = 0")
+                .unwrap()
+                .order_book
+                .enabled()
+        );
+
+        for (body, key) in [
+            ("levels = 11", "'order_book.levels'"),
+            (
+                "snapshot_interval_ms = 0",
+                "'order_book.snapshot_interval_ms'",
+            ),
+        ] {
+            let err = load(&format!("[order_book]\n{body}")).expect_err(body);
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(key), "{err} should name {key}");
+        }
+    }
+
+    #[test]
+    fn test_load_config_volume_model() {
+        let load = |body: &str| {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .unwrap();
+            let result = load_config(&path);
+            fs::remove_file(path).unwrap();
+            result
+        };
+
+        assert_eq!(
+            load("").expect("defaults").volume_model,
+            VolumeModel::default()
+        );
+
+        let model = load(
+            "[volume_model]\naverage_daily_volume = 2000000\nopen_close_ratio = 1\n\
+             spike_sensitivity = 0.5\n[volume_model.tickers]\naapl = 60000000",
+        )
+        .expect("custom volume model")
+        .volume_model;
+        assert_eq!(model.daily_volume("AAPL"), 60_000_000);
+        assert_eq!(model.daily_volume("XYZ"), 2_000_000);
+        assert_eq!(model.open_close_ratio, 1.0);
+        assert_eq!(model.spike_sensitivity, 0.5);
+
+        for (body, key) in [
+            (
+                "average_daily_volume = 0",
+                "'volume_model.average_daily_volume'",
+            ),
+            ("open_close_ratio = 0.5", "'volume_model.open_close_ratio'"),
+            ("spike_sensitivity = -1", "'volume_model.spike_sensitivity'"),
+            (
+                "[volume_model.tickers]\nMSFT = 1.5",
+                "'volume_model.tickers.MSFT'",
+            ),
+        ] {
+            let err = load(&format!("[volume_model]\n{body}")).expect_err(body);
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(key), "{err} should name {key}");
+        }
+    }
+
+    #[test]
+    fn test_load_config_price_bands() {
+        let load = |body: &str| {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .unwrap();
+            let result = load_config(&path);
+            fs::remove_file(path).unwrap();
+            result
+        };
+
+        assert_eq!(load("").expect("defaults").price_bands, None);
+        assert_eq!(
+            load("[price_bands]").expect("default bands").price_bands,
+            Some(PriceBands::default())
+        );
+
+        let bands = load(
+            "[price_bands]\npercent = 10\nreference_secs = 60\nhalt_secs = 15\n\
+             [price_bands.tickers]\ntsla = 20.0",
+        )
+        .expect("custom bands")
+        .price_bands
+        .expect("bands enabled");
+        assert_eq!(bands.percent("TSLA"), 20.0);
+        assert_eq!(bands.percent("AAPL"), 10.0);
+        assert_eq!(bands.reference_interval, Duration::from_secs(60));
+        assert_eq!(bands.halt_duration, Duration::from_secs(15));
+
+        for (body, key) in [
+            ("percent = 0", "'price_bands.percent'"),
+            ("halt_secs = 0", "'price_bands.halt_secs'"),
+            ("reference_secs = \"5m\"", "'price_bands.reference_secs'"),
+            (
+                "[price_bands.tickers]\nGME = 150",
+                "'price_bands.tickers.GME'",
+            ),
+        ] {
+            let err = load(&format!("[price_bands]\n{body}")).expect_err(body);
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(key), "{err} should name {key}");
+        }
+    }
+
+    #[test]
+    fn test_load_config_corporate_actions() {
+        let load = |body: &str| {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!("tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n{body}\n"),
+            )
+            .unwrap();
+            let result = load_config(&path);
+            fs::remove_file(path).unwrap();
+            result
+        };
+
+        assert!(load("").expect("defaults").corporate_actions.is_empty());
+
+        let actions = load(
+            "[[corporate_actions]]\nticker = \"msft\"\ndividend = 0.75\neffective = 2024-05-15T13:30:00Z\n\
+             [[corporate_actions]]\nticker = \"AAPL\"\nsplit = \"4:1\"\neffective = 1000",
+        )
+        .expect("valid actions")
+        .corporate_actions;
+        assert_eq!(
+            actions,
+            vec![
+                CorporateActionConfig {
+                    ticker: "AAPL".to_string(),
+                    action: CorporateActionKind::Split {
+                        new_shares: 4,
+                        old_shares: 1
+                    },
+                    effective_ms: 1_000,
+                },
+                CorporateActionConfig {
+                    ticker: "MSFT".to_string(),
+                    action: CorporateActionKind::Dividend { amount: 0.75 },
+                    effective_ms: 1_715_779_800_000,
+                },
+            ]
+        );
+
+        for (body, key) in [
+            (
+                "split = \"1:1\"\neffective = 0",
+                "'corporate_actions[0].split'",
+            ),
+            (
+                "split = \"2:1\"\ndividend = 1.0\neffective = 0",
+                "exactly one of 'split' and 'dividend'",
+            ),
+            (
+                "dividend = -1.0\neffective = 0",
+                "'corporate_actions[0].dividend'",
+            ),
+            (
+                "dividend = 1.0\neffective = 2024-05-15T09:30:00",
+                "'corporate_actions[0].effective'",
+            ),
+        ] {
+            let err =
+                load(&format!("[[corporate_actions]]\nticker = \"AAPL\"\n{body}")).expect_err(body);
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(key), "{err} should name {key}");
+        }
+    }
+
+    #[test]
+    fn test_load_config_rejects_invalid_bar_interval() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "bar_intervals = [\"1m\", \"1d\"]").unwrap();
+        drop(file);
+
+        let err = load_config(&path).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ConfigError { .. }));
+        assert!(err.to_string().contains("invalid bar interval '1d'"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_price_model_sectors() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[price_model]").unwrap();
+        writeln!(file, "market_correlation = 0.3").unwrap();
+        writeln!(file, "[price_model.sectors.semiconductors]").unwrap();
+        writeln!(file, "correlation = 0.5").unwrap();
+        writeln!(file, "tickers = [\"nvda\", \"AVGO\"]").unwrap();
+        writeln!(file, "[price_model.sectors.utilities]").unwrap();
+        writeln!(file, "correlation = 0.2").unwrap();
+        writeln!(file, "tickers = [\"DUK\", \"SO\"]").unwrap();
+        drop(file);
+
+        let model = load_config(&path).expect("load config").price_model;
+        assert_eq!(model.market_correlation, 0.3);
+        assert_eq!(model.sectors.len(), 2);
+        let semis = &model.sectors[model.sector_index("NVDA").expect("NVDA in a sector")];
+        assert_eq!(semis.name, "semiconductors");
+        assert_eq!(semis.correlation, 0.5);
+        assert_eq!(semis.tickers, vec!["NVDA", "AVGO"]);
+        assert_eq!(model.sector_index("AAPL"), None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_invalid_sectors() {
+        for (body, expected) in [
+            (
+                "market_correlation = 1.2",
+                "'price_model.market_correlation'",
+            ),
+            (
+                "market_correlation = 0.6\n[price_model.sectors.tech]\ncorrelation = 0.5\ntickers = [\"AAPL\"]",
+                "must not exceed 1",
+            ),
+            (
+                "[price_model.sectors.a]\ntickers = [\"AAPL\"]\n[price_model.sectors.b]\ntickers = [\"AAPL\"]",
+                "ticker 'AAPL' is listed in sectors 'a' and 'b'",
+            ),
+            (
+                "[price_model.sectors.a]\ncorrelation = 0.1",
+                "'price_model.sectors.a.tickers'",
+            ),
+        ] {
+            let path = unique_temp_path("config");
+            fs::write(
+                &path,
+                format!(
+                    "tcp_addr = \"127.0.0.1:8080\"\ntickers_file = \"t.txt\"\n[price_model]\n{body}\n"
+                ),
+            )
+            .expect("write temp file");
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains(expected), "{body}: {err}");
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_rejects_negative_volatility() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[price_model.tickers]").unwrap();
+        writeln!(file, "AAPL = {{ volatility = -0.2 }}").unwrap();
+        drop(file);
+
+        let err = load_config(&path).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ConfigError { .. }));
+        assert!(
+            err.to_string()
+                .contains("price_model.tickers.AAPL.volatility")
+        );
 
         fs::remove_file(path).unwrap();
     }
@@ -223,6 +2088,151 @@ mod tests {
         fs::remove_file(path).unwrap();
     }
 
+    #[test]
+    fn test_load_config_rejects_non_positive_max_connections() {
+        for value in ["0", "-1"] {
+            let path = unique_temp_path("config");
+            let mut file = fs::File::create(&path).expect("create temp file");
+            writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+            writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+            writeln!(file, "max_connections = {value}").unwrap();
+            drop(file);
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains("max_connections"));
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_rejects_non_positive_command_timeout() {
+        for value in ["0", "-5"] {
+            let path = unique_temp_path("config");
+            let mut file = fs::File::create(&path).expect("create temp file");
+            writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+            writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+            writeln!(file, "command_timeout_secs = {value}").unwrap();
+            drop(file);
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains("command_timeout_secs"));
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_rejects_oversized_udp_payload() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "udp_max_payload = {}", BUFFER_SIZE + 1).unwrap();
+        drop(file);
+
+        let err = load_config(&path).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ConfigError { .. }));
+        assert!(err.to_string().contains("udp_max_payload"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_out_of_range_udp_flush_interval() {
+        for value in [-1, MAX_UDP_FLUSH_INTERVAL_MS as i64 + 1] {
+            let path = unique_temp_path("config");
+            let mut file = fs::File::create(&path).expect("create temp file");
+            writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+            writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+            writeln!(file, "udp_flush_interval_ms = {value}").unwrap();
+            drop(file);
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains("udp_flush_interval_ms"));
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_config_multicast_groups() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "multicast_interface = \"127.0.0.1\"").unwrap();
+        writeln!(file, "multicast_ttl = 4").unwrap();
+        writeln!(file, "[[multicast_groups]]").unwrap();
+        writeln!(file, "group = \"239.255.0.1:30001\"").unwrap();
+        writeln!(file, "tickers = [\"aapl\", \"MSFT\"]").unwrap();
+        writeln!(file, "[[multicast_groups]]").unwrap();
+        writeln!(file, "group = \"239.255.0.2:30002\"").unwrap();
+        writeln!(file, "tickers = [\"NVDA\"]").unwrap();
+        writeln!(file, "encoding = \"binary\"").unwrap();
+        drop(file);
+
+        let config = load_config(&path).expect("load config");
+        assert_eq!(config.multicast.interface, Ipv4Addr::LOCALHOST);
+        assert_eq!(config.multicast.ttl, 4);
+        assert_eq!(
+            config.multicast.groups,
+            vec![
+                MulticastGroup {
+                    group: "239.255.0.1:30001".parse().unwrap(),
+                    tickers: vec!["AAPL".to_string(), "MSFT".to_string()],
+                    encoding: QuoteEncoding::Json,
+                },
+                MulticastGroup {
+                    group: "239.255.0.2:30002".parse().unwrap(),
+                    tickers: vec!["NVDA".to_string()],
+                    encoding: QuoteEncoding::Binary,
+                },
+            ]
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_unicast_multicast_group() {
+        let path = unique_temp_path("config");
+        let mut file = fs::File::create(&path).expect("create temp file");
+        writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+        writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+        writeln!(file, "[[multicast_groups]]").unwrap();
+        writeln!(file, "group = \"10.0.0.1:30001\"").unwrap();
+        writeln!(file, "tickers = [\"AAPL\"]").unwrap();
+        drop(file);
+
+        let err = load_config(&path).expect_err("should fail");
+        assert!(matches!(err, QuoteError::ConfigError { .. }));
+        assert!(err.to_string().contains("not an IPv4 multicast address"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_rejects_out_of_range_multicast_ttl() {
+        for value in [-1, 256] {
+            let path = unique_temp_path("config");
+            let mut file = fs::File::create(&path).expect("create temp file");
+            writeln!(file, "tcp_addr = \"127.0.0.1:8080\"").unwrap();
+            writeln!(file, "tickers_file = \"tickers.txt\"").unwrap();
+            writeln!(file, "multicast_ttl = {value}").unwrap();
+            drop(file);
+
+            let err = load_config(&path).expect_err("should fail");
+            assert!(matches!(err, QuoteError::ConfigError { .. }));
+            assert!(err.to_string().contains("multicast_ttl"));
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
     #[test]
     fn test_load_config_invalid_toml() {
         let path = unique_temp_path("config");
@@ -1,149 +1,1604 @@
-use std::collections::{HashMap, HashSet};
-use std::thread;
-use std::time::Duration;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::f64::consts::TAU;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crossbeam::channel::{self, Receiver, Sender};
-use log::info;
-use rand::{Rng, rng};
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use quote_common::{
-    DEFAULT_INITIAL_PRICE, DEFAULT_QUOTE_RATE_MS, POPULAR_TICKERS, QuoteError, StockQuote,
+    CorporateAction, DEFAULT_INITIAL_PRICE, HaltReason, MarketEvent, MarketEventKind, SessionEvent,
+    SessionState, StockQuote, StreamMessage, format_price,
 };
 
-/// Generates stock quotes on a fixed interval and broadcasts them over a channel.
+use crate::admin::AdminCommand;
+use crate::calendar::MarketCalendar;
+use crate::config::{
+    CorporateActionConfig, InstrumentSpec, Instruments, PriceBands, PriceModel, PriceParams,
+    QuoteRates, VolumeModel,
+};
+use crate::source::{QuoteSource, send_all};
+use crate::volume::{
+    DailyTotals, intraday_profile, spike_multiplier, trading_date, trading_day_ms,
+};
+
+/// Milliseconds in a trading year (252 sessions of 6.5 hours), the time unit of
+/// the annualized drift and volatility.
+const TRADING_MS_PER_YEAR: f64 = 252.0 * 6.5 * 60.0 * 60.0 * 1_000.0;
+/// Narrowest bid/ask spread, in basis points of the last price.
+const MIN_SPREAD_BPS: f64 = 1.0;
+/// Additional spread in basis points per unit of annualized volatility.
+const SPREAD_BPS_PER_VOLATILITY: f64 = 20.0;
+/// Range the spread is randomly scaled by each cycle.
+const SPREAD_JITTER: std::ops::Range<f64> = 0.5..1.5;
+/// Range each trade's expected volume is randomly scaled by.
+const VOLUME_JITTER: std::ops::Range<f64> = 0.5..1.5;
+/// Bid and ask sizes are whole round lots of this many shares.
+pub(crate) const ROUND_LOT: u32 = 100;
+/// Largest bid or ask size, in round lots.
+pub(crate) const MAX_SIZE_LOTS: u32 = 10;
+const BASIS_POINTS: f64 = 10_000.0;
+/// Volume and volatility scale when no calendar reduces them.
+const FULL_ACTIVITY: f64 = 1.0;
+
+/// Generates stock quotes, each ticker on its own interval, and broadcasts them over a channel.
 pub struct QuoteGenerator {
     tickers: Vec<String>,
+    /// Unrounded prices; rounding is applied only to quoted values so small steps accumulate.
     prices: HashMap<String, f64>,
-    popular: HashSet<String>,
-    quote_interval: Duration,
+    params: HashMap<String, PriceParams>,
+    loadings: HashMap<String, FactorLoadings>,
+    /// Factor levels at each ticker's last price move.
+    last_factors: HashMap<String, FactorSnapshot>,
+    factors: FactorPaths,
+    rates: QuoteRates,
+    /// Average daily volume and its intraday shape.
+    volume_model: VolumeModel,
+    /// Volume and trade count of each ticker today.
+    daily: DailyTotals,
+    /// Tick size and precision each ticker is quoted with.
+    instruments: Instruments,
+    /// Pending quote deadlines as (schedule milliseconds, index into `tickers`), earliest first.
+    schedule: BinaryHeap<Reverse<(i64, usize)>>,
+    /// Milliseconds since the start of the run of the event being generated.
+    schedule_ms: i64,
+    /// Seed for the random number generator; `None` seeds from the OS.
+    seed: Option<u64>,
+    clock: QuoteClock,
+    /// Trading sessions; `None` quotes around the clock.
+    calendar: Option<MarketCalendar>,
+    /// Last session state announced to subscribers.
+    session: Option<SessionState>,
+    /// Scale of volume and volatility in the current session.
+    activity: f64,
+    /// Halted tickers and the Unix milliseconds their halt ends, if timed.
+    halted: HashMap<String, Option<i64>>,
+    /// Splits and dividends not yet applied, earliest effective time first.
+    corporate_actions: Vec<CorporateActionConfig>,
+    /// Limit-up/limit-down bands; `None` lets prices move freely.
+    price_bands: Option<PriceBands>,
+    /// Band reference price of each ticker and the Unix milliseconds it was taken.
+    references: HashMap<String, (f64, i64)>,
+}
+
+/// Weights of the market, sector and ticker-specific shocks in a ticker's return.
+/// The squared weights sum to one, so correlation leaves the ticker's volatility unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FactorLoadings {
+    market: f64,
+    /// Sector factor index and weight.
+    sector: Option<(usize, f64)>,
+    idiosyncratic: f64,
+}
+
+impl Default for FactorLoadings {
+    fn default() -> Self {
+        Self {
+            market: 0.0,
+            sector: None,
+            idiosyncratic: 1.0,
+        }
+    }
+}
+
+impl FactorLoadings {
+    fn for_ticker(ticker: &str, model: &PriceModel) -> Self {
+        let sector = model
+            .sector_index(ticker)
+            .map(|index| (index, model.sectors[index].correlation));
+        let sector_correlation = sector.map_or(0.0, |(_, correlation)| correlation);
+        Self {
+            market: model.market_correlation.sqrt(),
+            sector: sector.map(|(index, correlation)| (index, correlation.sqrt())),
+            idiosyncratic: (1.0 - model.market_correlation - sector_correlation)
+                .max(0.0)
+                .sqrt(),
+        }
+    }
+}
+
+/// Market and sector factors as cumulative Brownian paths in schedule time (variance
+/// one per millisecond). A ticker's factor shock is the path increment since its last
+/// move, so tickers on different intervals share the moves of the time they overlap.
+#[derive(Debug, Clone, PartialEq)]
+struct FactorPaths {
+    time_ms: i64,
+    market: f64,
+    sectors: Vec<f64>,
+}
+
+/// Factor levels seen by a ticker when it last moved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FactorSnapshot {
+    time_ms: i64,
+    market: f64,
+    sector: f64,
+}
+
+impl FactorPaths {
+    fn new(sector_count: usize) -> Self {
+        Self {
+            time_ms: 0,
+            market: 0.0,
+            sectors: vec![0.0; sector_count],
+        }
+    }
+
+    /// Extend the paths to `time_ms`.
+    fn advance_to(&mut self, time_ms: i64, rng: &mut impl Rng) {
+        let elapsed = time_ms - self.time_ms;
+        if elapsed <= 0 {
+            return;
+        }
+        let scale = (elapsed as f64).sqrt();
+        self.market += scale * standard_normal(rng);
+        for sector in &mut self.sectors {
+            *sector += scale * standard_normal(rng);
+        }
+        self.time_ms = time_ms;
+    }
+
+    fn snapshot(&self, sector: Option<usize>) -> FactorSnapshot {
+        FactorSnapshot {
+            time_ms: self.time_ms,
+            market: self.market,
+            sector: sector
+                .and_then(|index| self.sectors.get(index).copied())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Source of quote timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuoteClock {
+    /// Current UTC time.
+    Wall,
+    /// Follows the quote schedule from a fixed instant, offset by any jumps.
+    Simulated { offset_ms: i64 },
+}
+
+impl QuoteClock {
+    /// Timestamp of the event scheduled `schedule_ms` after the run started.
+    fn at(&self, schedule_ms: i64) -> i64 {
+        match self {
+            QuoteClock::Wall => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as i64)
+                .unwrap_or_default(),
+            QuoteClock::Simulated { offset_ms } => offset_ms + schedule_ms,
+        }
+    }
+
+    /// Make a simulated clock read `timestamp` at `schedule_ms`; the wall clock cannot jump.
+    fn jump(&mut self, schedule_ms: i64, timestamp: i64) {
+        if let QuoteClock::Simulated { offset_ms } = self {
+            *offset_ms = timestamp - schedule_ms;
+        }
+    }
 }
 
 impl QuoteGenerator {
-    /// Create a new generator, seeding prices from configuration or defaults.
+    /// Create a new generator, seeding prices from configuration or defaults. Every
+    /// ticker first quotes at the start of the run.
     pub fn new(
         tickers: Vec<String>,
         initial_prices: &HashMap<String, f64>,
-        quote_rate_ms: Option<u64>,
+        rates: &QuoteRates,
+        price_model: &PriceModel,
+        seed: Option<u64>,
+        simulated_clock_start_ms: Option<i64>,
+        calendar: Option<MarketCalendar>,
     ) -> Self {
         let mut prices = HashMap::with_capacity(tickers.len());
+        let mut params = HashMap::with_capacity(tickers.len());
+        let mut loadings = HashMap::with_capacity(tickers.len());
         for ticker in &tickers {
             let price = initial_prices
                 .get(ticker)
                 .copied()
                 .unwrap_or(DEFAULT_INITIAL_PRICE);
             prices.insert(ticker.into(), price);
+            params.insert(ticker.into(), price_model.params(ticker));
+            loadings.insert(
+                ticker.into(),
+                FactorLoadings::for_ticker(ticker, price_model),
+            );
         }
 
-        let popular = POPULAR_TICKERS.iter().map(|s| s.to_string()).collect();
+        let schedule = (0..tickers.len())
+            .map(|index| Reverse((0, index)))
+            .collect();
 
         Self {
             tickers,
             prices,
-            popular,
-            quote_interval: Duration::from_millis(quote_rate_ms.unwrap_or(DEFAULT_QUOTE_RATE_MS)),
+            params,
+            loadings,
+            last_factors: HashMap::new(),
+            factors: FactorPaths::new(price_model.sectors.len()),
+            rates: rates.clone(),
+            volume_model: VolumeModel::default(),
+            daily: DailyTotals::default(),
+            instruments: Instruments::default(),
+            schedule,
+            schedule_ms: 0,
+            seed,
+            clock: match simulated_clock_start_ms {
+                Some(offset_ms) => QuoteClock::Simulated { offset_ms },
+                None => QuoteClock::Wall,
+            },
+            calendar,
+            session: None,
+            activity: FULL_ACTIVITY,
+            halted: HashMap::new(),
+            corporate_actions: Vec::new(),
+            price_bands: None,
+            references: HashMap::new(),
         }
     }
 
-    fn next_price(&mut self, ticker: &str, rng: &mut impl Rng) -> f64 {
+    /// Quote prices on each ticker's configured tick grid instead of whole cents.
+    pub fn with_instruments(mut self, instruments: &Instruments) -> Self {
+        self.instruments = instruments.clone();
+        self
+    }
+
+    /// Draw trade volumes from `volume_model` instead of the default model.
+    pub fn with_volume_model(mut self, volume_model: &VolumeModel) -> Self {
+        self.volume_model = volume_model.clone();
+        self
+    }
+
+    /// Apply scheduled splits and dividends to the price of their ticker once their
+    /// effective time is reached. Actions for tickers not generated are ignored.
+    pub fn with_corporate_actions(mut self, actions: &[CorporateActionConfig]) -> Self {
+        self.corporate_actions = actions
+            .iter()
+            .filter(|action| {
+                let known = self.prices.contains_key(&action.ticker);
+                if !known {
+                    warn!(
+                        "Ignoring {} for {}, which is not in the tickers file",
+                        action.action, action.ticker
+                    );
+                }
+                known
+            })
+            .cloned()
+            .collect();
+        self.corporate_actions
+            .sort_by_key(|action| action.effective_ms);
+        self
+    }
+
+    /// Halt a ticker for the configured pause when its price moves beyond the band
+    /// around its reference price.
+    pub fn with_price_bands(mut self, price_bands: &PriceBands) -> Self {
+        self.price_bands = Some(price_bands.clone());
+        self
+    }
+
+    /// Standard normal shock for `ticker`, mixing the market and sector moves since its
+    /// last shock with a draw of its own. `None` on a ticker's first quote, which is
+    /// made at its initial price.
+    fn shock(&mut self, ticker: &str, rng: &mut impl Rng) -> Option<f64> {
+        let loadings = self.loadings.get(ticker).copied().unwrap_or_default();
+        let sector_index = loadings.sector.map(|(index, _)| index);
+        self.factors.advance_to(self.schedule_ms, rng);
+        let current = self.factors.snapshot(sector_index);
+        let previous = self.last_factors.insert(ticker.to_string(), current)?;
+
+        // Deadlines of one ticker are at least a millisecond apart.
+        let scale = ((current.time_ms - previous.time_ms).max(1) as f64).sqrt();
+        let market = (current.market - previous.market) / scale;
+        let sector = loadings.sector.map_or(0.0, |(_, weight)| {
+            weight * (current.sector - previous.sector) / scale
+        });
+        Some(loadings.market * market + sector + loadings.idiosyncratic * standard_normal(rng))
+    }
+
+    /// Length of one price step of `ticker` in trading years.
+    fn step_years(&self, ticker: &str) -> f64 {
+        self.rates.interval(ticker).as_millis() as f64 / TRADING_MS_PER_YEAR
+    }
+
+    /// Timestamp of the event being generated.
+    fn now_ms(&self) -> i64 {
+        self.clock.at(self.schedule_ms)
+    }
+
+    fn spec(&self, ticker: &str) -> InstrumentSpec {
+        self.instruments.spec(ticker)
+    }
+
+    /// Current price of `ticker`, rounded to its tick size as quoted.
+    fn quoted_price(&self, ticker: &str) -> f64 {
+        let price = self
+            .prices
+            .get(ticker)
+            .copied()
+            .unwrap_or(DEFAULT_INITIAL_PRICE);
+        self.spec(ticker).round(price)
+    }
+
+    /// Advance `ticker` by one geometric Brownian motion step driven by the standard
+    /// normal `shock` and return the quoted price.
+    fn next_price(&mut self, ticker: &str, shock: f64) -> f64 {
         let current = self
             .prices
             .get(ticker)
             .copied()
             .unwrap_or(DEFAULT_INITIAL_PRICE);
-        let delta = rng.random_range(-0.02..0.02);
-        let updated = (current * (1.0 + delta)).max(0.01);
-        let rounded = (updated * 100.0).round() / 100.0;
-        self.prices.insert(ticker.to_string(), rounded);
-        rounded
+        let params = self.params.get(ticker).copied().unwrap_or_default();
+
+        let dt = self.step_years(ticker);
+        let volatility = params.volatility * self.activity;
+        let log_return =
+            (params.drift - 0.5 * volatility.powi(2)) * dt + volatility * dt.sqrt() * shock;
+        let updated = (current * log_return.exp()).max(self.spec(ticker).tick_size);
+        self.prices.insert(ticker.to_string(), updated);
+        self.quoted_price(ticker)
     }
 
-    fn next_volume(&self, ticker: &str, rng: &mut impl Rng) -> u32 {
-        let (low, high) = if self.popular.contains(ticker) {
-            (1_000, 6_001)
+    /// Simulate the top of the book around the quoted `price`. The spread widens with the
+    /// ticker's volatility, and the bid and ask are rounded outward to whole ticks so
+    /// `bid <= price <= ask` always holds.
+    fn next_book(&self, ticker: &str, price: f64, rng: &mut impl Rng) -> (f64, f64, u32, u32) {
+        let volatility = self
+            .params
+            .get(ticker)
+            .map(|params| params.volatility)
+            .unwrap_or_default();
+        let spread_bps = (MIN_SPREAD_BPS + SPREAD_BPS_PER_VOLATILITY * volatility)
+            * rng.random_range(SPREAD_JITTER);
+        let half_spread = price * spread_bps / BASIS_POINTS / 2.0;
+
+        let spec = self.spec(ticker);
+        let bid = spec.floor(price - half_spread).max(spec.tick_size);
+        let ask = spec
+            .ceil(price + half_spread)
+            .max(spec.round(bid + spec.tick_size));
+
+        let bid_size = rng.random_range(1..=MAX_SIZE_LOTS) * ROUND_LOT;
+        let ask_size = rng.random_range(1..=MAX_SIZE_LOTS) * ROUND_LOT;
+        (bid, ask, bid_size, ask_size)
+    }
+
+    /// Shares in the next trade of `ticker`: its average daily volume spread over the
+    /// trading day at the ticker's quote interval, shaped by the intraday profile during
+    /// regular hours, scaled by session activity and by the size of the price move in
+    /// standard deviations, then randomly jittered.
+    fn next_volume(&self, ticker: &str, move_sigmas: f64, rng: &mut impl Rng) -> u32 {
+        let model = &self.volume_model;
+        let calendar = self.calendar.as_ref();
+        let profile = calendar
+            .and_then(|calendar| calendar.regular_progress(self.now_ms()))
+            .map_or(1.0, |progress| {
+                intraday_profile(progress, model.open_close_ratio)
+            });
+        let share_of_day =
+            self.rates.interval(ticker).as_millis() as f64 / trading_day_ms(calendar) as f64;
+
+        let expected = model.daily_volume(ticker) as f64
+            * share_of_day
+            * profile
+            * self.activity
+            * spike_multiplier(move_sigmas, model.spike_sensitivity);
+        let volume = expected * rng.random_range(VOLUME_JITTER);
+        (volume.round() as u32).max(1)
+    }
+
+    /// Size of the move from `previous` to `price` of `ticker` in standard deviations
+    /// of one price step.
+    fn move_sigmas(&self, ticker: &str, previous: f64, price: f64) -> f64 {
+        let volatility = self
+            .params
+            .get(ticker)
+            .copied()
+            .unwrap_or_default()
+            .volatility;
+        let step_sigma = volatility * self.step_years(ticker).sqrt();
+        let log_move = (price / previous).ln().abs();
+        if log_move == 0.0 {
+            0.0
         } else {
-            (100, 1_101)
-        };
-        rng.random_range(low..high)
-    }
-
-    /// Start generating quotes, sending them via the provided channel sender.
-    pub fn run(mut self, sender: Sender<StockQuote>) {
-        let mut rng = rng();
-        loop {
-            for ticker in self.tickers.clone() {
-                let price = self.next_price(&ticker, &mut rng);
-                let volume = self.next_volume(&ticker, &mut rng);
-                let quote = StockQuote::new(ticker.clone(), price, volume);
-                if sender.send(quote).is_err() {
-                    info!("Quote generator shutting down (no active receivers)");
-                    return;
+            log_move / step_sigma
+        }
+    }
+
+    /// Random number generator for a run: seeded when configured, otherwise from the OS.
+    fn new_rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        }
+    }
+
+    /// Trade `ticker` at `price`, which moved `move_sigmas` standard deviations, with
+    /// freshly drawn volume, then quote a freshly drawn book around it carrying the
+    /// day's totals.
+    fn trade_at(
+        &mut self,
+        ticker: String,
+        price: f64,
+        move_sigmas: f64,
+        timestamp: i64,
+        rng: &mut impl Rng,
+    ) -> [StreamMessage; 2] {
+        let volume = self.next_volume(&ticker, move_sigmas, rng);
+        let (bid, ask, bid_size, ask_size) = self.next_book(&ticker, price, rng);
+        let mut quote = StockQuote {
+            precision: self.spec(&ticker).precision,
+            ticker,
+            price,
+            volume,
+            timestamp,
+            bid,
+            ask,
+            bid_size,
+            ask_size,
+            daily_volume: 0,
+            trade_count: 0,
+            halted: false,
+        };
+        let date = trading_date(timestamp, self.calendar.as_ref());
+        self.daily.record(&mut quote, date);
+        [quote.last_trade().into(), quote.into()]
+    }
+
+    /// Quote of halted `ticker` at its last price, without a trade or a book, marked
+    /// halted and carrying the day's totals so far.
+    fn halted_quote(&self, ticker: String, timestamp: i64) -> StreamMessage {
+        let mut quote = StockQuote {
+            timestamp,
+            precision: self.spec(&ticker).precision,
+            halted: true,
+            ..StockQuote::new(ticker.clone(), self.quoted_price(&ticker), 0)
+        };
+        let date = trading_date(timestamp, self.calendar.as_ref());
+        self.daily.stamp(&mut quote, date);
+        quote.into()
+    }
+
+    /// Take the current price of `ticker` as its band reference when it has none yet
+    /// or the reference is older than the configured interval.
+    fn refresh_reference(&mut self, ticker: &str, now_ms: i64) {
+        let Some(bands) = &self.price_bands else {
+            return;
+        };
+        let interval_ms = bands.reference_interval.as_millis() as i64;
+        let stale = self
+            .references
+            .get(ticker)
+            .is_none_or(|&(_, taken_ms)| now_ms - taken_ms >= interval_ms);
+        if stale {
+            let reference = self.quoted_price(ticker);
+            self.references
+                .insert(ticker.to_string(), (reference, now_ms));
+        }
+    }
+
+    /// Halt `ticker` at the band limit when `price` lies beyond it, returning the halt
+    /// event. The ticker resumes after the configured pause with a fresh reference.
+    fn limit_halt(&mut self, ticker: &str, price: f64, now_ms: i64) -> Option<StreamMessage> {
+        let bands = self.price_bands.as_ref()?;
+        let &(reference, _) = self.references.get(ticker)?;
+        let spec = self.spec(ticker);
+        let width = reference * bands.percent(ticker) / 100.0;
+        let upper = spec.floor(reference + width);
+        let lower = spec.ceil(reference - width).max(spec.tick_size);
+        let (reason, limit) = if price > upper {
+            (HaltReason::LimitUp, upper)
+        } else if price < lower {
+            (HaltReason::LimitDown, lower)
+        } else {
+            return None;
+        };
+        let resume_at = resume_time(now_ms, bands.halt_duration);
+
+        self.prices.insert(ticker.to_string(), limit);
+        self.halted.insert(ticker.to_string(), Some(resume_at));
+        self.references.remove(ticker);
+        info!(
+            "{ticker} reached its {reason} band at {}; halted until {resume_at}",
+            format_price(limit, spec.precision)
+        );
+        Some(
+            MarketEvent {
+                ticker: ticker.to_string(),
+                kind: MarketEventKind::Halt,
+                price: limit,
+                timestamp: now_ms,
+                resume_at: Some(resume_at),
+                reason: Some(reason),
+                precision: spec.precision,
+            }
+            .into(),
+        )
+    }
+
+    /// Schedule milliseconds of the next quote deadline.
+    fn next_due_ms(&self) -> Option<i64> {
+        self.schedule.peek().map(|Reverse((due_ms, _))| *due_ms)
+    }
+
+    /// Move to the next deadline and return the tickers due there, in ticker file order,
+    /// rescheduling each one interval later.
+    fn pop_due(&mut self) -> Vec<String> {
+        let Some(due_ms) = self.next_due_ms() else {
+            return Vec::new();
+        };
+        self.schedule_ms = due_ms;
+
+        let mut due = Vec::new();
+        while let Some(&Reverse((at_ms, index))) = self.schedule.peek()
+            && at_ms == due_ms
+        {
+            self.schedule.pop();
+            let ticker = self.tickers[index].clone();
+            let interval_ms = self.rates.interval(&ticker).as_millis().max(1) as i64;
+            self.schedule.push(Reverse((due_ms + interval_ms, index)));
+            due.push(ticker);
+        }
+        due
+    }
+
+    /// Trade and quote each of `due`, all stamped with the current event time. Halted
+    /// tickers quote their last price marked halted, and a ticker whose price moves
+    /// beyond its band halts at the limit instead of trading.
+    fn quote_due(&mut self, due: Vec<String>, rng: &mut impl Rng) -> Vec<StreamMessage> {
+        let timestamp = self.now_ms();
+        let mut messages = Vec::with_capacity(2 * due.len());
+        for ticker in due {
+            if self.halted.contains_key(&ticker) {
+                messages.push(self.halted_quote(ticker, timestamp));
+                continue;
+            }
+            self.refresh_reference(&ticker, timestamp);
+            let (price, move_sigmas) = match self.shock(&ticker, rng) {
+                Some(shock) => (self.next_price(&ticker, shock), shock.abs()),
+                None => (self.quoted_price(&ticker), 0.0),
+            };
+            if let Some(halt) = self.limit_halt(&ticker, price, timestamp) {
+                messages.push(halt);
+                messages.push(self.halted_quote(ticker, timestamp));
+                continue;
+            }
+            messages.extend(self.trade_at(ticker, price, move_sigmas, timestamp, rng));
+        }
+        messages
+    }
+
+    /// Apply an admin command, returning the event it raises and, for price changes
+    /// while the ticker trades, a trade and quote at the new price.
+    fn apply_admin(&mut self, command: AdminCommand, rng: &mut impl Rng) -> Vec<StreamMessage> {
+        let ticker = command.ticker().to_string();
+        let Some(&current) = self.prices.get(&ticker) else {
+            warn!("Ignoring admin command for unknown ticker {ticker}");
+            return Vec::new();
+        };
+        let timestamp = self.now_ms();
+        let spec = self.spec(&ticker);
+
+        let (kind, resume_at) = match command {
+            AdminCommand::Shock { percent, .. } => {
+                let shocked = (current * (1.0 + percent / 100.0)).max(spec.tick_size);
+                self.prices.insert(ticker.clone(), shocked);
+                self.references.remove(&ticker);
+                (MarketEventKind::Shock, None)
+            }
+            AdminCommand::SetPrice { price, .. } => {
+                self.prices
+                    .insert(ticker.clone(), price.max(spec.tick_size));
+                self.references.remove(&ticker);
+                (MarketEventKind::PriceSet, None)
+            }
+            AdminCommand::Halt { duration, .. } => {
+                let resume_at = duration.map(|duration| resume_time(timestamp, duration));
+                self.halted.insert(ticker.clone(), resume_at);
+                (MarketEventKind::Halt, resume_at)
+            }
+            AdminCommand::Resume { .. } => {
+                if self.halted.remove(&ticker).is_none() {
+                    warn!("Ignoring RESUME for {ticker}, which is not halted");
+                    return Vec::new();
                 }
+                self.references.remove(&ticker);
+                (MarketEventKind::Resume, None)
             }
-            thread::sleep(self.quote_interval);
+        };
+
+        let price = self.quoted_price(&ticker);
+        info!(
+            "Admin {kind} for {ticker}; price is now {}",
+            format_price(price, spec.precision)
+        );
+        let mut messages = vec![StreamMessage::from(MarketEvent {
+            ticker: ticker.clone(),
+            kind,
+            price,
+            timestamp,
+            resume_at,
+            reason: None,
+            precision: spec.precision,
+        })];
+        let trading =
+            !self.halted.contains_key(&ticker) && self.session != Some(SessionState::Closed);
+        if trading && matches!(kind, MarketEventKind::Shock | MarketEventKind::PriceSet) {
+            let move_sigmas = self.move_sigmas(&ticker, current, price);
+            messages.extend(self.trade_at(ticker, price, move_sigmas, timestamp, rng));
         }
+        messages
+    }
+
+    /// End the halts whose time is up, returning a resume event for each.
+    fn resume_expired_halts(&mut self, now_ms: i64) -> Vec<StreamMessage> {
+        let mut expired: Vec<String> = self
+            .halted
+            .iter()
+            .filter(|(_, resume_at)| resume_at.is_some_and(|resume_at| resume_at <= now_ms))
+            .map(|(ticker, _)| ticker.clone())
+            .collect();
+        expired.sort();
+
+        expired
+            .into_iter()
+            .map(|ticker| {
+                self.halted.remove(&ticker);
+                self.references.remove(&ticker);
+                info!("Halt of {ticker} expired; quoting resumes");
+                MarketEvent {
+                    price: self.quoted_price(&ticker),
+                    precision: self.spec(&ticker).precision,
+                    ticker,
+                    kind: MarketEventKind::Resume,
+                    timestamp: now_ms,
+                    resume_at: None,
+                    reason: None,
+                }
+                .into()
+            })
+            .collect()
+    }
+
+    /// Apply the corporate actions effective by `now_ms` in order, returning an
+    /// announcement of each. Prices stay at least one tick, even after a dividend
+    /// larger than the price.
+    fn apply_corporate_actions(&mut self, now_ms: i64) -> Vec<StreamMessage> {
+        let due = self
+            .corporate_actions
+            .iter()
+            .take_while(|action| action.effective_ms <= now_ms)
+            .count();
+        let due: Vec<CorporateActionConfig> = self.corporate_actions.drain(..due).collect();
+
+        due.into_iter()
+            .map(|scheduled| {
+                let ticker = scheduled.ticker;
+                let spec = self.spec(&ticker);
+                let previous_price = self.quoted_price(&ticker);
+                let current = self
+                    .prices
+                    .get(&ticker)
+                    .copied()
+                    .unwrap_or(DEFAULT_INITIAL_PRICE);
+                let adjusted = scheduled.action.adjust(current).max(spec.tick_size);
+                self.prices.insert(ticker.clone(), adjusted);
+                self.references.remove(&ticker);
+                let price = self.quoted_price(&ticker);
+                info!(
+                    "{} for {ticker}; price {} -> {}",
+                    scheduled.action,
+                    format_price(previous_price, spec.precision),
+                    format_price(price, spec.precision)
+                );
+                CorporateAction {
+                    ticker,
+                    action: scheduled.action,
+                    previous_price,
+                    price,
+                    timestamp: now_ms,
+                    precision: spec.precision,
+                }
+                .into()
+            })
+            .collect()
+    }
+
+    /// Produce the messages of the next deadline: resumes of expired halts, corporate
+    /// actions taking effect, a session event when the market changes state, then a
+    /// trade and quote for each due ticker that trades unless the market is closed.
+    fn next_messages(&mut self, rng: &mut impl Rng) -> Vec<StreamMessage> {
+        let due = self.pop_due();
+        let now_ms = self.now_ms();
+        let mut messages = self.resume_expired_halts(now_ms);
+        messages.extend(self.apply_corporate_actions(now_ms));
+        let Some(calendar) = &self.calendar else {
+            messages.extend(self.quote_due(due, rng));
+            return messages;
+        };
+
+        let state = calendar.state_at(now_ms);
+        let activity = calendar.activity(state);
+        let next_open = match state {
+            SessionState::Closed => calendar.next_open_after(now_ms),
+            _ => None,
+        };
+
+        if self.session != Some(state) {
+            info!("Market session is now {state}");
+            self.session = Some(state);
+            messages.push(
+                SessionEvent {
+                    state,
+                    timestamp: now_ms,
+                }
+                .into(),
+            );
+        }
+
+        if state == SessionState::Closed {
+            // A simulated clock skips the closed hours: the next deadline reads as the open.
+            if let (Some(open_ms), Some(next_due_ms)) = (next_open, self.next_due_ms()) {
+                self.clock.jump(next_due_ms, open_ms);
+            }
+            return messages;
+        }
+
+        self.activity = activity;
+        messages.extend(self.quote_due(due, rng));
+        messages
     }
 }
 
-/// Spawn a generator thread and return the receiving side for consumers.
-pub fn start_generator(
-    tickers: Vec<String>,
-    initial_prices: HashMap<String, f64>,
-    quote_rate_ms: Option<u64>,
-) -> Result<(Receiver<StockQuote>, thread::JoinHandle<()>), QuoteError> {
-    let generator = QuoteGenerator::new(tickers, &initial_prices, quote_rate_ms);
-    let (sender, receiver) = channel::unbounded();
-    let handle = thread::Builder::new()
-        .name("quote-generator".to_string())
-        .spawn(move || generator.run(sender))
-        .map_err(|err| {
-            quote_common::quote_error!(IoError, err, "failed to spawn quote generator thread")
-        })?;
-
-    Ok((receiver, handle))
+impl QuoteSource for QuoteGenerator {
+    /// Generate quotes as each ticker's deadline comes up, applying admin commands as
+    /// they arrive in between. Deadlines are fixed offsets from the start of the run, so
+    /// time spent generating never delays later quotes.
+    fn run(mut self: Box<Self>, sender: Sender<StreamMessage>, admin_rx: Receiver<AdminCommand>) {
+        let mut rng = self.new_rng();
+        let started = Instant::now();
+        'events: while let Some(due_ms) = self.next_due_ms() {
+            let deadline = started + Duration::from_millis(due_ms as u64);
+            loop {
+                match admin_rx.recv_deadline(deadline) {
+                    Ok(command) => {
+                        if !send_all(&sender, self.apply_admin(command, &mut rng)) {
+                            break 'events;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => break 'events,
+                }
+            }
+
+            if !send_all(&sender, self.next_messages(&mut rng)) {
+                break;
+            }
+        }
+        info!("Quote generator shutting down");
+    }
+}
+
+/// Draw a standard normal sample with the Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // `random` yields [0, 1); flip it so the logarithm never sees zero.
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random();
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// Unix milliseconds `duration` after `now_ms`, saturating at the end of time rather
+/// than overflowing.
+fn resume_time(now_ms: i64, duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).map_or(i64::MAX, |ms| now_ms.saturating_add(ms))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::SeedableRng;
-    use rand::rngs::StdRng;
+    use crate::config::Sector;
+    use crate::source::start_source;
+    use chrono::{NaiveDate, NaiveTime, TimeZone};
+    use quote_common::{CorporateActionKind, DEFAULT_TICK_SIZE};
+    use std::collections::HashSet;
+
+    fn uniform_rates(interval_ms: u64) -> QuoteRates {
+        QuoteRates {
+            default_ms: interval_ms,
+            ..QuoteRates::default()
+        }
+    }
+
+    /// New York sessions with the 2024 Independence Day holiday.
+    fn new_york_calendar(extended_hours_activity: f64) -> MarketCalendar {
+        MarketCalendar {
+            timezone: chrono_tz::America::New_York,
+            pre_market_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            regular_open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            regular_close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            after_hours_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            holidays: HashSet::from([NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()]),
+            extended_hours_activity,
+        }
+    }
+
+    /// Quotes of the next deadline.
+    fn next_quotes(generator: &mut QuoteGenerator, rng: &mut impl Rng) -> Vec<StockQuote> {
+        generator
+            .next_messages(rng)
+            .into_iter()
+            .filter_map(|message| match message {
+                StreamMessage::Quote(quote) => Some(quote),
+                _ => None,
+            })
+            .collect()
+    }
 
     #[test]
     fn test_next_price_within_bounds() {
         let tickers = vec!["AAPL".to_string()];
-        let mut generator = QuoteGenerator::new(tickers.clone(), &HashMap::new(), None);
+        let mut generator = QuoteGenerator::new(
+            tickers.clone(),
+            &HashMap::new(),
+            &QuoteRates::default(),
+            &PriceModel::default(),
+            None,
+            None,
+            None,
+        );
         let mut rng = StdRng::seed_from_u64(42);
 
         for _ in 0..50 {
-            let price = generator.next_price("AAPL", &mut rng);
+            let price = generator.next_price("AAPL", standard_normal(&mut rng));
             assert!(price >= 0.01);
         }
     }
 
     #[test]
-    fn test_volume_ranges() {
-        let tickers = vec!["AAPL".to_string(), "XYZ".to_string()];
-        let generator = QuoteGenerator::new(tickers.clone(), &HashMap::new(), None);
+    fn test_next_price_log_returns_match_configured_volatility() {
+        let tickers = vec!["TSLA".to_string(), "DUK".to_string()];
+        let mut model = PriceModel::default();
+        model.tickers.insert(
+            "TSLA".to_string(),
+            PriceParams {
+                drift: 0.0,
+                volatility: 0.6,
+            },
+        );
+        model.tickers.insert(
+            "DUK".to_string(),
+            PriceParams {
+                drift: 0.0,
+                volatility: 0.0,
+            },
+        );
+        let mut generator = QuoteGenerator::new(
+            tickers,
+            &HashMap::new(),
+            &uniform_rates(60_000),
+            &model,
+            None,
+            None,
+            None,
+        );
+        let mut rng = StdRng::seed_from_u64(11);
+
+        const STEPS: usize = 10_000;
+        let mut previous = generator.prices["TSLA"];
+        let mut sum_sq = 0.0;
+        for _ in 0..STEPS {
+            generator.next_price("TSLA", standard_normal(&mut rng));
+            let current = generator.prices["TSLA"];
+            sum_sq += (current / previous).ln().powi(2);
+            previous = current;
+        }
+        let realized = (sum_sq / STEPS as f64).sqrt();
+        let expected = 0.6 * generator.step_years("TSLA").sqrt();
+        assert!(
+            (realized / expected - 1.0).abs() < 0.05,
+            "realized {realized} vs expected {expected}"
+        );
+
+        for _ in 0..100 {
+            assert_eq!(
+                generator.next_price("DUK", standard_normal(&mut rng)),
+                DEFAULT_INITIAL_PRICE
+            );
+        }
+    }
+
+    #[test]
+    fn test_seeded_generator_with_simulated_clock_is_reproducible() {
+        const START_MS: i64 = 1_700_000_000_000;
+        let tickers = vec!["AAPL".to_string(), "MSFT".to_string(), "XYZ".to_string()];
+        let session = |seed: u64| {
+            let mut generator = QuoteGenerator::new(
+                tickers.clone(),
+                &HashMap::new(),
+                &uniform_rates(250),
+                &PriceModel::default(),
+                Some(seed),
+                Some(START_MS),
+                None,
+            );
+            let mut rng = generator.new_rng();
+            (0..20)
+                .flat_map(|_| next_quotes(&mut generator, &mut rng))
+                .map(|quote| serde_json::to_string(&quote).expect("serialize"))
+                .collect::<Vec<_>>()
+        };
+
+        let first = session(7);
+        assert_eq!(first, session(7));
+        assert_ne!(first, session(8));
+
+        let quotes: Vec<StockQuote> = first
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("deserialize"))
+            .collect();
+        assert_eq!(quotes[0].timestamp, START_MS);
+        assert_eq!(quotes[2].timestamp, START_MS);
+        assert_eq!(quotes[3].timestamp, START_MS + 250);
+        assert_eq!(quotes[3].ticker, "AAPL");
+    }
+
+    #[test]
+    fn test_sector_tickers_move_together() {
+        let tickers = vec!["NVDA".to_string(), "AVGO".to_string(), "XOM".to_string()];
+        let model = PriceModel {
+            market_correlation: 0.3,
+            sectors: vec![Sector {
+                name: "semiconductors".to_string(),
+                correlation: 0.5,
+                tickers: vec!["NVDA".to_string(), "AVGO".to_string()],
+            }],
+            ..PriceModel::default()
+        };
+        let mut generator = QuoteGenerator::new(
+            tickers,
+            &HashMap::new(),
+            &QuoteRates::default(),
+            &model,
+            Some(5),
+            None,
+            None,
+        );
+        let mut rng = generator.new_rng();
+
+        let mut returns: HashMap<&str, Vec<f64>> = HashMap::new();
+        for _ in 0..5_000 {
+            let before = generator.prices.clone();
+            next_quotes(&mut generator, &mut rng);
+            for ticker in ["NVDA", "AVGO", "XOM"] {
+                returns
+                    .entry(ticker)
+                    .or_default()
+                    .push((generator.prices[ticker] / before[ticker]).ln());
+            }
+        }
+
+        let in_sector = correlation(&returns["NVDA"], &returns["AVGO"]);
+        let across = correlation(&returns["NVDA"], &returns["XOM"]);
+        assert!((in_sector - 0.8).abs() < 0.05, "in-sector {in_sector}");
+        assert!((across - 0.3).abs() < 0.05, "cross-sector {across}");
+    }
+
+    #[test]
+    fn test_tickers_quote_on_their_own_intervals() {
+        const START_MS: i64 = 1_700_000_000_000;
+        let rates = QuoteRates {
+            default_ms: 200,
+            tickers: HashMap::from([("FAST".to_string(), 50)]),
+        };
+        let model = PriceModel {
+            market_correlation: 0.5,
+            ..PriceModel::default()
+        };
+        let mut generator = QuoteGenerator::new(
+            vec!["FAST".to_string(), "SLOW".to_string()],
+            &HashMap::new(),
+            &rates,
+            &model,
+            Some(21),
+            Some(START_MS),
+            None,
+        );
+        let mut rng = generator.new_rng();
+
+        let stamps: Vec<(String, i64)> = (0..6)
+            .flat_map(|_| next_quotes(&mut generator, &mut rng))
+            .map(|quote| (quote.ticker, quote.timestamp - START_MS))
+            .collect();
+        let expected = [
+            ("FAST", 0),
+            ("SLOW", 0),
+            ("FAST", 50),
+            ("FAST", 100),
+            ("FAST", 150),
+            ("FAST", 200),
+            ("SLOW", 200),
+            ("FAST", 250),
+        ];
+        assert_eq!(
+            stamps,
+            expected.map(|(ticker, ms)| (ticker.to_string(), ms))
+        );
+
+        // Both tickers see the same market moves over each slow interval.
+        let mut fast_returns = Vec::new();
+        let mut slow_returns = Vec::new();
+        let mut previous = (generator.prices["FAST"], generator.prices["SLOW"]);
+        while slow_returns.len() < 4_000 {
+            let quotes = next_quotes(&mut generator, &mut rng);
+            if quotes.iter().any(|quote| quote.ticker == "SLOW") {
+                let current = (generator.prices["FAST"], generator.prices["SLOW"]);
+                fast_returns.push((current.0 / previous.0).ln());
+                slow_returns.push((current.1 / previous.1).ln());
+                previous = current;
+            }
+        }
+        let across_rates = correlation(&fast_returns, &slow_returns);
+        assert!(
+            (across_rates - 0.5).abs() < 0.06,
+            "correlation {across_rates}"
+        );
+    }
+
+    fn correlation(xs: &[f64], ys: &[f64]) -> f64 {
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        let (mean_x, mean_y) = (mean(xs), mean(ys));
+        let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+        for (x, y) in xs.iter().zip(ys) {
+            cov += (x - mean_x) * (y - mean_y);
+            var_x += (x - mean_x).powi(2);
+            var_y += (y - mean_y).powi(2);
+        }
+        cov / (var_x * var_y).sqrt()
+    }
+
+    #[test]
+    fn test_book_brackets_price_and_widens_with_volatility() {
+        let tickers = vec!["TSLA".to_string(), "DUK".to_string()];
+        let mut model = PriceModel::default();
+        for (ticker, volatility) in [("TSLA", 0.8), ("DUK", 0.1)] {
+            model.tickers.insert(
+                ticker.to_string(),
+                PriceParams {
+                    drift: 0.0,
+                    volatility,
+                },
+            );
+        }
+        let mut generator = QuoteGenerator::new(
+            tickers,
+            &HashMap::new(),
+            &QuoteRates::default(),
+            &model,
+            Some(3),
+            None,
+            None,
+        );
+        let mut rng = generator.new_rng();
+
+        let mut total_spread = HashMap::<String, f64>::new();
+        for _ in 0..200 {
+            for quote in next_quotes(&mut generator, &mut rng) {
+                assert!(quote.bid <= quote.price && quote.price <= quote.ask);
+                assert!(quote.ask - quote.bid >= DEFAULT_TICK_SIZE - f64::EPSILON);
+                assert_eq!(quote.bid_size % ROUND_LOT, 0);
+                assert!((ROUND_LOT..=MAX_SIZE_LOTS * ROUND_LOT).contains(&quote.ask_size));
+                *total_spread.entry(quote.ticker.clone()).or_default() += quote.ask - quote.bid;
+            }
+        }
+        assert!(total_spread["TSLA"] > total_spread["DUK"]);
+    }
+
+    #[test]
+    fn test_quotes_follow_instrument_tick_sizes() {
+        let spec = |tick_size, precision| InstrumentSpec {
+            tick_size,
+            precision,
+        };
+        let instruments = Instruments {
+            default: spec(0.05, 2),
+            tickers: HashMap::from([
+                ("EURUSD".to_string(), spec(0.000_01, 5)),
+                ("PENNY".to_string(), spec(0.000_1, 4)),
+            ]),
+        };
+        let mut generator = QuoteGenerator::new(
+            vec!["EURUSD".to_string(), "PENNY".to_string(), "XYZ".to_string()],
+            &HashMap::from([
+                ("EURUSD".to_string(), 1.085_43),
+                ("PENNY".to_string(), 0.000_4),
+            ]),
+            &QuoteRates::default(),
+            &PriceModel::default(),
+            Some(17),
+            None,
+            None,
+        )
+        .with_instruments(&instruments);
+        let mut rng = generator.new_rng();
+
+        let on_grid = |price: f64, tick_size: f64| {
+            let ticks = price / tick_size;
+            (ticks - ticks.round()).abs() < 1e-6
+        };
+        for _ in 0..200 {
+            for quote in next_quotes(&mut generator, &mut rng) {
+                let spec = instruments.spec(&quote.ticker);
+                assert_eq!(quote.precision, spec.precision);
+                for price in [quote.price, quote.bid, quote.ask] {
+                    assert!(price >= spec.tick_size, "{quote:?}");
+                    assert!(on_grid(price, spec.tick_size), "{quote:?}");
+                }
+                assert!(quote.bid <= quote.price && quote.price <= quote.ask);
+                assert!(quote.ask - quote.bid >= spec.tick_size * (1.0 - 1e-9));
+            }
+        }
+        assert!(generator.quoted_price("PENNY") < DEFAULT_TICK_SIZE);
+    }
+
+    #[test]
+    fn test_calendar_announces_sessions_and_skips_closed_hours() {
+        let new_york = chrono_tz::America::New_York;
+        let at = |day: u32, hour: u32, min: u32, sec: u32| {
+            new_york
+                .with_ymd_and_hms(2024, 7, day, hour, min, sec)
+                .single()
+                .expect("unambiguous local time")
+                .timestamp_millis()
+        };
+        let calendar = new_york_calendar(0.1);
+        let mut generator = QuoteGenerator::new(
+            vec!["AAPL".to_string()],
+            &HashMap::new(),
+            &uniform_rates(1_000),
+            &PriceModel::default(),
+            Some(1),
+            Some(at(3, 19, 59, 59)),
+            Some(calendar),
+        );
+        let mut rng = generator.new_rng();
+        let session = |state, timestamp| StreamMessage::Session(SessionEvent { state, timestamp });
+
+        let messages = generator.next_messages(&mut rng);
+        assert_eq!(
+            messages[0],
+            session(SessionState::AfterHours, at(3, 19, 59, 59))
+        );
+        let StreamMessage::Trade(trade) = &messages[1] else {
+            panic!("expected a trade, got {:?}", messages[1]);
+        };
+        assert!(trade.volume <= 601, "after-hours volume is reduced");
+        assert!(
+            matches!(&messages[2], StreamMessage::Quote(quote) if quote.volume == trade.volume)
+        );
+
+        assert_eq!(
+            generator.next_messages(&mut rng),
+            vec![session(SessionState::Closed, at(3, 20, 0, 0))]
+        );
+
+        // The holiday is skipped and quoting resumes at the next pre-market open.
+        let messages = generator.next_messages(&mut rng);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0],
+            session(SessionState::PreMarket, at(5, 4, 0, 0))
+        );
+        assert!(
+            matches!(&messages[1], StreamMessage::Trade(trade) if trade.timestamp == at(5, 4, 0, 0))
+        );
+        assert!(
+            matches!(&messages[2], StreamMessage::Quote(quote) if quote.timestamp == at(5, 4, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_price_band_breach_halts_ticker_at_limit() {
+        const START_MS: i64 = 1_700_000_000_000;
+        let model = PriceModel {
+            default: PriceParams {
+                drift: 0.0,
+                volatility: 10.0,
+            },
+            ..PriceModel::default()
+        };
+        let bands = PriceBands {
+            percent: 0.5,
+            reference_interval: Duration::from_secs(60),
+            halt_duration: Duration::from_secs(3),
+            ..PriceBands::default()
+        };
+        let mut generator = QuoteGenerator::new(
+            vec!["AAPL".to_string()],
+            &HashMap::from([("AAPL".to_string(), 100.0)]),
+            &uniform_rates(1_000),
+            &model,
+            Some(11),
+            Some(START_MS),
+            None,
+        )
+        .with_price_bands(&bands);
+        let mut rng = generator.new_rng();
+
+        // Prices move freely within 0.5% of the first price until one breaches a limit.
+        let (halt, messages) = (0..50)
+            .find_map(|_| {
+                let messages = generator.next_messages(&mut rng);
+                match messages.first() {
+                    Some(StreamMessage::Event(event)) => Some((event.clone(), messages)),
+                    _ => {
+                        assert!(matches!(&messages[..], [StreamMessage::Trade(_), StreamMessage::Quote(quote)] if !quote.halted));
+                        None
+                    }
+                }
+            })
+            .expect("a 0.5% band is breached within 50 volatile steps");
+        assert_eq!(halt.kind, MarketEventKind::Halt);
+        let expected_limit = match halt.reason {
+            Some(HaltReason::LimitUp) => 100.5,
+            Some(HaltReason::LimitDown) => 99.5,
+            None => panic!("a band halt carries its reason"),
+        };
+        assert_eq!(halt.price, expected_limit);
+        assert_eq!(halt.resume_at, Some(halt.timestamp + 3_000));
+        let [_, StreamMessage::Quote(quote)] = &messages[..] else {
+            panic!("expected the halt and a halted quote, got {messages:?}");
+        };
+        assert!(quote.halted);
+        assert_eq!((quote.price, quote.volume), (expected_limit, 0));
+
+        for _ in 0..2 {
+            let messages = generator.next_messages(&mut rng);
+            assert!(
+                matches!(&messages[..], [StreamMessage::Quote(quote)] if quote.halted && quote.price == expected_limit),
+                "expected a halted quote, got {messages:?}"
+            );
+        }
+
+        // Trading resumes once the pause is over, against a reference at the limit.
+        let messages = generator.next_messages(&mut rng);
+        assert!(matches!(
+            &messages[0],
+            StreamMessage::Event(event) if event.kind == MarketEventKind::Resume && event.timestamp == halt.timestamp + 3_000
+        ));
+        assert!(messages.iter().any(|message| matches!(message, StreamMessage::Trade(_))
+            || matches!(message, StreamMessage::Event(event) if event.kind == MarketEventKind::Halt)));
+    }
+
+    #[test]
+    fn test_corporate_actions_adjust_prices_once_effective() {
+        const START_MS: i64 = 1_700_000_000_000;
+        let scheduled = |ticker: &str, action: CorporateActionKind| CorporateActionConfig {
+            ticker: ticker.to_string(),
+            action,
+            effective_ms: START_MS + 500,
+        };
+        let mut generator = QuoteGenerator::new(
+            vec!["AAPL".to_string(), "MSFT".to_string()],
+            &HashMap::from([("AAPL".to_string(), 600.0), ("MSFT".to_string(), 400.0)]),
+            &uniform_rates(1_000),
+            &PriceModel::default(),
+            Some(5),
+            Some(START_MS),
+            None,
+        )
+        .with_corporate_actions(&[
+            scheduled("MSFT", CorporateActionKind::Dividend { amount: 2.5 }),
+            scheduled(
+                "AAPL",
+                CorporateActionKind::Split {
+                    new_shares: 4,
+                    old_shares: 1,
+                },
+            ),
+            scheduled("ZZZZ", CorporateActionKind::Dividend { amount: 1.0 }),
+        ]);
+        let mut rng = generator.new_rng();
+
+        // Nothing is effective at the first deadline, made at the initial prices.
+        let messages = generator.next_messages(&mut rng);
+        assert!(
+            messages
+                .iter()
+                .all(|message| !matches!(message, StreamMessage::CorporateAction(_)))
+        );
+
+        // Both actions apply at the first deadline after their effective time, in file
+        // order, before the quotes they affect.
+        let messages = generator.next_messages(&mut rng);
+        let actions: Vec<(&str, f64, f64, i64)> = messages
+            .iter()
+            .filter_map(|message| match message {
+                StreamMessage::CorporateAction(action) => Some((
+                    action.ticker.as_str(),
+                    action.previous_price,
+                    action.price,
+                    action.timestamp,
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("MSFT", 400.0, 397.5, START_MS + 1_000),
+                ("AAPL", 600.0, 150.0, START_MS + 1_000),
+            ]
+        );
+        assert!(matches!(messages[0], StreamMessage::CorporateAction(_)));
+        for quote in messages.iter().filter_map(|message| match message {
+            StreamMessage::Quote(quote) => Some(quote),
+            _ => None,
+        }) {
+            let expected = if quote.ticker == "AAPL" { 150.0 } else { 397.5 };
+            assert!(
+                (quote.price / expected - 1.0).abs() < 0.05,
+                "{} quoted {} after the action",
+                quote.ticker,
+                quote.price
+            );
+        }
+
+        assert!(generator.corporate_actions.is_empty());
+    }
+
+    #[test]
+    fn test_admin_commands_shock_and_halt_tickers() {
+        const START_MS: i64 = 1_700_000_000_000;
+        let mut generator = QuoteGenerator::new(
+            vec!["AAPL".to_string(), "TSLA".to_string()],
+            &HashMap::from([("TSLA".to_string(), 200.0)]),
+            &uniform_rates(1_000),
+            &PriceModel::default(),
+            Some(9),
+            Some(START_MS),
+            None,
+        );
+        let mut rng = generator.new_rng();
+
+        let messages = generator.apply_admin(
+            AdminCommand::Shock {
+                ticker: "TSLA".to_string(),
+                percent: -15.0,
+            },
+            &mut rng,
+        );
+        let [
+            StreamMessage::Event(event),
+            StreamMessage::Trade(trade),
+            StreamMessage::Quote(quote),
+        ] = messages.as_slice()
+        else {
+            panic!("expected an event, a trade and a quote, got {messages:?}");
+        };
+        assert_eq!(event.kind, MarketEventKind::Shock);
+        assert_eq!(
+            (event.price, trade.price, quote.price),
+            (170.0, 170.0, 170.0)
+        );
+
+        let messages = generator.apply_admin(
+            AdminCommand::Halt {
+                ticker: "AAPL".to_string(),
+                duration: Some(Duration::from_secs(2)),
+            },
+            &mut rng,
+        );
+        assert!(
+            matches!(&messages[..], [StreamMessage::Event(event)] if event.resume_at == Some(START_MS + 2_000))
+        );
+
+        let quoted = |messages: &[StreamMessage]| -> Vec<String> {
+            messages
+                .iter()
+                .filter_map(|message| match message {
+                    StreamMessage::Quote(quote) if quote.halted => {
+                        Some(format!("{} halted", quote.ticker))
+                    }
+                    StreamMessage::Quote(quote) => Some(quote.ticker.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let traded = |messages: &[StreamMessage]| -> Vec<String> {
+            messages
+                .iter()
+                .filter_map(|message| match message {
+                    StreamMessage::Trade(trade) => Some(trade.ticker.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        // A halted ticker keeps quoting its last price, marked halted, without trading.
+        for _ in 0..2 {
+            let messages = generator.next_messages(&mut rng);
+            assert_eq!(quoted(&messages), vec!["AAPL halted", "TSLA"]);
+            assert_eq!(traded(&messages), vec!["TSLA"]);
+        }
+
+        // The halt expires on the cycle stamped two seconds after it started.
+        let messages = generator.next_messages(&mut rng);
+        assert!(matches!(
+            &messages[0],
+            StreamMessage::Event(event) if event.kind == MarketEventKind::Resume && event.ticker == "AAPL"
+        ));
+        assert_eq!(quoted(&messages), vec!["AAPL", "TSLA"]);
+
+        assert!(
+            generator
+                .apply_admin(
+                    AdminCommand::Resume {
+                        ticker: "AAPL".to_string()
+                    },
+                    &mut rng
+                )
+                .is_empty(),
+            "resuming a trading ticker is a no-op"
+        );
+
+        // A halt too long to represent never ends on its own rather than ending at once.
+        let messages = generator.apply_admin(
+            AdminCommand::Halt {
+                ticker: "AAPL".to_string(),
+                duration: Some(Duration::MAX),
+            },
+            &mut rng,
+        );
+        assert!(
+            matches!(&messages[..], [StreamMessage::Event(event)] if event.resume_at == Some(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_volume_follows_daily_volume_profile_and_price_moves() {
+        // 23.4M shares over the 390-minute session is one share per millisecond.
+        let model = VolumeModel {
+            tickers: HashMap::from([("AAPL".to_string(), 23_400_000)]),
+            spike_sensitivity: 0.0,
+            ..VolumeModel::default()
+        };
+        let at = |hour: u32, min: u32| {
+            chrono_tz::America::New_York
+                .with_ymd_and_hms(2024, 7, 3, hour, min, 0)
+                .single()
+                .expect("unambiguous local time")
+                .timestamp_millis()
+        };
+        let generator = |start_ms: i64, model: &VolumeModel| {
+            QuoteGenerator::new(
+                vec!["AAPL".to_string(), "XYZ".to_string()],
+                &HashMap::new(),
+                &uniform_rates(1_000),
+                &PriceModel::default(),
+                None,
+                Some(start_ms),
+                Some(new_york_calendar(0.25)),
+            )
+            .with_volume_model(model)
+        };
         let mut rng = StdRng::seed_from_u64(7);
+        let mut mean_volume = |generator: &QuoteGenerator, ticker: &str, sigmas: f64| {
+            let draws = 2_000;
+            (0..draws)
+                .map(|_| f64::from(generator.next_volume(ticker, sigmas, &mut rng)))
+                .sum::<f64>()
+                / f64::from(draws)
+        };
+
+        // With the default 3x open/close ratio, midday trades 0.6x and the open 1.8x the
+        // session's average rate of 1000 shares per one-second quote.
+        let midday = generator(at(12, 45), &model);
+        let open = generator(at(9, 30), &model);
+        let midday_mean = mean_volume(&midday, "AAPL", 0.0);
+        assert!((midday_mean / 600.0 - 1.0).abs() < 0.03, "{midday_mean}");
+        let open_mean = mean_volume(&open, "AAPL", 0.0);
+        assert!((open_mean / 1_800.0 - 1.0).abs() < 0.03, "{open_mean}");
+        let default_mean = mean_volume(&midday, "XYZ", 0.0);
+        assert!(
+            (default_mean / (600.0 * 10.0 / 23.4) - 1.0).abs() < 0.03,
+            "{default_mean}"
+        );
+
+        // Larger price moves trade more shares.
+        let spiky = generator(
+            at(12, 45),
+            &VolumeModel {
+                spike_sensitivity: 1.0,
+                ..model.clone()
+            },
+        );
+        let ratio = mean_volume(&spiky, "AAPL", 4.0) / mean_volume(&spiky, "AAPL", 0.0);
+        assert!((ratio / 5.0 - 1.0).abs() < 0.05, "spike ratio {ratio}");
+    }
 
-        let popular_volume = generator.next_volume("AAPL", &mut rng);
-        assert!((1_000..6_001).contains(&popular_volume));
+    #[test]
+    fn test_quotes_carry_daily_volume_and_trade_count() {
+        let mut generator = QuoteGenerator::new(
+            vec!["AAPL".to_string()],
+            &HashMap::new(),
+            &uniform_rates(60 * 60 * 1_000),
+            &PriceModel::default(),
+            Some(3),
+            // 2024-07-01 22:00 UTC: the third quote falls on the next UTC day.
+            Some(1_719_871_200_000),
+            None,
+        );
+        let mut rng = generator.new_rng();
 
-        let regular_volume = generator.next_volume("XYZ", &mut rng);
-        assert!((100..1_101).contains(&regular_volume));
+        let first = next_quotes(&mut generator, &mut rng).remove(0);
+        let second = next_quotes(&mut generator, &mut rng).remove(0);
+        let third = next_quotes(&mut generator, &mut rng).remove(0);
+        assert_eq!(
+            (first.daily_volume, first.trade_count),
+            (u64::from(first.volume), 1)
+        );
+        assert_eq!(
+            (second.daily_volume, second.trade_count),
+            (u64::from(first.volume + second.volume), 2)
+        );
+        assert_eq!(
+            (third.daily_volume, third.trade_count),
+            (u64::from(third.volume), 1)
+        );
     }
 
     #[test]
-    fn test_start_generator_returns_receiver() {
+    fn test_start_source_runs_generator_until_admin_channel_closes() {
         let tickers = vec!["AAPL".to_string(), "TSLA".to_string()];
-        let (receiver, handle) =
-            start_generator(tickers.clone(), HashMap::new(), Some(5)).expect("start generator");
-        let received: Vec<StockQuote> = receiver.iter().take(4).collect();
+        let generator = QuoteGenerator::new(
+            tickers.clone(),
+            &HashMap::new(),
+            &uniform_rates(5),
+            &PriceModel::default(),
+            None,
+            None,
+            None,
+        );
+        let (receiver, admin_tx, handle) =
+            start_source(Box::new(generator)).expect("start generator");
+        let received: Vec<StockQuote> = receiver
+            .iter()
+            .filter_map(|message| match message {
+                StreamMessage::Quote(quote) => Some(quote),
+                _ => None,
+            })
+            .take(4)
+            .collect();
         assert_eq!(received.len(), 4);
         for quote in &received {
             assert!(tickers.contains(&quote.ticker));
         }
-        drop(receiver);
+        // The receiver stays open; closing the admin channel alone stops the generator.
+        drop(admin_tx);
         handle.join().expect("generator thread should exit");
+        drop(receiver);
     }
 }
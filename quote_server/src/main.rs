@@ -1,20 +1,37 @@
+mod admin;
+mod aggregator;
+mod calendar;
 mod config;
 mod generator;
+mod order_book;
+mod quote_cache;
+mod replay;
+mod source;
 mod tcp_handler;
 mod udp_streamer;
+mod volume;
 
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crossbeam::channel;
-use log::info;
+use crossbeam::channel::{self, Sender};
+use log::{info, warn};
 
 use quote_common::QuoteError;
 
-use config::{load_config, load_tickers};
-use generator::start_generator;
-use tcp_handler::{StreamRequest, start_tcp_server};
-use udp_streamer::{UdpCommand, start_udp_streamer};
+use aggregator::start_aggregator;
+use config::{MulticastConfig, load_config, load_tickers};
+use generator::QuoteGenerator;
+use order_book::start_book_simulator;
+use quote_cache::QuoteCache;
+use replay::ReplaySource;
+use source::{QuoteSource, start_source};
+use tcp_handler::{
+    ControlRequest, Delivery, PublishedFeeds, SessionContext, StreamRequest, TcpLimits,
+    is_known_subscription, start_tcp_server,
+};
+use udp_streamer::{Batching, UdpCommand, start_udp_streamer};
 
 fn main() {
     env_logger::init();
@@ -37,26 +54,179 @@ fn run(shutdown_signal_rx: crossbeam::channel::Receiver<()>) -> Result<(), Quote
     info!("Loaded configuration:");
     info!("  TCP address: {}", config.tcp_addr);
     info!("  Tickers file: {}", config.tickers_file);
-    info!("  Quote rate: {}ms", config.quote_rate_ms);
+    info!(
+        "  Quote rate: {}ms ({} overrides)",
+        config.quote_rates.default_ms,
+        config.quote_rates.tickers.len()
+    );
     info!("  Keepalive timeout: {}s", config.keepalive_timeout_secs);
     info!("  Initial prices: {} tickers", config.initial_prices.len());
+    info!(
+        "  Price model: drift {}, volatility {} ({} overrides)",
+        config.price_model.default.drift,
+        config.price_model.default.volatility,
+        config.price_model.tickers.len()
+    );
+    info!(
+        "  Correlation: market {}, {} sectors",
+        config.price_model.market_correlation,
+        config.price_model.sectors.len()
+    );
+    info!(
+        "  Volume model: {} shares/day ({} overrides), open/close ratio {}, spike sensitivity {}",
+        config.volume_model.average_daily_volume,
+        config.volume_model.tickers.len(),
+        config.volume_model.open_close_ratio,
+        config.volume_model.spike_sensitivity
+    );
+    info!(
+        "  Tick size: {} at {} decimals ({} overrides)",
+        config.instruments.default.tick_size,
+        config.instruments.default.precision,
+        config.instruments.tickers.len()
+    );
+    info!("  Max TCP connections: {}", config.max_connections);
+    info!("  Command timeout: {}s", config.command_timeout_secs);
+    info!(
+        "  UDP batching: {} bytes, {}ms flush",
+        config.udp_max_payload, config.udp_flush_interval_ms
+    );
+    info!("  Multicast groups: {}", config.multicast.groups.len());
+    info!(
+        "  Bar intervals: [{}]",
+        config
+            .bar_intervals
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    if config.order_book.enabled() {
+        info!(
+            "  Order books: {} levels, snapshots every {}ms",
+            config.order_book.levels, config.order_book.snapshot_interval_ms
+        );
+    } else {
+        info!("  Order books: disabled");
+    }
+    if let Some(seed) = config.seed {
+        info!("  Seed: {seed}");
+    }
+    if let Some(start_ms) = config.simulated_clock_start_ms {
+        info!("  Simulated clock starting at {start_ms}ms");
+    }
+    match &config.market_calendar {
+        Some(calendar) => info!(
+            "  Market sessions ({}): pre-market {}, regular {}-{}, after hours until {}, {} holidays",
+            calendar.timezone,
+            calendar.pre_market_open,
+            calendar.regular_open,
+            calendar.regular_close,
+            calendar.after_hours_close,
+            calendar.holidays.len()
+        ),
+        None => info!("  Market sessions: quoting around the clock"),
+    }
+    if !config.corporate_actions.is_empty() {
+        info!(
+            "  Corporate actions: {} scheduled",
+            config.corporate_actions.len()
+        );
+    }
+    if let Some(bands) = &config.price_bands {
+        info!(
+            "  Price bands: {}% around {}s references, halting {}s ({} ticker overrides)",
+            bands.percent,
+            bands.reference_interval.as_secs(),
+            bands.halt_duration.as_secs(),
+            bands.tickers.len()
+        );
+    }
+    if config.admin_commands {
+        warn!("  Admin commands enabled: TCP clients can shock prices and halt tickers");
+    }
 
     let tickers = load_tickers(Path::new(&config.tickers_file))?;
     info!("Loaded {} tickers from file", tickers.len());
 
-    let (quote_rx, generator_handle) = start_generator(
-        tickers,
-        config.initial_prices.clone(),
-        Some(config.quote_rate_ms),
-    )?;
+    let ticker_universe = Arc::new(tickers.clone());
+    let source: Box<dyn QuoteSource> = match &config.replay {
+        Some(replay) => {
+            info!(
+                "Replaying '{}' at {}x speed{}",
+                replay.file.display(),
+                replay.speed,
+                if replay.looping { ", looping" } else { "" }
+            );
+            if config.market_calendar.is_some() {
+                warn!("Ignoring [market_calendar]: replayed ticks keep their recorded times");
+            }
+            if !config.corporate_actions.is_empty() {
+                warn!("Ignoring [[corporate_actions]]: replayed ticks keep their recorded prices");
+            }
+            if config.price_bands.is_some() {
+                warn!("Ignoring [price_bands]: replayed ticks keep their recorded prices");
+            }
+            Box::new(ReplaySource::load(replay, &tickers, &config.instruments)?)
+        }
+        None => {
+            let mut generator = QuoteGenerator::new(
+                tickers,
+                &config.initial_prices,
+                &config.quote_rates,
+                &config.price_model,
+                config.seed,
+                config.simulated_clock_start_ms,
+                config.market_calendar.clone(),
+            )
+            .with_instruments(&config.instruments)
+            .with_volume_model(&config.volume_model)
+            .with_corporate_actions(&config.corporate_actions);
+            if let Some(bands) = &config.price_bands {
+                generator = generator.with_price_bands(bands);
+            }
+            Box::new(generator)
+        }
+    };
+    let (quote_rx, admin_tx, source_handle) = start_source(source)?;
+
+    let feeds = Arc::new(PublishedFeeds {
+        bar_intervals: config.bar_intervals.clone(),
+        order_book: config.order_book.enabled(),
+    });
+    let (bar_rx, aggregator_handle) = start_aggregator(quote_rx, config.bar_intervals)?;
+    let (message_rx, order_book_handle) =
+        start_book_simulator(bar_rx, config.order_book, config.instruments, config.seed)?;
 
     let keepalive_timeout = Duration::from_secs(config.keepalive_timeout_secs);
-    let (dispatcher_tx, dispatcher_handle) =
-        start_udp_streamer(quote_rx, keepalive_timeout, config.tcp_addr)?;
+    let batching = Batching {
+        max_payload: config.udp_max_payload,
+        flush_interval: Duration::from_millis(config.udp_flush_interval_ms),
+    };
+    let quote_cache = QuoteCache::default();
+    let (dispatcher_tx, dispatcher_handle) = start_udp_streamer(
+        message_rx,
+        keepalive_timeout,
+        config.tcp_addr,
+        batching,
+        quote_cache.clone(),
+    )?;
+    publish_multicast_groups(&dispatcher_tx, &config.multicast, &ticker_universe, &feeds)?;
 
-    let (request_tx, request_rx) = channel::unbounded::<StreamRequest>();
+    let (request_tx, request_rx) = channel::unbounded::<ControlRequest>();
     // FIX: Store shutdown_tx instead of dropping it immediately with underscore
-    let (shutdown_tx, tcp_handle) = start_tcp_server(config.tcp_addr, request_tx.clone())?;
+    let session_context = SessionContext {
+        request_tx: request_tx.clone(),
+        tickers: ticker_universe,
+        cache: quote_cache,
+        feeds,
+        admin_tx: config.admin_commands.then(|| admin_tx.clone()),
+    };
+    let tcp_limits = TcpLimits {
+        max_connections: config.max_connections,
+        command_timeout: Duration::from_secs(config.command_timeout_secs),
+    };
+    let (shutdown_tx, tcp_handle) = start_tcp_server(config.tcp_addr, session_context, tcp_limits)?;
 
     // Drop main thread's sender - TCP thread now owns the only active sender
     // This allows the recv loop to exit when TCP thread finishes
@@ -67,11 +237,10 @@ fn run(shutdown_signal_rx: crossbeam::channel::Receiver<()>) -> Result<(), Quote
         crossbeam::channel::select! {
             recv(request_rx) -> result => match result {
                 Ok(request) => {
-                    log_stream_request(&request);
                     dispatcher_tx
-                        .send(UdpCommand::AddClient(request))
+                        .send(to_udp_command(request))
                         .map_err(|err| {
-                            quote_common::quote_error!(NetworkError, "failed to register UDP client: {}", err)
+                            quote_common::quote_error!(NetworkError, "failed to forward request to UDP dispatcher: {}", err)
                         })?;
                 }
                 Err(_) => {
@@ -103,17 +272,122 @@ fn run(shutdown_signal_rx: crossbeam::channel::Receiver<()>) -> Result<(), Quote
         .join()
         .map_err(|_| quote_common::quote_error!(NetworkError, "udp dispatcher thread panicked"))?;
 
-    generator_handle
+    // Sessions have released their admin senders; dropping the last one stops the source.
+    drop(admin_tx);
+    source_handle
         .join()
-        .map_err(|_| quote_common::quote_error!(NetworkError, "generator thread panicked"))?;
+        .map_err(|_| quote_common::quote_error!(NetworkError, "quote source thread panicked"))?;
+
+    aggregator_handle
+        .join()
+        .map_err(|_| quote_common::quote_error!(NetworkError, "bar aggregator thread panicked"))?;
+
+    order_book_handle
+        .join()
+        .map_err(|_| quote_common::quote_error!(NetworkError, "order book thread panicked"))?;
 
     Ok(())
 }
 
+/// Register every configured multicast group with the dispatcher, dropping tickers
+/// the generator does not produce and feeds the server does not publish.
+fn publish_multicast_groups(
+    dispatcher_tx: &Sender<UdpCommand>,
+    multicast: &MulticastConfig,
+    universe: &[String],
+    feeds: &PublishedFeeds,
+) -> Result<(), QuoteError> {
+    for group in &multicast.groups {
+        let (tickers, unknown): (Vec<_>, Vec<_>) = group
+            .tickers
+            .iter()
+            .cloned()
+            .partition(|key| is_known_subscription(key, universe, feeds));
+        if !unknown.is_empty() {
+            warn!(
+                "Multicast group {} lists unknown tickers [{}]",
+                group.group,
+                unknown.join(",")
+            );
+        }
+        if tickers.is_empty() {
+            warn!("Skipping multicast group {}: no known tickers", group.group);
+            continue;
+        }
+
+        info!(
+            "Publishing [{}] to multicast group {}",
+            tickers.join(","),
+            group.group
+        );
+        let request = StreamRequest {
+            delivery: Delivery::Multicast {
+                group: group.group,
+                interface: multicast.interface,
+                ttl: multicast.ttl,
+            },
+            tickers,
+            encoding: group.encoding,
+        };
+        dispatcher_tx
+            .send(UdpCommand::AddClient(request))
+            .map_err(|err| {
+                quote_common::quote_error!(
+                    NetworkError,
+                    "failed to register multicast group {}: {}",
+                    group.group,
+                    err
+                )
+            })?;
+    }
+    Ok(())
+}
+
+fn to_udp_command(request: ControlRequest) -> UdpCommand {
+    match request {
+        ControlRequest::Stream(request) => {
+            log_stream_request(&request);
+            UdpCommand::AddClient(request)
+        }
+        ControlRequest::Subscribe {
+            client_addr,
+            tickers,
+        } => {
+            info!(
+                "Client {} subscribed to [{}]",
+                client_addr,
+                tickers.join(",")
+            );
+            UdpCommand::Subscribe {
+                client_addr,
+                tickers,
+            }
+        }
+        ControlRequest::Unsubscribe {
+            client_addr,
+            tickers,
+        } => {
+            info!(
+                "Client {} unsubscribed from [{}]",
+                client_addr,
+                tickers.join(",")
+            );
+            UdpCommand::Unsubscribe {
+                client_addr,
+                tickers,
+            }
+        }
+        ControlRequest::Stop { client_addr } => {
+            info!("Client {} closed its TCP stream", client_addr);
+            UdpCommand::RemoveClient { client_addr }
+        }
+    }
+}
+
 fn log_stream_request(request: &StreamRequest) {
     info!(
         "Client requested STREAM to {} for [{}]",
-        request.udp_addr,
+        request.delivery,
         request.tickers.join(",")
     );
 }
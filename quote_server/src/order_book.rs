@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::thread;
+
+use crossbeam::channel::{self, Receiver, Sender};
+use log::info;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use quote_common::{
+    BookLevel, BookSide, BookSnapshot, BookUpdate, LevelChange, QuoteError, StockQuote,
+    StreamMessage,
+};
+
+use crate::config::{InstrumentSpec, Instruments, OrderBookConfig};
+use crate::generator::{MAX_SIZE_LOTS, ROUND_LOT};
+
+const ORDER_BOOK_THREAD_NAME: &str = "order-book";
+/// Chance that a resting level changes size from one quote to the next.
+const LEVEL_CHANGE_PROBABILITY: f64 = 0.3;
+/// Largest change of a resting level's size between quotes, in round lots.
+const MAX_LEVEL_CHANGE_LOTS: u32 = 3;
+
+/// Simulated depth of one ticker.
+#[derive(Debug, Default)]
+struct TickerBook {
+    /// Sequence number of the last snapshot or update published.
+    sequence: u64,
+    bids: Vec<BookLevel>,
+    asks: Vec<BookLevel>,
+    /// Quote timestamp of the last full snapshot; `None` before the first.
+    last_snapshot: Option<i64>,
+}
+
+/// Simulates order-book depth per ticker around the top of the book of each quote.
+/// The best level of each side is the quote's bid or ask and deeper levels step one
+/// tick away from the mid. Levels whose price stays in the book keep their size with
+/// occasional random changes, so the depth evolves with the mid instead of being
+/// redrawn on every quote.
+pub struct BookSimulator {
+    config: OrderBookConfig,
+    instruments: Instruments,
+    books: HashMap<String, TickerBook>,
+    rng: StdRng,
+}
+
+impl BookSimulator {
+    /// Create a simulator with `config.levels` levels per side; seeded runs repeat.
+    pub fn new(config: OrderBookConfig, instruments: Instruments, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Self {
+            config,
+            instruments,
+            books: HashMap::new(),
+            rng,
+        }
+    }
+
+    /// Move the book of `quote`'s ticker to the quote's top of the book. Returns a full
+    /// snapshot when `snapshot_interval_ms` has passed since the last one, otherwise
+    /// the changed levels, or `None` when no level changed or the ticker is halted.
+    pub fn on_quote(&mut self, quote: &StockQuote) -> Option<StreamMessage> {
+        if quote.halted {
+            return None;
+        }
+        let spec = self.instruments.spec(&quote.ticker);
+        let levels = self.config.levels;
+        let (bid, ask, bid_size, ask_size) = touch(quote, &spec, &mut self.rng);
+        let book = self.books.entry(quote.ticker.clone()).or_default();
+
+        let bids = next_side(
+            &book.bids,
+            bid,
+            bid_size,
+            -spec.tick_size,
+            levels,
+            &spec,
+            &mut self.rng,
+        );
+        let asks = next_side(
+            &book.asks,
+            ask,
+            ask_size,
+            spec.tick_size,
+            levels,
+            &spec,
+            &mut self.rng,
+        );
+        let mut changes = Vec::new();
+        diff_side(BookSide::Bid, &book.bids, &bids, &spec, &mut changes);
+        diff_side(BookSide::Ask, &book.asks, &asks, &spec, &mut changes);
+        book.bids = bids;
+        book.asks = asks;
+
+        let snapshot_interval_ms = self.config.snapshot_interval_ms as i64;
+        let snapshot_due = book
+            .last_snapshot
+            .is_none_or(|at| quote.timestamp - at >= snapshot_interval_ms);
+        if !snapshot_due && changes.is_empty() {
+            return None;
+        }
+
+        book.sequence += 1;
+        if snapshot_due {
+            book.last_snapshot = Some(quote.timestamp);
+            return Some(
+                BookSnapshot {
+                    ticker: quote.ticker.clone(),
+                    sequence: book.sequence,
+                    bids: book.bids.clone(),
+                    asks: book.asks.clone(),
+                    timestamp: quote.timestamp,
+                    precision: quote.precision,
+                }
+                .into(),
+            );
+        }
+        Some(
+            BookUpdate {
+                ticker: quote.ticker.clone(),
+                sequence: book.sequence,
+                changes,
+                timestamp: quote.timestamp,
+                precision: quote.precision,
+            }
+            .into(),
+        )
+    }
+
+    fn run(mut self, input_rx: Receiver<StreamMessage>, message_tx: Sender<StreamMessage>) {
+        for input in input_rx.iter() {
+            let depth = match &input {
+                StreamMessage::Quote(quote) if self.config.enabled() => self.on_quote(quote),
+                _ => None,
+            };
+            // Depth follows the quote it was built from.
+            for message in std::iter::once(input).chain(depth) {
+                if message_tx.send(message).is_err() {
+                    info!("Order book simulator shutting down (no active receivers)");
+                    return;
+                }
+            }
+        }
+        info!("Order book simulator shutting down (quote source finished)");
+    }
+}
+
+/// Best bid and ask of `quote` with their sizes. Quotes without a book, such as
+/// replayed ticks, get the ticks on either side of the price and freshly drawn sizes.
+fn touch(quote: &StockQuote, spec: &InstrumentSpec, rng: &mut impl Rng) -> (f64, f64, u32, u32) {
+    if quote.has_book() {
+        return (quote.bid, quote.ask, quote.bid_size, quote.ask_size);
+    }
+    let half_tick = spec.tick_size / 2.0;
+    (
+        spec.floor(quote.price - half_tick).max(spec.tick_size),
+        spec.ceil(quote.price + half_tick),
+        fresh_size(0, rng),
+        fresh_size(0, rng),
+    )
+}
+
+/// Levels of one side: `best` with `best_size`, then one `step` further from the mid
+/// per level. Bids stop before reaching a zero price.
+fn next_side(
+    previous: &[BookLevel],
+    best: f64,
+    best_size: u32,
+    step: f64,
+    levels: usize,
+    spec: &InstrumentSpec,
+    rng: &mut impl Rng,
+) -> Vec<BookLevel> {
+    let mut side = Vec::with_capacity(levels);
+    for depth in 0..levels {
+        let raw = best + step * depth as f64;
+        if raw < spec.tick_size / 2.0 {
+            break;
+        }
+        let price = spec.round(raw);
+        let size = if depth == 0 {
+            best_size
+        } else {
+            match previous
+                .iter()
+                .find(|level| same_price(level.price, price, spec))
+            {
+                Some(level) => evolve_size(level.size, rng),
+                None => fresh_size(depth, rng),
+            }
+        };
+        side.push(BookLevel { price, size });
+    }
+    side
+}
+
+/// Size of a level entering the book; deeper levels hold more shares.
+fn fresh_size(depth: usize, rng: &mut impl Rng) -> u32 {
+    rng.random_range(1..=MAX_SIZE_LOTS) * (depth as u32 + 1) * ROUND_LOT
+}
+
+/// Size of a resting level after one quote: usually unchanged, sometimes a few lots
+/// larger or smaller, never below one lot.
+fn evolve_size(size: u32, rng: &mut impl Rng) -> u32 {
+    if !rng.random_bool(LEVEL_CHANGE_PROBABILITY) {
+        return size;
+    }
+    let change = rng.random_range(1..=MAX_LEVEL_CHANGE_LOTS) * ROUND_LOT;
+    if rng.random_bool(0.5) {
+        size + change
+    } else {
+        size.saturating_sub(change).max(ROUND_LOT)
+    }
+}
+
+/// Append the changes turning `previous` into `next` on one side: removed levels with
+/// a size of zero, then new and resized levels.
+fn diff_side(
+    side: BookSide,
+    previous: &[BookLevel],
+    next: &[BookLevel],
+    spec: &InstrumentSpec,
+    changes: &mut Vec<LevelChange>,
+) {
+    for level in previous {
+        if !next
+            .iter()
+            .any(|new| same_price(new.price, level.price, spec))
+        {
+            changes.push(LevelChange {
+                side,
+                price: level.price,
+                size: 0,
+            });
+        }
+    }
+    for level in next {
+        let unchanged = previous
+            .iter()
+            .any(|old| same_price(old.price, level.price, spec) && old.size == level.size);
+        if !unchanged {
+            changes.push(LevelChange {
+                side,
+                price: level.price,
+                size: level.size,
+            });
+        }
+    }
+}
+
+fn same_price(a: f64, b: f64, spec: &InstrumentSpec) -> bool {
+    (a - b).abs() < spec.tick_size / 2.0
+}
+
+/// Spawn the order-book stage: every message is forwarded and each quote is followed
+/// by a depth snapshot or update for its ticker. With order books disabled, messages
+/// pass through unchanged.
+pub fn start_book_simulator(
+    input_rx: Receiver<StreamMessage>,
+    config: OrderBookConfig,
+    instruments: Instruments,
+    seed: Option<u64>,
+) -> Result<(Receiver<StreamMessage>, thread::JoinHandle<()>), QuoteError> {
+    let simulator = BookSimulator::new(config, instruments, seed);
+    let (message_tx, message_rx) = channel::unbounded();
+    let handle = thread::Builder::new()
+        .name(ORDER_BOOK_THREAD_NAME.to_string())
+        .spawn(move || simulator.run(input_rx, message_tx))
+        .map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to spawn order book thread")
+        })?;
+
+    Ok((message_rx, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote_at(price: f64, bid: f64, ask: f64, timestamp: i64) -> StockQuote {
+        StockQuote {
+            timestamp,
+            ..StockQuote::new("AAPL", price, 100).with_book(bid, ask, 300, 500)
+        }
+    }
+
+    fn config(levels: usize) -> OrderBookConfig {
+        OrderBookConfig {
+            levels,
+            snapshot_interval_ms: 1_000,
+        }
+    }
+
+    fn apply(levels: &mut Vec<BookLevel>, change: &LevelChange) {
+        levels.retain(|level| (level.price - change.price).abs() > 1e-9);
+        if change.size > 0 {
+            levels.push(BookLevel {
+                price: change.price,
+                size: change.size,
+            });
+        }
+    }
+
+    #[test]
+    fn test_book_simulator_snapshots_then_updates_consistently() {
+        let mut simulator = BookSimulator::new(config(3), Instruments::default(), Some(5));
+
+        let Some(StreamMessage::BookSnapshot(snapshot)) =
+            simulator.on_quote(&quote_at(100.0, 99.99, 100.02, 0))
+        else {
+            panic!("the first quote of a ticker publishes a snapshot");
+        };
+        assert_eq!(snapshot.sequence, 1);
+        let prices = |levels: &[BookLevel]| levels.iter().map(|l| l.price).collect::<Vec<_>>();
+        assert_eq!(prices(&snapshot.bids), vec![99.99, 99.98, 99.97]);
+        assert_eq!(prices(&snapshot.asks), vec![100.02, 100.03, 100.04]);
+        assert_eq!((snapshot.bids[0].size, snapshot.asks[0].size), (300, 500));
+
+        // Replaying the updates onto the snapshot reproduces the simulated book.
+        let (mut bids, mut asks) = (snapshot.bids, snapshot.asks);
+        let mut sequence = snapshot.sequence;
+        for (step, mid) in [100.01, 100.03, 100.02, 99.98].into_iter().enumerate() {
+            let quote = quote_at(mid, mid - 0.01, mid + 0.01, 100 * (step as i64 + 1));
+            let Some(StreamMessage::BookUpdate(update)) = simulator.on_quote(&quote) else {
+                panic!("expected an update before the next snapshot is due");
+            };
+            assert_eq!(update.sequence, sequence + 1);
+            sequence = update.sequence;
+            for change in &update.changes {
+                match change.side {
+                    BookSide::Bid => apply(&mut bids, change),
+                    BookSide::Ask => apply(&mut asks, change),
+                }
+            }
+            bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+            asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+            let book = &simulator.books["AAPL"];
+            assert_eq!((&bids, &asks), (&book.bids, &book.asks));
+        }
+
+        let snapshot = simulator.on_quote(&quote_at(100.0, 99.99, 100.02, 1_000));
+        assert!(
+            matches!(snapshot, Some(StreamMessage::BookSnapshot(snapshot)) if snapshot.sequence == sequence + 1)
+        );
+    }
+
+    #[test]
+    fn test_book_simulator_brackets_quotes_without_book() {
+        let mut simulator = BookSimulator::new(config(2), Instruments::default(), Some(1));
+        let Some(StreamMessage::BookSnapshot(snapshot)) =
+            simulator.on_quote(&StockQuote::new("AAPL", 0.015, 100))
+        else {
+            panic!("expected a snapshot");
+        };
+        // Bids never reach a zero price, so this book has a single bid level.
+        assert_eq!(snapshot.bids.len(), 1);
+        assert_eq!(snapshot.bids[0].price, 0.01);
+        assert_eq!(snapshot.asks[0].price, 0.02);
+        assert!(snapshot.bids[0].size >= ROUND_LOT);
+
+        let halted = StockQuote {
+            halted: true,
+            ..StockQuote::new("AAPL", 0.5, 0)
+        };
+        assert_eq!(simulator.on_quote(&halted), None);
+    }
+
+    #[test]
+    fn test_start_book_simulator_follows_quotes_with_depth() {
+        for (levels, expected) in [
+            (0, vec!["quote AAPL", "trade AAPL"]),
+            (2, vec!["quote AAPL", "book snapshot AAPL", "trade AAPL"]),
+        ] {
+            let (input_tx, input_rx) = channel::unbounded();
+            let (message_rx, handle) =
+                start_book_simulator(input_rx, config(levels), Instruments::default(), Some(3))
+                    .expect("start order book");
+            let quote = quote_at(100.0, 99.99, 100.01, 0);
+            input_tx.send(quote.clone().into()).unwrap();
+            input_tx.send(quote.last_trade().into()).unwrap();
+            drop(input_tx);
+
+            let messages: Vec<String> = message_rx.iter().map(|m| m.to_string()).collect();
+            assert_eq!(messages, expected);
+            handle.join().expect("order book thread should exit");
+        }
+    }
+}
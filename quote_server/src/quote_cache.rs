@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use quote_common::StockQuote;
+
+/// Last known quote per ticker, shared between the UDP dispatcher (writer)
+/// and TCP control sessions (readers).
+#[derive(Debug, Clone, Default)]
+pub struct QuoteCache {
+    quotes: Arc<Mutex<HashMap<String, StockQuote>>>,
+}
+
+impl QuoteCache {
+    /// Record `quote` as the latest for its ticker.
+    pub fn update(&self, quote: &StockQuote) {
+        match self.quotes.lock() {
+            Ok(mut quotes) => {
+                quotes.insert(quote.ticker.clone(), quote.clone());
+            }
+            Err(_) => warn!(
+                "Quote cache lock poisoned; dropping update for {}",
+                quote.ticker
+            ),
+        }
+    }
+
+    /// Latest quotes for `tickers`, in request order. Tickers without a quote yet are skipped.
+    pub fn latest(&self, tickers: &[String]) -> Vec<StockQuote> {
+        match self.quotes.lock() {
+            Ok(quotes) => tickers
+                .iter()
+                .filter_map(|ticker| quotes.get(ticker).cloned())
+                .collect(),
+            Err(_) => {
+                warn!("Quote cache lock poisoned; returning no quotes");
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_cache_keeps_latest_quote_per_ticker() {
+        let cache = QuoteCache::default();
+        cache.update(&StockQuote::new("AAPL", 150.0, 1_000));
+        cache.update(&StockQuote::new("MSFT", 380.0, 2_000));
+        cache.update(&StockQuote::new("AAPL", 151.5, 3_000));
+
+        let latest = cache.latest(&["MSFT".to_string(), "TSLA".to_string(), "AAPL".to_string()]);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].ticker, "MSFT");
+        assert_eq!(latest[1].ticker, "AAPL");
+        assert_eq!(latest[1].price, 151.5);
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+use log::{info, warn};
+
+use quote_common::{DEFAULT_QUOTE_RATE_MS, QuoteError, StockQuote, StreamMessage};
+
+use crate::admin::AdminCommand;
+use crate::config::{Instruments, ReplayConfig};
+use crate::source::{QuoteSource, send_all};
+use crate::volume::{DailyTotals, trading_date};
+
+/// Columns of a CSV replay file, which may start with this line as a header.
+const CSV_HEADER: &str = "ticker,price,volume,timestamp";
+const CSV_COLUMNS: usize = 4;
+
+/// Layout of a replay file, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFormat {
+    /// `ticker,price,volume,timestamp` rows with an optional header line.
+    Csv,
+    /// One JSON `StockQuote` per line; book fields may be omitted.
+    JsonLines,
+}
+
+impl ReplayFormat {
+    /// Format implied by the extension of `path` (`.csv`, `.jsonl` or `.ndjson`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(ReplayFormat::Csv),
+            "jsonl" | "ndjson" => Some(ReplayFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// Replays recorded ticks in timestamp order, keeping their original spacing divided
+/// by `speed`. Each tick is sent as a trade at its price and volume, then as a quote
+/// carrying the volume and trade count of the tick's UTC day so far. A looping replay
+/// shifts each pass forward in time so timestamps keep increasing.
+pub struct ReplaySource {
+    ticks: Vec<StockQuote>,
+    speed: f64,
+    looping: bool,
+}
+
+impl ReplaySource {
+    /// Create a source replaying `ticks`, which are sorted by timestamp first.
+    pub fn new(mut ticks: Vec<StockQuote>, speed: f64, looping: bool) -> Self {
+        ticks.sort_by_key(|tick| tick.timestamp);
+        Self {
+            ticks,
+            speed,
+            looping,
+        }
+    }
+
+    /// Load the configured replay file, keeping only ticks of `tickers`. Each tick is
+    /// tagged with its instrument's precision; recorded prices are kept as they are.
+    pub fn load(
+        config: &ReplayConfig,
+        tickers: &[String],
+        instruments: &Instruments,
+    ) -> Result<Self, QuoteError> {
+        let ticks = load_ticks(&config.file, config.format)?;
+        let total = ticks.len();
+        let known: HashSet<&str> = tickers.iter().map(String::as_str).collect();
+        let ticks: Vec<StockQuote> = ticks
+            .into_iter()
+            .filter(|tick| known.contains(tick.ticker.as_str()))
+            .map(|tick| StockQuote {
+                precision: instruments.spec(&tick.ticker).precision,
+                ..tick
+            })
+            .collect();
+
+        if ticks.len() < total {
+            warn!(
+                "Skipping {} ticks in '{}' for tickers missing from the tickers file",
+                total - ticks.len(),
+                config.file.display()
+            );
+        }
+        if ticks.is_empty() {
+            return Err(quote_common::quote_error!(
+                ConfigError,
+                "replay file '{}' has no ticks for the configured tickers",
+                config.file.display()
+            ));
+        }
+
+        Ok(Self::new(ticks, config.speed, config.looping))
+    }
+
+    /// Recorded milliseconds from the start of one pass to the start of the next: the
+    /// span of the file plus one average gap between ticks.
+    fn pass_length_ms(&self) -> i64 {
+        let (Some(first), Some(last)) = (self.ticks.first(), self.ticks.last()) else {
+            return 0;
+        };
+        let span = last.timestamp - first.timestamp;
+        if span <= 0 {
+            return DEFAULT_QUOTE_RATE_MS as i64;
+        }
+        span + span / (self.ticks.len() as i64 - 1)
+    }
+}
+
+impl QuoteSource for ReplaySource {
+    fn run(self: Box<Self>, sender: Sender<StreamMessage>, admin_rx: Receiver<AdminCommand>) {
+        let Some(first_ms) = self.ticks.first().map(|tick| tick.timestamp) else {
+            return;
+        };
+        let pass_ms = self.pass_length_ms();
+        let started = Instant::now();
+        let mut offset_ms = 0;
+        let mut daily = DailyTotals::default();
+
+        'passes: loop {
+            for tick in &self.ticks {
+                let recorded_ms = tick.timestamp - first_ms + offset_ms;
+                let due =
+                    started + Duration::from_secs_f64(recorded_ms as f64 / 1_000.0 / self.speed);
+                if !wait_until(due, &admin_rx) {
+                    break 'passes;
+                }
+                let mut quote = StockQuote {
+                    timestamp: tick.timestamp + offset_ms,
+                    ..tick.clone()
+                };
+                let date = trading_date(quote.timestamp, None);
+                daily.record(&mut quote, date);
+                if !send_all(&sender, vec![quote.last_trade().into(), quote.into()]) {
+                    break 'passes;
+                }
+            }
+
+            if !self.looping {
+                info!("Replay finished after {} ticks", self.ticks.len());
+                break;
+            }
+            offset_ms += pass_ms;
+        }
+        info!("Replay source shutting down");
+    }
+}
+
+/// Wait until `due`, discarding admin commands, which do not apply to recorded data.
+/// Returns `false` once the admin channel closes.
+fn wait_until(due: Instant, admin_rx: &Receiver<AdminCommand>) -> bool {
+    loop {
+        match admin_rx.recv_deadline(due) {
+            Ok(command) => warn!("Ignoring {command:?}: admin commands do not apply to a replay"),
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
+/// Read every tick of a replay file. Blank lines are skipped; any other line that
+/// does not parse is an error naming the file and line.
+pub fn load_ticks(path: &Path, format: ReplayFormat) -> Result<Vec<StockQuote>, QuoteError> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        quote_common::quote_error!(
+            IoError,
+            err,
+            "failed to read replay file '{}'",
+            path.display()
+        )
+    })?;
+
+    let mut ticks = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line.eq_ignore_ascii_case(CSV_HEADER)) {
+            continue;
+        }
+        let tick = match format {
+            ReplayFormat::Csv => parse_csv_tick(line),
+            ReplayFormat::JsonLines => serde_json::from_str::<StockQuote>(line)
+                .map_err(|err| err.to_string())
+                .and_then(validate_tick),
+        }
+        .map_err(|reason| {
+            quote_common::quote_error!(
+                ParseError,
+                "invalid tick at '{}' line {}: {}",
+                path.display(),
+                index + 1,
+                reason
+            )
+        })?;
+        ticks.push(tick);
+    }
+
+    Ok(ticks)
+}
+
+fn parse_csv_tick(line: &str) -> Result<StockQuote, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [ticker, price, volume, timestamp] = fields[..] else {
+        return Err(format!(
+            "expected {CSV_COLUMNS} columns ({CSV_HEADER}), found {}",
+            fields.len()
+        ));
+    };
+    let price = price
+        .parse::<f64>()
+        .map_err(|_| format!("invalid price '{price}'"))?;
+    let volume = volume
+        .parse::<u32>()
+        .map_err(|_| format!("invalid volume '{volume}'"))?;
+    let timestamp = timestamp
+        .parse::<i64>()
+        .map_err(|_| format!("invalid timestamp '{timestamp}'"))?;
+
+    validate_tick(StockQuote {
+        timestamp,
+        ..StockQuote::new(ticker, price, volume)
+    })
+}
+
+/// Normalize the ticker and reject ticks no generator could have produced.
+fn validate_tick(mut tick: StockQuote) -> Result<StockQuote, String> {
+    if tick.ticker.is_empty() || !tick.ticker.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("invalid ticker '{}'", tick.ticker));
+    }
+    if !tick.price.is_finite() || tick.price <= 0.0 {
+        return Err(format!("price {} must be positive", tick.price));
+    }
+    tick.ticker = tick.ticker.to_uppercase();
+    Ok(tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_path(extension: &str) -> std::path::PathBuf {
+        let mut path = std::env::temp_dir();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        path.push(format!("replay_test_{nanos}.{extension}"));
+        path
+    }
+
+    fn tick(ticker: &str, price: f64, timestamp: i64) -> StockQuote {
+        StockQuote {
+            timestamp,
+            ..StockQuote::new(ticker, price, 100)
+        }
+    }
+
+    #[test]
+    fn test_load_ticks_reads_csv_and_jsonl() {
+        let csv = unique_temp_path("csv");
+        fs::write(
+            &csv,
+            "ticker,price,volume,timestamp\naapl,150.25,300,1000\n\nMSFT, 410.5, 200, 1500\n",
+        )
+        .unwrap();
+        let ticks = load_ticks(&csv, ReplayFormat::Csv).expect("load csv");
+        assert_eq!(
+            ticks,
+            vec![
+                StockQuote {
+                    volume: 300,
+                    ..tick("AAPL", 150.25, 1_000)
+                },
+                StockQuote {
+                    volume: 200,
+                    ..tick("MSFT", 410.5, 1_500)
+                },
+            ]
+        );
+        fs::remove_file(csv).unwrap();
+
+        let jsonl = unique_temp_path("jsonl");
+        fs::write(
+            &jsonl,
+            "{\"ticker\":\"AAPL\",\"price\":150.25,\"volume\":100,\"timestamp\":1000}\n",
+        )
+        .unwrap();
+        let ticks = load_ticks(&jsonl, ReplayFormat::JsonLines).expect("load jsonl");
+        assert_eq!(ticks, vec![tick("AAPL", 150.25, 1_000)]);
+        fs::remove_file(jsonl).unwrap();
+
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("ticks.JSONL")),
+            Some(ReplayFormat::JsonLines)
+        );
+        assert_eq!(ReplayFormat::from_path(Path::new("ticks.txt")), None);
+    }
+
+    #[test]
+    fn test_load_ticks_reports_bad_line() {
+        let path = unique_temp_path("csv");
+        fs::write(&path, "AAPL,150.25,300,1000\nAAPL,-1,300,2000\n").unwrap();
+
+        let err = load_ticks(&path, ReplayFormat::Csv).expect_err("negative price");
+        assert!(matches!(err, QuoteError::ParseError { .. }));
+        assert!(err.to_string().contains("line 2"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_paces_ticks_and_shifts_looped_passes() {
+        // 200ms of recorded ticks at 10x speed, looped: one pass every 30ms of real time.
+        let ticks = vec![
+            tick("MSFT", 410.0, 1_100),
+            tick("AAPL", 150.0, 1_000),
+            tick("AAPL", 151.0, 1_200),
+        ];
+        let source = ReplaySource::new(ticks, 10.0, true);
+        let (sender, receiver) = channel::unbounded();
+        let (admin_tx, admin_rx) = channel::unbounded();
+        let started = Instant::now();
+        let handle = std::thread::spawn(move || Box::new(source).run(sender, admin_rx));
+
+        let timestamps: Vec<i64> = receiver
+            .iter()
+            .take(12)
+            .filter_map(|message| match message {
+                StreamMessage::Trade(_) => None,
+                StreamMessage::Quote(quote) => Some(quote.timestamp),
+                other => panic!("unexpected {other}"),
+            })
+            .collect();
+        let elapsed = started.elapsed();
+        drop(admin_tx);
+        handle.join().expect("replay thread should exit");
+
+        // The second pass starts one average gap (100ms) after the first ends.
+        assert_eq!(timestamps, vec![1_000, 1_100, 1_200, 1_300, 1_400, 1_500]);
+        assert!(
+            elapsed >= Duration::from_millis(50),
+            "ticks were not paced: {elapsed:?}"
+        );
+    }
+}
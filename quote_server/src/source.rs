@@ -0,0 +1,46 @@
+use std::thread;
+
+use crossbeam::channel::{self, Receiver, Sender};
+
+use quote_common::{QuoteError, StreamMessage};
+
+use crate::admin::AdminCommand;
+
+const SOURCE_THREAD_NAME: &str = "quote-source";
+
+/// Producer of the messages the server publishes: synthetic quotes or recorded ticks.
+pub trait QuoteSource: Send + 'static {
+    /// Send messages until the receiver is dropped, the admin channel closes or the
+    /// source runs out of data.
+    fn run(self: Box<Self>, sender: Sender<StreamMessage>, admin_rx: Receiver<AdminCommand>);
+}
+
+/// Message receiver, admin command sender and thread handle of a running source.
+/// Dropping every admin sender stops the source.
+pub type SourceHandles = (
+    Receiver<StreamMessage>,
+    Sender<AdminCommand>,
+    thread::JoinHandle<()>,
+);
+
+/// Spawn `source` on its own thread and return the receiving side for consumers, along
+/// with the sender for admin commands.
+pub fn start_source(source: Box<dyn QuoteSource>) -> Result<SourceHandles, QuoteError> {
+    let (sender, receiver) = channel::unbounded();
+    let (admin_tx, admin_rx) = channel::unbounded();
+    let handle = thread::Builder::new()
+        .name(SOURCE_THREAD_NAME.to_string())
+        .spawn(move || source.run(sender, admin_rx))
+        .map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to spawn quote source thread")
+        })?;
+
+    Ok((receiver, admin_tx, handle))
+}
+
+/// Send `messages` in order, returning `false` once the receiver is gone.
+pub fn send_all(sender: &Sender<StreamMessage>, messages: Vec<StreamMessage>) -> bool {
+    messages
+        .into_iter()
+        .all(|message| sender.send(message).is_ok())
+}
@@ -1,29 +1,302 @@
-use std::io::{BufRead, BufReader, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4, TcpListener, TcpStream};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossbeam::channel::Sender;
-use log::{info, warn};
+use log::{debug, info, warn};
 
-use quote_common::{QuoteError, RESPONSE_ERR_PREFIX, UDP_SCHEME_PREFIX, UNKNOWN_ADDR_PLACEHOLDER};
+use crate::admin::{AdminCommand, parse_admin_command};
+use crate::quote_cache::QuoteCache;
+
+use quote_common::{
+    BarInterval, ENCODING_OPTION, Feed, QuoteEncoding, QuoteError, RESPONSE_ERR_PREFIX,
+    RESPONSE_OK, RESPONSE_PARTIAL, TCP_SCHEME_PREFIX, UDP_SCHEME_PREFIX, UNKNOWN_ADDR_PLACEHOLDER,
+    parse_subscription_key, subscription_key,
+};
 
 const STREAM_PREFIX: &str = "STREAM ";
+const SUBSCRIBE_PREFIX: &str = "SUBSCRIBE ";
+const UNSUBSCRIBE_PREFIX: &str = "UNSUBSCRIBE ";
+const LIST_COMMAND: &str = "LIST";
+const SNAPSHOT_PREFIX: &str = "SNAPSHOT ";
+const ADMIN_PREFIX: &str = "ADMIN ";
 const TCP_LISTENER_THREAD_NAME: &str = "tcp-listener";
+const TCP_SESSION_THREAD_NAME_PREFIX: &str = "tcp-session-";
 const TCP_POLL_INTERVAL_MS: u64 = 100;
+const MAX_COMMAND_LENGTH: usize = 4_096;
+
+/// Destination named in a STREAM command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTarget {
+    /// `udp://host:port`: datagrams sent to the given address.
+    Udp(SocketAddr),
+    /// `tcp://`: newline-delimited frames written back on the control connection.
+    Connection,
+}
 
 /// Parsed representation of a valid STREAM command.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamCommand {
+    /// Where the client wants to receive quotes.
+    pub target: StreamTarget,
+    /// List of ticker symbols requested by the client (uppercase).
+    pub tickers: Vec<String>,
+    /// Encoding used for quotes sent to the client.
+    pub encoding: QuoteEncoding,
+}
+
+/// Write half of a control connection, shared by its session and the stream writing quotes to it.
+/// Both write whole lines under the lock so responses and quote frames never interleave.
+#[derive(Debug, Clone)]
+pub struct ConnectionWriter {
+    peer_addr: SocketAddr,
+    stream: Arc<Mutex<TcpStream>>,
+}
+
+/// How quotes of a registered stream reach the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Delivery {
+    /// Batched datagrams sent to a UDP address.
+    Udp(SocketAddr),
+    /// Newline-delimited frames written on the client's control connection.
+    Tcp(ConnectionWriter),
+    /// Batched datagrams published to a multicast group from the server configuration.
+    Multicast {
+        group: SocketAddrV4,
+        interface: Ipv4Addr,
+        ttl: u32,
+    },
+}
+
+/// A stream ready to be registered with the dispatcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StreamRequest {
-    /// UDP address where the client will receive quotes.
-    pub udp_addr: SocketAddr,
+    /// Transport the client receives quotes on.
+    pub delivery: Delivery,
     /// List of ticker symbols requested by the client (uppercase).
     pub tickers: Vec<String>,
+    /// Encoding used for quotes sent to the client.
+    pub encoding: QuoteEncoding,
+}
+
+/// Result of parsing a single line received on a TCP control session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlCommand {
+    /// Start streaming quotes to a UDP address or over this connection.
+    Stream(StreamCommand),
+    /// Add tickers to the session's active stream.
+    Subscribe(Vec<String>),
+    /// Remove tickers from the session's active stream.
+    Unsubscribe(Vec<String>),
+    /// List the server's tickers, optionally restricted to a prefix.
+    List(Option<String>),
+    /// Return the last known quote for each ticker.
+    Snapshot(Vec<String>),
+    /// Inject a scenario into the quote generator.
+    Admin(AdminCommand),
+}
+
+/// Requests forwarded from TCP sessions to the server main loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest {
+    /// Register a new stream.
+    Stream(StreamRequest),
+    /// Add tickers to the stream identified by `client_addr`.
+    Subscribe {
+        client_addr: SocketAddr,
+        tickers: Vec<String>,
+    },
+    /// Remove tickers from the stream identified by `client_addr`.
+    Unsubscribe {
+        client_addr: SocketAddr,
+        tickers: Vec<String>,
+    },
+    /// Stop a stream delivered on a control connection that has closed.
+    Stop { client_addr: SocketAddr },
+}
+
+/// Shared state available to every TCP control session.
+#[derive(Debug, Clone)]
+pub struct SessionContext {
+    /// Channel used to forward stream changes to the server main loop.
+    pub request_tx: Sender<ControlRequest>,
+    /// Ticker universe produced by the quote generator.
+    pub tickers: Arc<Vec<String>>,
+    /// Last known quote per ticker, answered by SNAPSHOT.
+    pub cache: QuoteCache,
+    /// Feeds beyond trades and quotes that clients may subscribe to.
+    pub feeds: Arc<PublishedFeeds>,
+    /// Channel into the quote generator; `None` when admin commands are disabled.
+    pub admin_tx: Option<Sender<AdminCommand>>,
+}
+
+/// Feeds the server publishes for every ticker besides trades, quotes and events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishedFeeds {
+    /// Bar intervals the server aggregates, subscribable as `TICKER@interval`.
+    pub bar_intervals: Vec<BarInterval>,
+    /// Whether order books are simulated, subscribable as `TICKER@book`.
+    pub order_book: bool,
+}
+
+impl PublishedFeeds {
+    /// Whether the server publishes `feed`.
+    pub fn publishes(&self, feed: Feed) -> bool {
+        match feed {
+            Feed::Quotes => true,
+            Feed::Bars(interval) => self.bar_intervals.contains(&interval),
+            Feed::Depth => self.order_book,
+        }
+    }
+}
+
+/// Limits protecting the TCP server from slow or misbehaving clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpLimits {
+    /// Maximum number of concurrently served control sessions.
+    pub max_connections: usize,
+    /// Time allowed for the first command and for completing any started line.
+    pub command_timeout: Duration,
+}
+
+/// Per-connection state kept for the lifetime of a TCP control session.
+#[derive(Debug, Default)]
+struct Session {
+    /// The session's own connection, used for `tcp://` delivery.
+    connection: Option<ConnectionWriter>,
+    /// Address identifying the stream registered by this session, if any.
+    client_addr: Option<SocketAddr>,
+    /// Whether the registered stream is delivered on this connection.
+    streams_on_connection: bool,
+}
+
+impl ConnectionWriter {
+    /// Share the write half of an accepted control connection.
+    pub fn new(stream: &TcpStream) -> Result<Self, QuoteError> {
+        let peer_addr = stream.peer_addr().map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to read TCP peer address")
+        })?;
+        let stream = stream.try_clone().map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to clone TCP stream")
+        })?;
+        Ok(Self {
+            peer_addr,
+            stream: Arc::new(Mutex::new(stream)),
+        })
+    }
+
+    /// Address of the client at the other end of the connection.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Write one newline-terminated line to the connection.
+    pub fn write_line(&self, line: &[u8]) -> Result<(), QuoteError> {
+        let stream = self.stream.lock().map_err(|_| {
+            quote_common::quote_error!(NetworkError, "TCP connection lock poisoned")
+        })?;
+        write_line(&stream, line)
+    }
+
+    /// Build a response and write it while holding the connection, so quote frames
+    /// produced by the command (such as cached quotes of a new stream) follow it.
+    pub fn respond(&self, build: impl FnOnce() -> String) -> Result<(), QuoteError> {
+        let stream = self.stream.lock().map_err(|_| {
+            quote_common::quote_error!(NetworkError, "TCP connection lock poisoned")
+        })?;
+        write_line(&stream, build().as_bytes())
+    }
 }
 
-/// Parse an incoming STREAM command into a `StreamRequest`.
-pub fn parse_stream_command(command: &str) -> Result<StreamRequest, QuoteError> {
+// Writers are compared by identity: two handles are equal when they share one connection.
+impl PartialEq for ConnectionWriter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.stream, &other.stream)
+    }
+}
+
+impl Eq for ConnectionWriter {}
+
+impl Delivery {
+    /// Address identifying the stream in SUBSCRIBE, UNSUBSCRIBE and keep-alive handling.
+    pub fn client_addr(&self) -> SocketAddr {
+        match self {
+            Delivery::Udp(udp_addr) => *udp_addr,
+            Delivery::Tcp(connection) => connection.peer_addr(),
+            Delivery::Multicast { group, .. } => SocketAddr::V4(*group),
+        }
+    }
+}
+
+impl fmt::Display for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delivery::Udp(udp_addr) => write!(f, "{UDP_SCHEME_PREFIX}{udp_addr}"),
+            Delivery::Tcp(connection) => write!(f, "{TCP_SCHEME_PREFIX}{}", connection.peer_addr()),
+            Delivery::Multicast { group, .. } => {
+                write!(f, "{UDP_SCHEME_PREFIX}{group} (multicast)")
+            }
+        }
+    }
+}
+
+/// Parse a comma-separated list of subscription keys: `AAPL` for quotes, `AAPL@1m`
+/// for bars or `AAPL@book` for depth. Keys are returned in canonical form.
+fn parse_ticker_list(list: &str) -> Result<Vec<String>, QuoteError> {
+    let mut tickers = Vec::new();
+    for raw in list.split(',').map(str::trim).filter(|raw| !raw.is_empty()) {
+        let invalid =
+            || quote_common::quote_error!(InvalidCommand, "invalid ticker symbol: {}", raw);
+        let (ticker, feed) = parse_subscription_key(raw).map_err(|_| invalid())?;
+        if ticker.is_empty()
+            || !ticker
+                .chars()
+                .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        tickers.push(subscription_key(&ticker, feed));
+    }
+
+    if tickers.is_empty() {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "ticker list cannot be empty"
+        ));
+    }
+
+    Ok(tickers)
+}
+
+/// Parse the target of a STREAM command: `udp://host:port` or a bare `tcp://`.
+fn parse_stream_target(target: &str) -> Result<StreamTarget, QuoteError> {
+    if let Some(udp_addr) = target.strip_prefix(UDP_SCHEME_PREFIX) {
+        let socket_addr = SocketAddr::from_str(udp_addr).map_err(|_| {
+            quote_common::quote_error!(InvalidCommand, "invalid UDP address: {}", udp_addr)
+        })?;
+        return Ok(StreamTarget::Udp(socket_addr));
+    }
+
+    match target.strip_prefix(TCP_SCHEME_PREFIX) {
+        Some("") => Ok(StreamTarget::Connection),
+        Some(_) => Err(quote_common::quote_error!(
+            InvalidCommand,
+            "tcp:// streams are delivered on the control connection and take no address"
+        )),
+        None => Err(quote_common::quote_error!(
+            InvalidCommand,
+            "STREAM command missing udp:// or tcp:// prefix"
+        )),
+    }
+}
+
+/// Parse an incoming STREAM command into a `StreamCommand`.
+pub fn parse_stream_command(command: &str) -> Result<StreamCommand, QuoteError> {
     let trimmed = command.trim();
     let rest = trimmed
         .strip_prefix(STREAM_PREFIX)
@@ -33,115 +306,552 @@ pub fn parse_stream_command(command: &str) -> Result<StreamRequest, QuoteError>
         quote_common::quote_error!(InvalidCommand, "STREAM command missing ticker list")
     })?;
 
-    let udp_addr = addr_part.strip_prefix(UDP_SCHEME_PREFIX).ok_or_else(|| {
-        quote_common::quote_error!(InvalidCommand, "STREAM command missing udp:// prefix")
-    })?;
+    let target = parse_stream_target(addr_part)?;
 
-    let socket_addr = SocketAddr::from_str(udp_addr).map_err(|_| {
-        quote_common::quote_error!(InvalidCommand, "invalid UDP address: {}", udp_addr)
-    })?;
+    // Tokens of the form `key=value` are options; everything else is part of the ticker list.
+    let mut ticker_tokens = Vec::new();
+    let mut encoding = QuoteEncoding::default();
+    for token in tickers_part.split_whitespace() {
+        match token.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case(ENCODING_OPTION) => {
+                encoding = value.parse().map_err(|_| {
+                    quote_common::quote_error!(InvalidCommand, "unsupported encoding: {}", value)
+                })?;
+            }
+            Some((key, _)) => {
+                return Err(quote_common::quote_error!(
+                    InvalidCommand,
+                    "unknown STREAM option: {}",
+                    key
+                ));
+            }
+            None => ticker_tokens.push(token),
+        }
+    }
 
-    let tickers = tickers_part
-        .split(',')
-        .map(|ticker| ticker.trim().to_uppercase())
-        .filter(|ticker| !ticker.is_empty())
-        .collect::<Vec<_>>();
+    // Frames on the control connection are newline-delimited, which binary quotes cannot be.
+    if target == StreamTarget::Connection && encoding == QuoteEncoding::Binary {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "binary encoding requires udp:// delivery"
+        ));
+    }
 
-    if tickers.is_empty() {
+    let tickers = parse_ticker_list(&ticker_tokens.join(","))?;
+
+    Ok(StreamCommand {
+        target,
+        tickers,
+        encoding,
+    })
+}
+
+fn parse_list_command(rest: &str) -> Result<ControlCommand, QuoteError> {
+    if !rest.is_empty() && !rest.starts_with(' ') {
         return Err(quote_common::quote_error!(
             InvalidCommand,
-            "ticker list cannot be empty"
+            "unknown command: {}{}",
+            LIST_COMMAND,
+            rest
         ));
     }
 
-    for ticker in &tickers {
-        if !ticker
-            .chars()
-            .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit())
-        {
-            return Err(quote_common::quote_error!(
+    let prefix = rest.trim().to_uppercase();
+    if prefix.contains(char::is_whitespace) {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "LIST accepts at most one prefix"
+        ));
+    }
+
+    Ok(ControlCommand::List((!prefix.is_empty()).then_some(prefix)))
+}
+
+/// Parse any command accepted on a TCP control session.
+pub fn parse_command(command: &str) -> Result<ControlCommand, QuoteError> {
+    let trimmed = command.trim();
+    if trimmed.starts_with(STREAM_PREFIX) {
+        return parse_stream_command(trimmed).map(ControlCommand::Stream);
+    }
+    if let Some(rest) = trimmed.strip_prefix(SUBSCRIBE_PREFIX) {
+        return parse_ticker_list(rest).map(ControlCommand::Subscribe);
+    }
+    if let Some(rest) = trimmed.strip_prefix(UNSUBSCRIBE_PREFIX) {
+        return parse_ticker_list(rest).map(ControlCommand::Unsubscribe);
+    }
+    if let Some(rest) = trimmed.strip_prefix(LIST_COMMAND) {
+        return parse_list_command(rest);
+    }
+    if let Some(rest) = trimmed.strip_prefix(SNAPSHOT_PREFIX) {
+        return parse_ticker_list(rest).map(ControlCommand::Snapshot);
+    }
+    if let Some(rest) = trimmed.strip_prefix(ADMIN_PREFIX) {
+        return parse_admin_command(rest).map(ControlCommand::Admin);
+    }
+
+    Err(quote_common::quote_error!(
+        InvalidCommand,
+        "unknown command: {}",
+        trimmed
+    ))
+}
+
+impl Session {
+    fn new(connection: ConnectionWriter) -> Self {
+        Self {
+            connection: Some(connection),
+            ..Self::default()
+        }
+    }
+
+    /// Apply a parsed command, returning the request to forward to the main loop.
+    fn apply(&mut self, command: ControlCommand) -> Result<ControlRequest, QuoteError> {
+        match (command, self.client_addr) {
+            (ControlCommand::Stream(_), Some(client_addr)) => Err(quote_common::quote_error!(
+                InvalidCommand,
+                "stream to {} already active on this connection",
+                client_addr
+            )),
+            (ControlCommand::Stream(command), None) => {
+                let request = self.resolve(command)?;
+                self.client_addr = Some(request.delivery.client_addr());
+                self.streams_on_connection = matches!(request.delivery, Delivery::Tcp(_));
+                Ok(ControlRequest::Stream(request))
+            }
+            (ControlCommand::Subscribe(tickers), Some(client_addr)) => {
+                Ok(ControlRequest::Subscribe {
+                    client_addr,
+                    tickers,
+                })
+            }
+            (ControlCommand::Unsubscribe(tickers), Some(client_addr)) => {
+                Ok(ControlRequest::Unsubscribe {
+                    client_addr,
+                    tickers,
+                })
+            }
+            (ControlCommand::Subscribe(_) | ControlCommand::Unsubscribe(_), None) => Err(
+                quote_common::quote_error!(InvalidCommand, "no active stream on this connection"),
+            ),
+            (
+                ControlCommand::List(_) | ControlCommand::Snapshot(_) | ControlCommand::Admin(_),
+                _,
+            ) => Err(quote_common::quote_error!(
                 InvalidCommand,
-                "invalid ticker symbol: {}",
-                ticker
-            ));
+                "command does not change the stream"
+            )),
         }
     }
 
-    Ok(StreamRequest {
-        udp_addr: socket_addr,
-        tickers,
-    })
+    /// Attach the delivery transport to a parsed STREAM command.
+    fn resolve(&self, command: StreamCommand) -> Result<StreamRequest, QuoteError> {
+        let delivery = match (command.target, &self.connection) {
+            (StreamTarget::Udp(udp_addr), _) => Delivery::Udp(udp_addr),
+            (StreamTarget::Connection, Some(connection)) => Delivery::Tcp(connection.clone()),
+            (StreamTarget::Connection, None) => {
+                return Err(quote_common::quote_error!(
+                    InvalidCommand,
+                    "tcp:// delivery is unavailable on this session"
+                ));
+            }
+        };
+        Ok(StreamRequest {
+            delivery,
+            tickers: command.tickers,
+            encoding: command.encoding,
+        })
+    }
+
+    /// Request to send when the connection closes: streams delivered on it must stop.
+    fn close(&self) -> Option<ControlRequest> {
+        self.client_addr
+            .filter(|_| self.streams_on_connection)
+            .map(|client_addr| ControlRequest::Stop { client_addr })
+    }
 }
 
+fn write_line(mut stream: &TcpStream, line: &[u8]) -> Result<(), QuoteError> {
+    stream
+        .write_all(line)
+        .and_then(|_| stream.write_all(b"\n"))
+        .and_then(|_| stream.flush())
+        .map_err(|err| quote_common::quote_error!(IoError, err, "failed to write response"))
+}
+
+/// Split requested tickers into those the server generates and those it does not.
+/// Fails when none of the requested tickers are known.
+fn validate_tickers(
+    tickers: Vec<String>,
+    universe: &[String],
+    feeds: &PublishedFeeds,
+) -> Result<(Vec<String>, Vec<String>), QuoteError> {
+    let (known, unknown): (Vec<_>, Vec<_>) = tickers
+        .into_iter()
+        .partition(|key| is_known_subscription(key, universe, feeds));
+
+    if known.is_empty() {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "unknown tickers: {}",
+            unknown.join(",")
+        ));
+    }
+
+    Ok((known, unknown))
+}
+
+/// Whether the server publishes `key`: a generated ticker with a feed the server
+/// publishes.
+pub fn is_known_subscription(key: &str, universe: &[String], feeds: &PublishedFeeds) -> bool {
+    parse_subscription_key(key)
+        .is_ok_and(|(ticker, feed)| universe.contains(&ticker) && feeds.publishes(feed))
+}
+
+/// Restrict the tickers of STREAM and SUBSCRIBE commands to the server's universe,
+/// returning the adjusted command and the rejected symbols.
+fn restrict_to_universe(
+    command: ControlCommand,
+    universe: &[String],
+    feeds: &PublishedFeeds,
+) -> Result<(ControlCommand, Vec<String>), QuoteError> {
+    match command {
+        ControlCommand::Stream(mut request) => {
+            let (known, unknown) = validate_tickers(request.tickers, universe, feeds)?;
+            request.tickers = known;
+            Ok((ControlCommand::Stream(request), unknown))
+        }
+        ControlCommand::Subscribe(tickers) => {
+            let (known, unknown) = validate_tickers(tickers, universe, feeds)?;
+            Ok((ControlCommand::Subscribe(known), unknown))
+        }
+        command => Ok((command, Vec::new())),
+    }
+}
+
+/// Build the response to an accepted request, listing the subscribed tickers
+/// when some of the requested ones were rejected.
+fn accepted_response(request: &ControlRequest, rejected: &[String]) -> String {
+    let accepted = match request {
+        ControlRequest::Stream(request) => &request.tickers,
+        ControlRequest::Subscribe { tickers, .. } => tickers,
+        ControlRequest::Unsubscribe { .. } | ControlRequest::Stop { .. } => {
+            return RESPONSE_OK.to_string();
+        }
+    };
+
+    if rejected.is_empty() {
+        RESPONSE_OK.to_string()
+    } else {
+        format!("{RESPONSE_OK} {RESPONSE_PARTIAL} {}", accepted.join(","))
+    }
+}
+
+/// Build the multi-line LIST response: `OK <count>` followed by one ticker per line.
+fn list_response(tickers: &[String], prefix: Option<&str>) -> String {
+    let matching = tickers
+        .iter()
+        .filter(|ticker| prefix.is_none_or(|prefix| ticker.starts_with(prefix)))
+        .collect::<Vec<_>>();
+
+    let mut response = format!("{RESPONSE_OK} {}", matching.len());
+    for ticker in matching {
+        response.push('\n');
+        response.push_str(ticker);
+    }
+    response
+}
+
+/// Build the multi-line SNAPSHOT response: `OK <count>` followed by one JSON quote per line.
+/// Tickers that have not been quoted yet are left out.
+fn snapshot_response(tickers: Vec<String>, context: &SessionContext) -> Result<String, QuoteError> {
+    // Only quotes are cached, so bar and depth keys are reported as unknown.
+    let (known, _) = validate_tickers(tickers, &context.tickers, &PublishedFeeds::default())?;
+    let quotes = context.cache.latest(&known);
+
+    let mut response = format!("{RESPONSE_OK} {}", quotes.len());
+    for quote in &quotes {
+        let line = serde_json::to_string(quote).map_err(|err| {
+            quote_common::quote_error!(ParseError, "failed to serialize quote: {}", err)
+        })?;
+        response.push('\n');
+        response.push_str(&line);
+    }
+    Ok(response)
+}
+
+/// Forward an admin command to the generator once the feature is enabled and the
+/// ticker is known.
+fn admin_response(command: AdminCommand, context: &SessionContext) -> Result<String, QuoteError> {
+    let Some(admin_tx) = &context.admin_tx else {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "admin commands are disabled"
+        ));
+    };
+    if !context
+        .tickers
+        .iter()
+        .any(|ticker| ticker == command.ticker())
+    {
+        return Err(quote_common::quote_error!(
+            InvalidCommand,
+            "unknown tickers: {}",
+            command.ticker()
+        ));
+    }
+    admin_tx
+        .send(command)
+        .map_err(|_| quote_common::quote_error!(NetworkError, "quote generator unavailable"))?;
+    Ok(RESPONSE_OK.to_string())
+}
+
+fn handle_line(
+    line: &str,
+    session: &mut Session,
+    context: &SessionContext,
+    peer_addr: &str,
+) -> String {
+    let command = match parse_command(line) {
+        Ok(ControlCommand::List(prefix)) => {
+            debug!("LIST request from {peer_addr}");
+            return list_response(&context.tickers, prefix.as_deref());
+        }
+        Ok(ControlCommand::Snapshot(tickers)) => {
+            debug!("SNAPSHOT request from {peer_addr}");
+            return snapshot_response(tickers, context).unwrap_or_else(|err| {
+                warn!("Rejected SNAPSHOT from {peer_addr}: {err}");
+                format!("{RESPONSE_ERR_PREFIX}{err}")
+            });
+        }
+        Ok(ControlCommand::Admin(command)) => {
+            info!("ADMIN request from {peer_addr}: {command:?}");
+            return admin_response(command, context).unwrap_or_else(|err| {
+                warn!("Rejected ADMIN from {peer_addr}: {err}");
+                format!("{RESPONSE_ERR_PREFIX}{err}")
+            });
+        }
+        Ok(command) => command,
+        Err(err) => {
+            warn!("Invalid command from {peer_addr}: {err}");
+            return format!("{RESPONSE_ERR_PREFIX}{err}");
+        }
+    };
+
+    let request = restrict_to_universe(command, &context.tickers, &context.feeds)
+        .and_then(|(command, rejected)| session.apply(command).map(|request| (request, rejected)));
+    let (request, rejected) = match request {
+        Ok(request) => request,
+        Err(err) => {
+            warn!("Rejected command from {peer_addr}: {err}");
+            return format!("{RESPONSE_ERR_PREFIX}{err}");
+        }
+    };
+
+    let description = describe_request(&request);
+    let response = accepted_response(&request, &rejected);
+    if let Err(err) = context.request_tx.send(request) {
+        warn!("Failed to forward request from {peer_addr}: {err}");
+        return format!("{RESPONSE_ERR_PREFIX}server unavailable");
+    }
+
+    if rejected.is_empty() {
+        info!("Accepted {description} from {peer_addr}");
+    } else {
+        info!(
+            "Partially accepted {description} from {peer_addr}; unknown tickers: {}",
+            rejected.join(",")
+        );
+    }
+    response
+}
+
+fn describe_request(request: &ControlRequest) -> String {
+    match request {
+        ControlRequest::Stream(request) => format!("STREAM for {}", request.tickers.join(",")),
+        ControlRequest::Subscribe { tickers, .. } => format!("SUBSCRIBE for {}", tickers.join(",")),
+        ControlRequest::Unsubscribe { tickers, .. } => {
+            format!("UNSUBSCRIBE for {}", tickers.join(","))
+        }
+        ControlRequest::Stop { client_addr } => format!("stop of stream to {client_addr}"),
+    }
+}
+
+/// Serve a control session until the peer disconnects, misbehaves or shutdown is requested.
 fn handle_connection(
-    mut stream: TcpStream,
-    request_tx: &Sender<StreamRequest>,
+    stream: TcpStream,
+    context: &SessionContext,
+    limits: &TcpLimits,
+    shutdown: &AtomicBool,
 ) -> Result<(), QuoteError> {
     let peer_addr = stream
         .peer_addr()
         .map(|addr| addr.to_string())
         .unwrap_or_else(|_| UNKNOWN_ADDR_PLACEHOLDER.to_string());
 
-    let mut reader = BufReader::new(&stream);
+    // Blocking reads with a short timeout let the session notice shutdown.
+    stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(Some(Duration::from_millis(TCP_POLL_INTERVAL_MS))))
+        .and_then(|_| stream.set_write_timeout(Some(limits.command_timeout)))
+        .map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to configure TCP session socket")
+        })?;
+
+    let connection = ConnectionWriter::new(&stream)?;
+    let mut session = Session::new(connection.clone());
+    let result = serve_commands(
+        &stream,
+        &connection,
+        &mut session,
+        context,
+        limits,
+        shutdown,
+        &peer_addr,
+    );
+
+    if let Some(request) = session.close() {
+        // Wake the stream writer blocked on this socket, then have the dispatcher drop it.
+        if let Err(err) = stream.shutdown(Shutdown::Both) {
+            debug!("Failed to shut down TCP stream to {peer_addr}: {err}");
+        }
+        if let Err(err) = context.request_tx.send(request) {
+            warn!("Failed to stop TCP stream to {peer_addr}: {err}");
+        }
+    }
+
+    debug!("TCP session with {peer_addr} closed");
+    result
+}
+
+/// Read and answer commands until the peer disconnects, misbehaves or shutdown is requested.
+fn serve_commands(
+    stream: &TcpStream,
+    connection: &ConnectionWriter,
+    session: &mut Session,
+    context: &SessionContext,
+    limits: &TcpLimits,
+    shutdown: &AtomicBool,
+    peer_addr: &str,
+) -> Result<(), QuoteError> {
+    let mut reader = BufReader::new(stream);
     let mut line = String::new();
-    let bytes_read = reader.read_line(&mut line).map_err(|err| {
-        quote_common::quote_error!(IoError, err, "failed to read line from TCP stream")
-    })?;
+    // The first command must arrive promptly; afterwards only started lines are timed.
+    let mut waiting_since = Some(Instant::now());
 
-    if bytes_read == 0 {
-        return Ok(());
-    }
-
-    match parse_stream_command(&line) {
-        Ok(request) => {
-            if let Err(err) = request_tx.send(request.clone()) {
-                let message = "server unavailable";
-                stream
-                    .write_all(format!("{RESPONSE_ERR_PREFIX}{message}\n").as_bytes())
-                    .and_then(|_| stream.flush())
-                    .map_err(|err| {
-                        quote_common::quote_error!(IoError, err, "failed to write error response")
-                    })?;
-                warn!("Failed to forward stream request from {peer_addr}: {err}");
-            } else {
-                stream
-                    .write_all(format!("{}\n", quote_common::RESPONSE_OK).as_bytes())
-                    .and_then(|_| stream.flush())
-                    .map_err(|err| {
-                        quote_common::quote_error!(IoError, err, "failed to write OK response")
-                    })?;
-                info!(
-                    "Accepted STREAM request from {peer_addr} for {}",
-                    request.tickers.join(",")
-                );
+    while !shutdown.load(Ordering::SeqCst) {
+        let limit = (MAX_COMMAND_LENGTH + 1 - line.len()) as u64;
+        match (&mut reader).take(limit).read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) if !line.ends_with('\n') && line.len() > MAX_COMMAND_LENGTH => {
+                warn!("Command from {peer_addr} exceeds {MAX_COMMAND_LENGTH} bytes");
+                connection
+                    .write_line(format!("{RESPONSE_ERR_PREFIX}command too long").as_bytes())?;
+                break;
+            }
+            Ok(_) => {
+                connection.respond(|| handle_line(&line, session, context, peer_addr))?;
+                line.clear();
+                waiting_since = None;
+            }
+            // A timed out read keeps any partial line in the buffer for the next attempt.
+            Err(err)
+                if err.kind() == std::io::ErrorKind::WouldBlock
+                    || err.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                if !line.is_empty() && waiting_since.is_none() {
+                    waiting_since = Some(Instant::now());
+                }
+                if waiting_since.is_some_and(|since| since.elapsed() > limits.command_timeout) {
+                    warn!(
+                        "Closing TCP session with {peer_addr}: no complete command within {:?}",
+                        limits.command_timeout
+                    );
+                    connection
+                        .write_line(format!("{RESPONSE_ERR_PREFIX}command timed out").as_bytes())?;
+                    break;
+                }
+            }
+            Err(err) => {
+                return Err(quote_common::quote_error!(
+                    IoError,
+                    err,
+                    "failed to read line from TCP stream"
+                ));
             }
-        }
-        Err(err) => {
-            stream
-                .write_all(format!("{RESPONSE_ERR_PREFIX}{}\n", err).as_bytes())
-                .and_then(|_| stream.flush())
-                .map_err(|err| {
-                    quote_common::quote_error!(IoError, err, "failed to write error response")
-                })?;
-            warn!("Invalid STREAM command from {peer_addr}: {err}");
         }
     }
 
     Ok(())
 }
 
-/// Start TCP server listening for STREAM commands, returning a shutdown sender and join handle.
+/// Turn away a connection accepted while the server is at capacity.
+fn reject_connection(stream: TcpStream, max_connections: usize) {
+    let peer_addr = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|_| UNKNOWN_ADDR_PLACEHOLDER.to_string());
+    warn!("Rejecting TCP connection from {peer_addr}: {max_connections} sessions already active");
+    if let Err(err) = write_line(
+        &stream,
+        format!("{RESPONSE_ERR_PREFIX}server busy").as_bytes(),
+    ) {
+        debug!("Failed to notify {peer_addr} of rejection: {err}");
+    }
+}
+
+fn spawn_session(
+    stream: TcpStream,
+    session_id: usize,
+    context: SessionContext,
+    limits: TcpLimits,
+    shutdown: Arc<AtomicBool>,
+) -> Result<thread::JoinHandle<()>, QuoteError> {
+    thread::Builder::new()
+        .name(format!("{TCP_SESSION_THREAD_NAME_PREFIX}{session_id}"))
+        .spawn(move || {
+            if let Err(err) = handle_connection(stream, &context, &limits, &shutdown) {
+                warn!("Failed to handle connection: {err}");
+            }
+        })
+        .map_err(|err| {
+            quote_common::quote_error!(IoError, err, "failed to spawn TCP session thread")
+        })
+}
+
+/// Join session threads that have already finished.
+fn reap_sessions(sessions: &mut Vec<thread::JoinHandle<()>>) {
+    let (finished, running): (Vec<_>, Vec<_>) =
+        sessions.drain(..).partition(|handle| handle.is_finished());
+    *sessions = running;
+    for handle in finished {
+        if handle.join().is_err() {
+            warn!("TCP session thread panicked");
+        }
+    }
+}
+
+/// Start TCP server accepting control sessions, returning a shutdown sender and join handle.
 pub fn start_tcp_server(
     addr: SocketAddr,
-    request_tx: Sender<StreamRequest>,
+    context: SessionContext,
+    limits: TcpLimits,
 ) -> Result<(Sender<()>, thread::JoinHandle<()>), QuoteError> {
     let listener = TcpListener::bind(addr).map_err(|err| {
         quote_common::quote_error!(IoError, err, "failed to bind TCP listener to {}", addr)
     })?;
+    info!("TCP server listening on {addr}");
+    serve(listener, context, limits)
+}
+
+/// Accept connections on a bound listener, serving each session on its own thread.
+fn serve(
+    listener: TcpListener,
+    context: SessionContext,
+    limits: TcpLimits,
+) -> Result<(Sender<()>, thread::JoinHandle<()>), QuoteError> {
     listener.set_nonblocking(true).map_err(|err| {
         quote_common::quote_error!(IoError, err, "failed to set TCP listener to non-blocking")
     })?;
-    info!("TCP server listening on {addr}");
 
     let (shutdown_tx, shutdown_rx) = crossbeam::channel::bounded(1);
 
@@ -149,16 +859,32 @@ pub fn start_tcp_server(
         .name(TCP_LISTENER_THREAD_NAME.to_string())
         .spawn(move || {
             let poll_interval = Duration::from_millis(TCP_POLL_INTERVAL_MS);
+            let session_shutdown = Arc::new(AtomicBool::new(false));
+            let mut sessions = Vec::new();
+            let mut next_session_id: usize = 0;
             loop {
                 if shutdown_rx.try_recv().is_ok() {
                     break;
                 }
 
+                reap_sessions(&mut sessions);
+
                 match listener.accept() {
+                    Ok((stream, _)) if sessions.len() >= limits.max_connections => {
+                        reject_connection(stream, limits.max_connections);
+                    }
                     Ok((stream, _)) => {
-                        if let Err(err) = handle_connection(stream, &request_tx) {
-                            warn!("Failed to handle connection: {err}");
+                        match spawn_session(
+                            stream,
+                            next_session_id,
+                            context.clone(),
+                            limits,
+                            Arc::clone(&session_shutdown),
+                        ) {
+                            Ok(handle) => sessions.push(handle),
+                            Err(err) => warn!("Failed to start TCP session: {err}"),
                         }
+                        next_session_id += 1;
                     }
                     Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
                         thread::sleep(poll_interval);
@@ -169,6 +895,13 @@ pub fn start_tcp_server(
                     }
                 }
             }
+
+            session_shutdown.store(true, Ordering::SeqCst);
+            for handle in sessions {
+                if handle.join().is_err() {
+                    warn!("TCP session thread panicked during shutdown");
+                }
+            }
             info!("TCP server shutting down");
         })
         .map_err(|err| {
@@ -186,8 +919,44 @@ mod tests {
     fn test_parse_stream_command_valid() {
         let command = "STREAM udp://127.0.0.1:9000 aapl, tsla \n";
         let result = parse_stream_command(command).expect("valid command");
-        assert_eq!(result.udp_addr, "127.0.0.1:9000".parse().unwrap());
+        assert_eq!(
+            result.target,
+            StreamTarget::Udp("127.0.0.1:9000".parse().unwrap())
+        );
         assert_eq!(result.tickers, vec!["AAPL".to_string(), "TSLA".to_string()]);
+        assert_eq!(result.encoding, QuoteEncoding::Json);
+    }
+
+    #[test]
+    fn test_parse_stream_command_tcp_target() {
+        let result = parse_stream_command("STREAM tcp:// AAPL,TSLA\n").expect("valid command");
+        assert_eq!(result.target, StreamTarget::Connection);
+        assert_eq!(result.tickers, vec!["AAPL".to_string(), "TSLA".to_string()]);
+
+        let err = parse_stream_command("STREAM tcp://127.0.0.1:9000 AAPL").expect_err("address");
+        assert!(err.to_string().contains("take no address"));
+
+        let err = parse_stream_command("STREAM tcp:// AAPL encoding=binary").expect_err("binary");
+        assert!(err.to_string().contains("requires udp://"));
+    }
+
+    #[test]
+    fn test_parse_stream_command_with_encoding_option() {
+        let command = "STREAM udp://127.0.0.1:9000 AAPL,TSLA encoding=binary\n";
+        let result = parse_stream_command(command).expect("valid command");
+        assert_eq!(result.tickers, vec!["AAPL".to_string(), "TSLA".to_string()]);
+        assert_eq!(result.encoding, QuoteEncoding::Binary);
+    }
+
+    #[test]
+    fn test_parse_stream_command_rejects_unknown_option() {
+        let err = parse_stream_command("STREAM udp://127.0.0.1:9000 AAPL encoding=xml")
+            .expect_err("should fail");
+        assert!(err.to_string().contains("unsupported encoding"));
+
+        let err = parse_stream_command("STREAM udp://127.0.0.1:9000 AAPL format=binary")
+            .expect_err("should fail");
+        assert!(err.to_string().contains("unknown STREAM option"));
     }
 
     #[test]
@@ -214,4 +983,388 @@ mod tests {
             parse_stream_command("STREAM udp://127.0.0.1:9000 a$pl").expect_err("should fail");
         assert!(matches!(err, QuoteError::InvalidCommand { .. }));
     }
+
+    #[test]
+    fn test_parse_command_subscribe() {
+        let command = parse_command("SUBSCRIBE msft, nvda\n").expect("valid command");
+        assert_eq!(
+            command,
+            ControlCommand::Subscribe(vec!["MSFT".to_string(), "NVDA".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_command_unsubscribe() {
+        let command = parse_command("UNSUBSCRIBE AAPL").expect("valid command");
+        assert_eq!(
+            command,
+            ControlCommand::Unsubscribe(vec!["AAPL".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_command_unknown() {
+        let err = parse_command("HELLO").expect_err("should fail");
+        assert!(matches!(err, QuoteError::InvalidCommand { .. }));
+    }
+
+    #[test]
+    fn test_session_rejects_subscribe_without_stream() {
+        let mut session = Session::default();
+        let err = session
+            .apply(ControlCommand::Subscribe(vec!["AAPL".to_string()]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("no active stream"));
+    }
+
+    #[test]
+    fn test_session_forwards_updates_for_active_stream() {
+        let mut session = Session::default();
+        let command = parse_stream_command("STREAM udp://127.0.0.1:9000 AAPL").unwrap();
+        let client_addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+
+        let forwarded = session
+            .apply(ControlCommand::Stream(command.clone()))
+            .expect("stream accepted");
+        assert_eq!(
+            forwarded,
+            ControlRequest::Stream(StreamRequest {
+                delivery: Delivery::Udp(client_addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            })
+        );
+
+        let forwarded = session
+            .apply(ControlCommand::Unsubscribe(vec!["AAPL".to_string()]))
+            .expect("unsubscribe accepted");
+        assert_eq!(
+            forwarded,
+            ControlRequest::Unsubscribe {
+                client_addr,
+                tickers: vec!["AAPL".to_string()],
+            }
+        );
+
+        let err = session
+            .apply(ControlCommand::Stream(command))
+            .expect_err("second stream rejected");
+        assert!(err.to_string().contains("already active"));
+        assert_eq!(session.close(), None, "UDP streams outlive the connection");
+    }
+
+    fn test_context() -> (SessionContext, crossbeam::channel::Receiver<ControlRequest>) {
+        let (request_tx, request_rx) = crossbeam::channel::unbounded();
+        let tickers = ["AAPL", "AMZN", "MSFT"].map(String::from).to_vec();
+        let context = SessionContext {
+            request_tx,
+            tickers: Arc::new(tickers),
+            cache: QuoteCache::default(),
+            feeds: Arc::new(PublishedFeeds {
+                bar_intervals: vec!["1m".parse().expect("interval")],
+                order_book: true,
+            }),
+            admin_tx: None,
+        };
+        (context, request_rx)
+    }
+
+    #[test]
+    fn test_parse_command_list() {
+        assert_eq!(parse_command("LIST\n").unwrap(), ControlCommand::List(None));
+        assert_eq!(
+            parse_command("LIST a\n").unwrap(),
+            ControlCommand::List(Some("A".to_string()))
+        );
+        assert!(parse_command("LISTING").is_err());
+        assert!(parse_command("LIST A B").is_err());
+    }
+
+    #[test]
+    fn test_list_response_filters_by_prefix() {
+        let (context, _request_rx) = test_context();
+        assert_eq!(
+            list_response(&context.tickers, None),
+            "OK 3\nAAPL\nAMZN\nMSFT"
+        );
+        assert_eq!(
+            list_response(&context.tickers, Some("A")),
+            "OK 2\nAAPL\nAMZN"
+        );
+        assert_eq!(list_response(&context.tickers, Some("Z")), "OK 0");
+    }
+
+    #[test]
+    fn test_handle_line_snapshot_returns_cached_quotes() {
+        let (context, request_rx) = test_context();
+        let mut session = Session::default();
+        let quote = quote_common::StockQuote::new("AAPL", 150.0, 1_000);
+        context.cache.update(&quote);
+
+        assert_eq!(
+            parse_command("SNAPSHOT aapl,msft\n").unwrap(),
+            ControlCommand::Snapshot(vec!["AAPL".to_string(), "MSFT".to_string()])
+        );
+
+        let response = handle_line("SNAPSHOT AAPL,MSFT,XYZ\n", &mut session, &context, "test");
+        let mut lines = response.lines();
+        assert_eq!(lines.next(), Some("OK 1"));
+        let received: quote_common::StockQuote =
+            serde_json::from_str(lines.next().expect("quote line")).expect("valid JSON");
+        assert_eq!(received, quote);
+        assert_eq!(lines.next(), None);
+        assert!(
+            request_rx.try_recv().is_err(),
+            "SNAPSHOT is answered locally"
+        );
+
+        let response = handle_line("SNAPSHOT XYZ\n", &mut session, &context, "test");
+        assert!(response.contains("unknown tickers: XYZ"));
+    }
+
+    #[test]
+    fn test_handle_line_admin_requires_enabled_commands() {
+        let (mut context, request_rx) = test_context();
+        let mut session = Session::default();
+
+        let response = handle_line("ADMIN SHOCK AAPL -15%\n", &mut session, &context, "test");
+        assert!(response.contains("admin commands are disabled"));
+
+        let (admin_tx, admin_rx) = crossbeam::channel::unbounded();
+        context.admin_tx = Some(admin_tx);
+        let response = handle_line("ADMIN SHOCK aapl -15%\n", &mut session, &context, "test");
+        assert_eq!(response, RESPONSE_OK);
+        assert_eq!(
+            admin_rx.try_recv().unwrap(),
+            AdminCommand::Shock {
+                ticker: "AAPL".to_string(),
+                percent: -15.0
+            }
+        );
+
+        let response = handle_line("ADMIN HALT XYZ\n", &mut session, &context, "test");
+        assert!(response.contains("unknown tickers: XYZ"));
+        let response = handle_line("ADMIN CRASH AAPL\n", &mut session, &context, "test");
+        assert!(response.starts_with(RESPONSE_ERR_PREFIX));
+        assert!(admin_rx.try_recv().is_err());
+        assert!(
+            request_rx.try_recv().is_err(),
+            "ADMIN does not touch the stream"
+        );
+    }
+
+    #[test]
+    fn test_handle_line_responds_and_forwards() {
+        let (context, request_rx) = test_context();
+        let mut session = Session::default();
+
+        let response = handle_line(
+            "STREAM udp://127.0.0.1:9000 AAPL\n",
+            &mut session,
+            &context,
+            "test",
+        );
+        assert_eq!(response, RESPONSE_OK);
+        assert!(matches!(
+            request_rx.try_recv(),
+            Ok(ControlRequest::Stream(_))
+        ));
+
+        let response = handle_line("SUBSCRIBE $$$\n", &mut session, &context, "test");
+        assert!(response.starts_with(RESPONSE_ERR_PREFIX));
+        assert!(request_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_handle_line_rejects_unknown_tickers() {
+        let (context, request_rx) = test_context();
+        let mut session = Session::default();
+
+        let response = handle_line(
+            "STREAM udp://127.0.0.1:9000 APPL,MSTF\n",
+            &mut session,
+            &context,
+            "test",
+        );
+        assert!(response.starts_with(RESPONSE_ERR_PREFIX));
+        assert!(response.contains("unknown tickers: APPL,MSTF"));
+        assert!(request_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_handle_line_accepts_bar_and_depth_subscriptions_for_published_feeds() {
+        let (context, request_rx) = test_context();
+        let mut session = Session::default();
+
+        assert_eq!(
+            parse_ticker_list("aapl@1M, msft").unwrap(),
+            vec!["AAPL@1m".to_string(), "MSFT".to_string()]
+        );
+        assert!(parse_ticker_list("AAPL@soon").is_err());
+
+        let response = handle_line(
+            "STREAM udp://127.0.0.1:9000 AAPL@1m,AAPL@5m,MSFT@book\n",
+            &mut session,
+            &context,
+            "test",
+        );
+        assert_eq!(response, "OK PARTIAL AAPL@1m,MSFT@book");
+        match request_rx.try_recv() {
+            Ok(ControlRequest::Stream(request)) => {
+                assert_eq!(request.tickers, vec!["AAPL@1m", "MSFT@book"]);
+            }
+            other => panic!("Expected stream request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_handle_line_partially_accepts_stream() {
+        let (context, request_rx) = test_context();
+        let mut session = Session::default();
+
+        let response = handle_line(
+            "STREAM udp://127.0.0.1:9000 AAPL,APPL,MSFT\n",
+            &mut session,
+            &context,
+            "test",
+        );
+        assert_eq!(response, "OK PARTIAL AAPL,MSFT");
+        match request_rx.try_recv() {
+            Ok(ControlRequest::Stream(request)) => {
+                assert_eq!(request.tickers, vec!["AAPL", "MSFT"]);
+            }
+            other => panic!("Expected stream request, got {other:?}"),
+        }
+
+        let response = handle_line("SUBSCRIBE AMZN,XYZ\n", &mut session, &context, "test");
+        assert_eq!(response, "OK PARTIAL AMZN");
+    }
+
+    fn start_test_server(
+        limits: TcpLimits,
+    ) -> (
+        SocketAddr,
+        Sender<()>,
+        thread::JoinHandle<()>,
+        crossbeam::channel::Receiver<ControlRequest>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let addr = listener.local_addr().expect("local addr");
+        let (context, request_rx) = test_context();
+        let (shutdown_tx, handle) = serve(listener, context, limits).expect("serve");
+        (addr, shutdown_tx, handle, request_rx)
+    }
+
+    fn read_response_line(stream: &TcpStream) -> String {
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .expect("set timeout");
+        let mut line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut line)
+            .expect("read response");
+        line.trim_end().to_string()
+    }
+
+    #[test]
+    fn test_idle_client_does_not_block_other_sessions() {
+        let (addr, shutdown_tx, handle, _request_rx) = start_test_server(TcpLimits {
+            max_connections: 4,
+            command_timeout: Duration::from_secs(5),
+        });
+
+        let _idle = TcpStream::connect(addr).expect("connect idle client");
+        let mut partial = TcpStream::connect(addr).expect("connect partial client");
+        partial.write_all(b"LIST").expect("write partial line");
+
+        let mut active = TcpStream::connect(addr).expect("connect active client");
+        active.write_all(b"LIST M\n").expect("write command");
+        assert_eq!(read_response_line(&active), "OK 1");
+
+        shutdown_tx.send(()).expect("signal shutdown");
+        handle.join().expect("join listener");
+    }
+
+    #[test]
+    fn test_connections_over_limit_are_rejected() {
+        let (addr, shutdown_tx, handle, _request_rx) = start_test_server(TcpLimits {
+            max_connections: 1,
+            command_timeout: Duration::from_secs(5),
+        });
+
+        let _first = TcpStream::connect(addr).expect("connect first client");
+        thread::sleep(Duration::from_millis(3 * TCP_POLL_INTERVAL_MS));
+        let second = TcpStream::connect(addr).expect("connect second client");
+        assert_eq!(read_response_line(&second), "ERR server busy");
+
+        shutdown_tx.send(()).expect("signal shutdown");
+        handle.join().expect("join listener");
+    }
+
+    #[test]
+    fn test_incomplete_command_times_out() {
+        let (addr, shutdown_tx, handle, _request_rx) = start_test_server(TcpLimits {
+            max_connections: 4,
+            command_timeout: Duration::from_millis(200),
+        });
+
+        let mut client = TcpStream::connect(addr).expect("connect client");
+        client.write_all(b"LIST").expect("write partial line");
+        assert_eq!(read_response_line(&client), "ERR command timed out");
+
+        shutdown_tx.send(()).expect("signal shutdown");
+        handle.join().expect("join listener");
+    }
+
+    #[test]
+    fn test_overlong_command_is_rejected() {
+        let (addr, shutdown_tx, handle, _request_rx) = start_test_server(TcpLimits {
+            max_connections: 4,
+            command_timeout: Duration::from_secs(5),
+        });
+
+        let mut client = TcpStream::connect(addr).expect("connect client");
+        client
+            .write_all(&vec![b'A'; MAX_COMMAND_LENGTH + 1])
+            .expect("write long line");
+        assert_eq!(read_response_line(&client), "ERR command too long");
+
+        shutdown_tx.send(()).expect("signal shutdown");
+        handle.join().expect("join listener");
+    }
+
+    #[test]
+    fn test_tcp_stream_delivers_on_connection_and_stops_on_close() {
+        let (addr, shutdown_tx, handle, request_rx) = start_test_server(TcpLimits {
+            max_connections: 4,
+            command_timeout: Duration::from_secs(5),
+        });
+
+        let mut client = TcpStream::connect(addr).expect("connect client");
+        let client_addr = client.local_addr().expect("local addr");
+        client
+            .write_all(b"STREAM tcp:// AAPL\n")
+            .expect("write command");
+        assert_eq!(read_response_line(&client), "OK");
+
+        let request = match request_rx.recv_timeout(Duration::from_secs(2)) {
+            Ok(ControlRequest::Stream(request)) => request,
+            other => panic!("Expected stream request, got {other:?}"),
+        };
+        assert_eq!(request.delivery.client_addr(), client_addr);
+        let Delivery::Tcp(connection) = request.delivery else {
+            panic!("Expected delivery on the control connection");
+        };
+        connection.write_line(b"frame").expect("write frame");
+        assert_eq!(read_response_line(&client), "frame");
+
+        drop(client);
+        assert_eq!(
+            request_rx.recv_timeout(Duration::from_secs(2)),
+            Ok(ControlRequest::Stop { client_addr })
+        );
+
+        shutdown_tx.send(()).expect("signal shutdown");
+        handle.join().expect("join listener");
+    }
 }
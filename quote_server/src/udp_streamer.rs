@@ -1,52 +1,102 @@
 use std::collections::{HashMap, HashSet};
-use std::net::UdpSocket;
+use std::net::{Ipv4Addr, UdpSocket};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender};
 use log::{debug, info, warn};
-use serde_json::to_vec;
+use quote_common::{
+    DEFAULT_KEEPALIVE_TIMEOUT_SECS, DEFAULT_UDP_FLUSH_INTERVAL_MS, DEFAULT_UDP_MAX_PAYLOAD,
+    ErrorNotice, HEARTBEAT_INTERVAL_SECS, Heartbeat, PING_PAYLOAD, PacketBuilder, QuoteError,
+    StockQuote, StreamMessage, encode_message,
+};
 
-use quote_common::{DEFAULT_KEEPALIVE_TIMEOUT_SECS, PING_PAYLOAD, QuoteError, StockQuote};
-
-use crate::tcp_handler::StreamRequest;
+use crate::quote_cache::QuoteCache;
+use crate::tcp_handler::{ConnectionWriter, Delivery, StreamRequest};
 
 // Constants replacing magic numbers/words in this module
 const UDP_DISPATCHER_THREAD_NAME: &str = "udp-dispatcher";
 const UDP_CLIENT_THREAD_NAME_PREFIX: &str = "udp-client-";
 const PING_BUFFER_SIZE: usize = 16;
 const CLIENT_RECV_POLL_TIMEOUT_MS: u64 = 100;
+/// Longest the dispatcher waits for a message or command before reading PINGs, so
+/// clients stay alive while the source is quiet.
+const PING_POLL_INTERVAL_MS: u64 = 100;
 
 /// Commands sent to the UDP dispatcher.
 #[derive(Debug)]
 pub enum UdpCommand {
     /// Add a new client to receive filtered quotes.
     AddClient(StreamRequest),
+    /// Add tickers to every client identified by `client_addr`.
+    Subscribe {
+        client_addr: std::net::SocketAddr,
+        tickers: Vec<String>,
+    },
+    /// Remove tickers from every client identified by `client_addr`.
+    Unsubscribe {
+        client_addr: std::net::SocketAddr,
+        tickers: Vec<String>,
+    },
+    /// Stop and remove every client identified by `client_addr`.
+    RemoveClient { client_addr: std::net::SocketAddr },
     /// Shutdown the dispatcher and terminate all client threads.
     Shutdown,
 }
 
+/// Limits for packing several quotes into one UDP datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Batching {
+    /// Maximum datagram size in bytes, including the envelope.
+    pub max_payload: usize,
+    /// Longest time a partially filled datagram waits for more quotes.
+    pub flush_interval: Duration,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self {
+            max_payload: DEFAULT_UDP_MAX_PAYLOAD,
+            flush_interval: Duration::from_millis(DEFAULT_UDP_FLUSH_INTERVAL_MS),
+        }
+    }
+}
+
 struct ClientChannels {
+    /// Subscription keys: tickers for quotes, `TICKER@interval` for bars.
     tickers: HashSet<String>,
-    sender: Sender<StockQuote>,
+    sender: Sender<StreamMessage>,
     handle: thread::JoinHandle<()>,
     last_ping: Arc<Mutex<Instant>>,
-    timeout: Duration,
-    udp_addr: std::net::SocketAddr,
+    /// Keep-alive timeout; `None` for clients served on their TCP connection.
+    timeout: Option<Duration>,
+    client_addr: std::net::SocketAddr,
 }
 
-/// Start a UDP dispatcher that distributes quotes to client threads.
+/// Start a UDP dispatcher that distributes quotes and bars to client threads.
+/// Every quote is also recorded in `cache`, which primes newly subscribed clients.
 pub fn start_udp_streamer(
-    quote_rx: Receiver<StockQuote>,
+    message_rx: Receiver<StreamMessage>,
     keepalive_timeout: Duration,
     server_udp_addr: std::net::SocketAddr,
+    batching: Batching,
+    cache: QuoteCache,
 ) -> Result<(Sender<UdpCommand>, thread::JoinHandle<()>), QuoteError> {
     let (command_tx, command_rx) = channel::unbounded::<UdpCommand>();
 
     let handle = thread::Builder::new()
         .name(UDP_DISPATCHER_THREAD_NAME.to_string())
-        .spawn(move || dispatcher_loop(quote_rx, command_rx, keepalive_timeout, server_udp_addr))
+        .spawn(move || {
+            dispatcher_loop(
+                message_rx,
+                command_rx,
+                keepalive_timeout,
+                server_udp_addr,
+                batching,
+                cache,
+            )
+        })
         .map_err(|err| {
             quote_common::quote_error!(IoError, err, "failed to spawn UDP dispatcher thread")
         })?;
@@ -55,10 +105,12 @@ pub fn start_udp_streamer(
 }
 
 fn dispatcher_loop(
-    quote_rx: Receiver<StockQuote>,
+    mut message_rx: Receiver<StreamMessage>,
     command_rx: Receiver<UdpCommand>,
     keepalive_timeout: Duration,
     server_udp_addr: std::net::SocketAddr,
+    batching: Batching,
+    cache: QuoteCache,
 ) {
     let mut clients: HashMap<usize, ClientChannels> = HashMap::new();
     let mut next_id: usize = 0;
@@ -83,27 +135,65 @@ fn dispatcher_loop(
         crossbeam::channel::select! {
             recv(command_rx) -> command => match command {
                 Ok(UdpCommand::AddClient(request)) => {
-                    if let Err(err) = register_client(&mut clients, &mut next_id, request, keepalive_timeout, server_udp_addr) {
-                        warn!("Failed to register UDP client: {err}");
+                    let latest = cache.latest(&request.tickers);
+                    match register_client(&mut clients, &mut next_id, request, keepalive_timeout, server_udp_addr, batching) {
+                        Ok(client_id) => prime_client(&clients, client_id, latest),
+                        Err(err) => warn!("Failed to register UDP client: {err}"),
+                    }
+                }
+                Ok(UdpCommand::Subscribe { client_addr, tickers }) => {
+                    for client_id in update_client_tickers(&mut clients, client_addr, &tickers, true) {
+                        prime_client(&clients, client_id, cache.latest(&tickers));
                     }
                 }
+                Ok(UdpCommand::Unsubscribe { client_addr, tickers }) => {
+                    update_client_tickers(&mut clients, client_addr, &tickers, false);
+                }
+                Ok(UdpCommand::RemoveClient { client_addr }) => {
+                    remove_clients(&mut clients, client_addr);
+                }
                 Ok(UdpCommand::Shutdown) | Err(_) => break,
             },
-            recv(quote_rx) -> message => match message {
-                Ok(quote) => {
-                    deliver_quote(&mut clients, &quote);
+            recv(message_rx) -> message => match message {
+                Ok(message) => {
+                    if let StreamMessage::Quote(quote) = &message {
+                        cache.update(quote);
+                    }
+                    deliver_message(&mut clients, &message);
                 }
-                Err(_) => break,
-            }
+                // A finished replay leaves nothing to deliver, but SNAPSHOT and existing
+                // clients keep being served, their PINGs read on the poll timer, until
+                // shutdown.
+                Err(_) => {
+                    info!("Quote source finished; no further messages to deliver");
+                    message_rx = channel::never();
+                }
+            },
+            // The source may be quiet for hours, e.g. while the market is closed.
+            default(Duration::from_millis(PING_POLL_INTERVAL_MS)) => {}
         }
 
-        // Check for PINGs on shared socket
-        match ping_socket.recv_from(&mut ping_buffer) {
+        read_pings(&ping_socket, &mut ping_buffer, &clients);
+        purge_expired_clients(&mut clients);
+    }
+
+    shutdown_clients(clients);
+    info!("UDP dispatcher shutting down");
+}
+
+/// Refresh `last_ping` of the clients whose PINGs are waiting on the shared socket.
+fn read_pings(
+    ping_socket: &UdpSocket,
+    ping_buffer: &mut [u8],
+    clients: &HashMap<usize, ClientChannels>,
+) {
+    loop {
+        match ping_socket.recv_from(ping_buffer) {
             Ok((size, from_addr)) => {
                 if &ping_buffer[..size] == PING_PAYLOAD {
                     // Find client by UDP address and update last_ping
                     for client in clients.values() {
-                        if client.udp_addr == from_addr {
+                        if client.client_addr == from_addr {
                             let now = Instant::now();
                             if let Ok(mut guard) = client.last_ping.lock() {
                                 *guard = now;
@@ -116,17 +206,16 @@ fn dispatcher_loop(
             }
             Err(err)
                 if err.kind() == std::io::ErrorKind::WouldBlock
-                    || err.kind() == std::io::ErrorKind::TimedOut => {}
+                    || err.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                return;
+            }
             Err(err) => {
                 warn!("PING socket recv error: {}", err);
+                return;
             }
         }
-
-        purge_expired_clients(&mut clients);
     }
-
-    shutdown_clients(clients);
-    info!("UDP dispatcher shutting down");
 }
 
 fn register_client(
@@ -135,10 +224,17 @@ fn register_client(
     request: StreamRequest,
     keepalive_timeout: Duration,
     server_udp_addr: std::net::SocketAddr,
-) -> Result<(), QuoteError> {
+    batching: Batching,
+) -> Result<usize, QuoteError> {
     let tickers = request.tickers.iter().cloned().collect::<HashSet<_>>();
+    // Only unicast UDP clients PING; TCP clients live as long as their connection and
+    // multicast groups as long as the server.
+    let timeout = match request.delivery {
+        Delivery::Udp(_) => Some(keepalive_timeout),
+        Delivery::Tcp(_) | Delivery::Multicast { .. } => None,
+    };
 
-    let (quote_tx, quote_rx) = channel::unbounded::<StockQuote>();
+    let (message_tx, message_rx) = channel::unbounded::<StreamMessage>();
     let client_id = *next_id;
 
     let request_for_thread = request.clone();
@@ -150,10 +246,11 @@ fn register_client(
         .spawn(move || {
             client_loop(
                 request_for_thread,
-                quote_rx,
-                keepalive_timeout,
+                message_rx,
+                timeout,
                 last_ping_for_thread,
                 server_udp_addr,
+                batching,
             )
         })
         .map_err(|err| {
@@ -169,30 +266,101 @@ fn register_client(
         client_id,
         ClientChannels {
             tickers,
-            sender: quote_tx,
+            sender: message_tx,
             handle,
             last_ping,
-            timeout: keepalive_timeout,
-            udp_addr: request.udp_addr,
+            timeout,
+            client_addr: request.delivery.client_addr(),
         },
     );
 
     *next_id += 1;
 
     info!(
-        "Registered UDP client {} for [{}] at {}",
+        "Registered client {} for [{}] at {}",
         client_id,
         request.tickers.join(","),
-        request.udp_addr
+        request.delivery
     );
 
-    Ok(())
+    Ok(client_id)
+}
+
+/// Send the last known quotes to a client so it does not wait for the next generator cycle.
+fn prime_client(
+    clients: &HashMap<usize, ClientChannels>,
+    client_id: usize,
+    latest: Vec<StockQuote>,
+) {
+    let Some(client) = clients.get(&client_id) else {
+        return;
+    };
+    for quote in latest {
+        if client.sender.send(StreamMessage::Quote(quote)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Add or remove tickers for the clients at `client_addr`, returning the updated client ids.
+fn update_client_tickers(
+    clients: &mut HashMap<usize, ClientChannels>,
+    client_addr: std::net::SocketAddr,
+    tickers: &[String],
+    subscribe: bool,
+) -> Vec<usize> {
+    let mut matched = Vec::new();
+    for (client_id, client) in clients.iter_mut() {
+        if client.client_addr != client_addr {
+            continue;
+        }
+        matched.push(*client_id);
+        for ticker in tickers {
+            if subscribe {
+                client.tickers.insert(ticker.clone());
+            } else {
+                client.tickers.remove(ticker);
+            }
+        }
+        info!(
+            "Client {} at {} now streams {} tickers",
+            client_id,
+            client_addr,
+            client.tickers.len()
+        );
+    }
+
+    if matched.is_empty() {
+        warn!("No client registered at {client_addr} to update");
+    }
+    matched
 }
 
-fn deliver_quote(clients: &mut HashMap<usize, ClientChannels>, quote: &StockQuote) {
+fn remove_clients(clients: &mut HashMap<usize, ClientChannels>, client_addr: std::net::SocketAddr) {
+    let removed = clients
+        .iter()
+        .filter(|(_, client)| client.client_addr == client_addr)
+        .map(|(client_id, _)| *client_id)
+        .collect::<Vec<_>>();
+
+    for client_id in removed {
+        if let Some(client) = clients.remove(&client_id) {
+            info!("Removing client {client_id} at {client_addr}");
+            drop(client.sender);
+            if let Err(err) = client.handle.join() {
+                warn!("Client thread {client_id} panicked during removal: {err:?}");
+            }
+        }
+    }
+}
+
+/// Send `message` to the clients subscribed to its key; keyless messages reach every client.
+fn deliver_message(clients: &mut HashMap<usize, ClientChannels>, message: &StreamMessage) {
+    let key = message.subscription_key();
     let mut stale_clients = Vec::new();
     for (client_id, client) in clients.iter() {
-        if client.tickers.contains(&quote.ticker) && client.sender.send(quote.clone()).is_err() {
+        let subscribed = key.as_ref().is_none_or(|key| client.tickers.contains(key));
+        if subscribed && client.sender.send(message.clone()).is_err() {
             stale_clients.push(*client_id);
         }
     }
@@ -209,59 +377,172 @@ fn deliver_quote(clients: &mut HashMap<usize, ClientChannels>, quote: &StockQuot
 
 fn client_loop(
     request: StreamRequest,
-    quote_rx: Receiver<StockQuote>,
-    keepalive_timeout: Duration,
+    message_rx: Receiver<StreamMessage>,
+    keepalive_timeout: Option<Duration>,
     last_ping: Arc<Mutex<Instant>>,
     _server_udp_addr: std::net::SocketAddr,
+    batching: Batching,
 ) {
-    // Bind to ephemeral port for sending quotes
-    let socket = match UdpSocket::bind("0.0.0.0:0") {
+    let (udp_addr, interface, multicast_ttl) = match &request.delivery {
+        Delivery::Udp(udp_addr) => (*udp_addr, Ipv4Addr::UNSPECIFIED, None),
+        Delivery::Multicast {
+            group,
+            interface,
+            ttl,
+        } => (std::net::SocketAddr::V4(*group), *interface, Some(*ttl)),
+        Delivery::Tcp(connection) => return connection_loop(connection, &request, message_rx),
+    };
+
+    // Bind to ephemeral port for sending quotes; the address picks the multicast interface.
+    let socket = match UdpSocket::bind((interface, 0)) {
         Ok(socket) => socket,
         Err(err) => {
-            warn!(
-                "Failed to bind UDP socket for {}: {}",
-                request.udp_addr, err
-            );
+            warn!("Failed to bind UDP socket for {}: {}", udp_addr, err);
             return;
         }
     };
 
-    if let Err(err) = socket.connect(request.udp_addr) {
-        warn!(
-            "Failed to connect UDP socket to {}: {}",
-            request.udp_addr, err
-        );
+    if let Some(ttl) = multicast_ttl
+        && let Err(err) = socket.set_multicast_ttl_v4(ttl)
+    {
+        warn!("Failed to set multicast TTL for {}: {}", udp_addr, err);
+    }
+
+    if let Err(err) = socket.connect(udp_addr) {
+        warn!("Failed to connect UDP socket to {}: {}", udp_addr, err);
         return;
     }
 
+    // Sequence numbers let the client detect lost, duplicated and reordered datagrams.
+    let mut sequence: u64 = 0;
+    let mut batch = PacketBuilder::new(batching.max_payload);
+    let mut flush_deadline: Option<Instant> = None;
+    let poll_timeout = Duration::from_millis(CLIENT_RECV_POLL_TIMEOUT_MS);
+    let heartbeat_interval = Duration::from_secs(HEARTBEAT_INTERVAL_SECS);
+    let mut last_queued = Instant::now();
+
     loop {
         // Check timeout based on last_ping (updated by dispatcher)
-        let elapsed = last_ping
-            .lock()
-            .map(|instant| instant.elapsed())
-            .unwrap_or_else(|_| Duration::from_secs(keepalive_timeout.as_secs() + 1));
-
-        if elapsed > keepalive_timeout {
+        if let Some(keepalive_timeout) = expired_keepalive(keepalive_timeout, &last_ping) {
             warn!(
                 "Client {} exceeded keepalive timeout of {:?}",
-                request.udp_addr, keepalive_timeout
+                udp_addr, keepalive_timeout
+            );
+            close_on_timeout(
+                &socket,
+                &mut batch,
+                &mut sequence,
+                &request,
+                keepalive_timeout,
             );
             break;
         }
 
-        match quote_rx.recv_timeout(Duration::from_millis(CLIENT_RECV_POLL_TIMEOUT_MS)) {
-            Ok(quote) => match to_vec(&quote) {
+        let recv_timeout = flush_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .map_or(poll_timeout, |remaining| remaining.min(poll_timeout));
+
+        let message = match message_rx.recv_timeout(recv_timeout) {
+            Ok(message) => Some(message),
+            // Idle streams get heartbeats so clients can tell a quiet market from a dead server.
+            Err(RecvTimeoutError::Timeout) => (last_queued.elapsed() >= heartbeat_interval)
+                .then(|| StreamMessage::from(Heartbeat::now())),
+            // The dispatcher also removes clients that stopped PINGing; they get the notice too.
+            Err(RecvTimeoutError::Disconnected) => {
+                match expired_keepalive(keepalive_timeout, &last_ping) {
+                    Some(keepalive_timeout) => close_on_timeout(
+                        &socket,
+                        &mut batch,
+                        &mut sequence,
+                        &request,
+                        keepalive_timeout,
+                    ),
+                    None => flush_batch(&socket, &mut batch, &mut sequence, udp_addr),
+                }
+                break;
+            }
+        };
+
+        if let Some(message) = message {
+            match encode_message(&message, request.encoding) {
                 Ok(payload) => {
-                    if let Err(err) = socket.send(&payload) {
-                        warn!("Failed to send UDP packet to {}: {}", request.udp_addr, err);
+                    if !batch.fits(payload.len()) {
+                        flush_batch(&socket, &mut batch, &mut sequence, udp_addr);
+                        flush_deadline = None;
+                    }
+                    if !batch.push(&payload) {
+                        warn!(
+                            "Encoded {} ({} bytes) exceeds the {} byte datagram limit",
+                            message,
+                            payload.len(),
+                            batching.max_payload
+                        );
+                        continue;
                     }
+                    last_queued = Instant::now();
+                    flush_deadline.get_or_insert_with(|| Instant::now() + batching.flush_interval);
                 }
                 Err(err) => {
-                    warn!("Failed to serialize quote for {}: {}", quote.ticker, err);
+                    warn!("Failed to encode {}: {}", message, err);
                 }
-            },
-            Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+
+        if flush_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            flush_batch(&socket, &mut batch, &mut sequence, udp_addr);
+            flush_deadline = None;
+        }
+    }
+}
+
+/// Send the pending batch (if any) as one datagram and advance the sequence number.
+fn flush_batch(
+    socket: &UdpSocket,
+    batch: &mut PacketBuilder,
+    sequence: &mut u64,
+    udp_addr: std::net::SocketAddr,
+) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let quotes = batch.len();
+    if let Err(err) = socket.send(&batch.finish(*sequence)) {
+        warn!("Failed to send UDP packet to {}: {}", udp_addr, err);
+    } else {
+        debug!(
+            "Sent datagram #{} with {} quotes to {}",
+            sequence, quotes, udp_addr
+        );
+    }
+    *sequence += 1;
+}
+
+/// Write each message as a newline-delimited frame on the client's control connection
+/// until the dispatcher drops the client or the connection fails. Heartbeats fill
+/// idle periods, as on UDP streams.
+fn connection_loop(
+    connection: &ConnectionWriter,
+    request: &StreamRequest,
+    message_rx: Receiver<StreamMessage>,
+) {
+    let heartbeat_interval = Duration::from_secs(HEARTBEAT_INTERVAL_SECS);
+    loop {
+        let message = match message_rx.recv_timeout(heartbeat_interval) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => Heartbeat::now().into(),
             Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let frame = match encode_message(&message, request.encoding) {
+            Ok(frame) => frame,
+            Err(err) => {
+                warn!("Failed to encode {}: {}", message, err);
+                continue;
+            }
+        };
+        if let Err(err) = connection.write_line(&frame) {
+            warn!("Stopping TCP stream to {}: {}", connection.peer_addr(), err);
+            break;
         }
     }
 }
@@ -276,22 +557,64 @@ fn shutdown_clients(mut clients: HashMap<usize, ClientChannels>) {
     }
 }
 
+/// `keepalive_timeout` when the last PING is older than it.
+fn expired_keepalive(
+    keepalive_timeout: Option<Duration>,
+    last_ping: &Mutex<Instant>,
+) -> Option<Duration> {
+    let keepalive_timeout = keepalive_timeout?;
+    let elapsed = last_ping
+        .lock()
+        .map(|instant| instant.elapsed())
+        .unwrap_or_else(|_| Duration::from_secs(keepalive_timeout.as_secs() + 1));
+    (elapsed > keepalive_timeout).then_some(keepalive_timeout)
+}
+
+/// Flush the messages already batched, then tell a client whose PINGs stopped why its
+/// stream ends; it may still be listening if only the PINGs were lost.
+fn close_on_timeout(
+    socket: &UdpSocket,
+    batch: &mut PacketBuilder,
+    sequence: &mut u64,
+    request: &StreamRequest,
+    keepalive_timeout: Duration,
+) {
+    let udp_addr = request.delivery.client_addr();
+    let notice = ErrorNotice::new(format!(
+        "no PING received within {}s; stream closed",
+        keepalive_timeout.as_secs()
+    ));
+    flush_batch(socket, batch, sequence, udp_addr);
+    if let Ok(payload) = encode_message(&notice.into(), request.encoding)
+        && batch.push(&payload)
+    {
+        flush_batch(socket, batch, sequence, udp_addr);
+    }
+}
+
 fn purge_expired_clients(clients: &mut HashMap<usize, ClientChannels>) {
     let mut expired = Vec::new();
     for (client_id, client) in clients.iter() {
+        let Some(timeout) = client.timeout else {
+            continue;
+        };
         let elapsed = client
             .last_ping
             .lock()
             .map(|instant| instant.elapsed())
             .unwrap_or_else(|_| Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS * 2));
-        if elapsed > client.timeout {
+        if elapsed > timeout {
             expired.push(*client_id);
         }
     }
 
     for client_id in expired {
         if let Some(client) = clients.remove(&client_id) {
-            warn!("Client {} timed out after {:?}", client_id, client.timeout);
+            warn!(
+                "Client {} timed out after {:?}",
+                client_id,
+                client.timeout.unwrap_or_default()
+            );
             drop(client.sender);
             if let Err(err) = client.handle.join() {
                 warn!("Client thread {client_id} panicked during timeout cleanup: {err:?}");
@@ -306,16 +629,86 @@ mod tests {
     use std::net::UdpSocket;
     use std::time::Duration;
 
-    use quote_common::StockQuote;
+    use quote_common::{
+        OhlcvBar, QuoteEncoding, SessionEvent, SessionState, StockQuote, decode_message,
+        decode_packet, decode_quote,
+    };
+
+    fn decode_datagram(datagram: &[u8]) -> (u64, StockQuote) {
+        let (sequence, entries) = decode_packet(datagram).expect("decode packet");
+        assert_eq!(entries.len(), 1, "expected a single quote per datagram");
+        (sequence, decode_quote(entries[0]).expect("decode quote"))
+    }
+
+    fn unbatched() -> Batching {
+        Batching {
+            flush_interval: Duration::ZERO,
+            ..Batching::default()
+        }
+    }
+
+    /// Loopback address of a UDP port that was free a moment ago, for tests that PING
+    /// the dispatcher.
+    fn free_udp_addr() -> std::net::SocketAddr {
+        UdpSocket::bind("127.0.0.1:0")
+            .and_then(|socket| socket.local_addr())
+            .expect("reserve UDP port")
+    }
+
+    /// Next quote sent to `listener`, skipping heartbeats of an idle stream.
+    fn recv_quote(listener: &UdpSocket) -> StockQuote {
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let mut buffer = [0u8; 1024];
+        loop {
+            let (size, _) = listener.recv_from(&mut buffer).expect("receive quote");
+            let (_, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+            for entry in entries {
+                match decode_message(entry).expect("decode message") {
+                    StreamMessage::Heartbeat(_) => {}
+                    StreamMessage::Quote(quote) => return quote,
+                    other => panic!("expected a quote, got {other}"),
+                }
+            }
+        }
+    }
+
+    /// PING `server_addr` from `listener` for `duration`, asserting that the client is
+    /// not sent a timeout notice meanwhile. Heartbeats of the idle stream may arrive.
+    fn ping_quietly(listener: &UdpSocket, server_addr: std::net::SocketAddr, duration: Duration) {
+        listener
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .expect("set timeout");
+        let mut buffer = [0u8; 1024];
+        let started = Instant::now();
+        while started.elapsed() < duration {
+            listener
+                .send_to(PING_PAYLOAD, server_addr)
+                .expect("send PING");
+            if let Ok((size, _)) = listener.recv_from(&mut buffer) {
+                let (_, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+                for entry in entries {
+                    let message = decode_message(entry).expect("decode message");
+                    assert!(
+                        !matches!(message, StreamMessage::Error(_)),
+                        "client was dropped: {message}"
+                    );
+                }
+            }
+        }
+    }
 
     #[test]
     fn test_client_receives_filtered_quotes() {
-        let (quote_tx, quote_rx) = channel::unbounded::<StockQuote>();
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
         let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
         let (manager_tx, manager_handle) = start_udp_streamer(
             quote_rx,
             Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
             server_addr,
+            unbatched(),
+            QuoteCache::default(),
         )
         .expect("start manager");
 
@@ -326,8 +719,9 @@ mod tests {
         let addr = listener.local_addr().expect("local addr");
 
         let request = StreamRequest {
-            udp_addr: addr,
+            delivery: Delivery::Udp(addr),
             tickers: vec!["AAPL".to_string()],
+            encoding: QuoteEncoding::Json,
         };
 
         manager_tx
@@ -340,17 +734,17 @@ mod tests {
         let quote_in = StockQuote::new("AAPL", 150.0, 1_000);
         let quote_filtered = StockQuote::new("MSFT", 200.0, 2_000);
 
-        quote_tx.send(quote_filtered).expect("send filtered");
+        quote_tx.send(quote_filtered.into()).expect("send filtered");
         quote_tx
-            .send(quote_in.clone())
+            .send(quote_in.clone().into())
             .expect("send matching quote");
 
         let mut buffer = [0u8; 1024];
         let (size, _) = listener.recv_from(&mut buffer).expect("receive quote");
 
-        let received: StockQuote =
-            serde_json::from_slice(&buffer[..size]).expect("deserialize quote");
+        let (sequence, received) = decode_datagram(&buffer[..size]);
 
+        assert_eq!(sequence, 0);
         assert_eq!(received.ticker, quote_in.ticker);
 
         manager_tx
@@ -361,13 +755,134 @@ mod tests {
         manager_handle.join().expect("join manager");
     }
 
+    #[test]
+    fn test_bar_subscription_receives_bars_and_session_changes_without_quotes() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(listener.local_addr().expect("local addr")),
+                tickers: vec!["AAPL@1m".to_string()],
+                encoding: QuoteEncoding::Binary,
+            }))
+            .expect("add client");
+        std::thread::sleep(Duration::from_millis(50));
+
+        let quote = StockQuote::new("AAPL", 150.0, 1_000);
+        let bar = OhlcvBar::open(&quote.last_trade(), "1m".parse().unwrap());
+        quote_tx.send(quote.into()).expect("send quote");
+        quote_tx.send(bar.clone().into()).expect("send bar");
+
+        let mut buffer = [0u8; 1024];
+        let (size, _) = listener.recv_from(&mut buffer).expect("receive bar");
+        let (_, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            decode_message(entries[0]).expect("decode message"),
+            StreamMessage::Bar(bar)
+        );
+
+        // Session changes reach every client, whatever it subscribed to.
+        let event = SessionEvent {
+            state: SessionState::Closed,
+            timestamp: 1_000,
+        };
+        quote_tx.send(event.into()).expect("send session change");
+        let (size, _) = listener
+            .recv_from(&mut buffer)
+            .expect("receive session change");
+        let (_, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+        assert_eq!(
+            decode_message(entries[0]).expect("decode message"),
+            StreamMessage::Session(event)
+        );
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_client_sequence_numbers_increase() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let addr = listener.local_addr().expect("local addr");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+        std::thread::sleep(Duration::from_millis(50));
+
+        for _ in 0..3 {
+            quote_tx
+                .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+                .expect("send quote");
+        }
+
+        let mut buffer = [0u8; 1024];
+        let sequences = (0..3)
+            .map(|_| {
+                let (size, _) = listener.recv_from(&mut buffer).expect("receive quote");
+                decode_datagram(&buffer[..size]).0
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sequences, vec![0, 1, 2]);
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
     #[test]
     fn test_client_times_out_without_ping() {
-        let (quote_tx, quote_rx) = channel::unbounded::<StockQuote>();
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
         let timeout = Duration::from_millis(50);
         let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
-        let (manager_tx, manager_handle) =
-            start_udp_streamer(quote_rx, timeout, server_addr).expect("start manager");
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            timeout,
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
 
         let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
         listener
@@ -376,8 +891,9 @@ mod tests {
         let addr = listener.local_addr().expect("local addr");
 
         let request = StreamRequest {
-            udp_addr: addr,
+            delivery: Delivery::Udp(addr),
             tickers: vec!["AAPL".to_string()],
+            encoding: QuoteEncoding::Json,
         };
 
         manager_tx
@@ -387,9 +903,17 @@ mod tests {
         std::thread::sleep(Duration::from_millis(120));
 
         let quote = StockQuote::new("AAPL", 123.0, 1_000);
-        quote_tx.send(quote).expect("send quote");
+        quote_tx.send(quote.into()).expect("send quote");
 
         let mut buffer = [0u8; 1024];
+        let (len, _) = listener.recv_from(&mut buffer).expect("error notice");
+        let (_, entries) = decode_packet(&buffer[..len]).expect("decode packet");
+        let notice = decode_message(entries[0]).expect("decode notice");
+        assert!(
+            matches!(&notice, StreamMessage::Error(notice) if notice.message.contains("PING")),
+            "expected an error notice, got {notice}"
+        );
+
         let recv_result = listener.recv_from(&mut buffer);
         assert!(
             recv_result.is_err(),
@@ -403,4 +927,432 @@ mod tests {
 
         manager_handle.join().expect("join manager");
     }
+
+    #[test]
+    fn test_pinging_client_survives_quiet_source() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let timeout = Duration::from_millis(300);
+        let server_addr = free_udp_addr();
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            timeout,
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        let addr = listener.local_addr().expect("local addr");
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+
+        // No message arrives for three keep-alive timeouts, as while the market is closed.
+        ping_quietly(&listener, server_addr, 3 * timeout);
+
+        quote_tx
+            .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+            .expect("send quote");
+        assert_eq!(recv_quote(&listener).ticker, "AAPL");
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_pinging_client_survives_finished_source() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let timeout = Duration::from_millis(300);
+        let server_addr = free_udp_addr();
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            timeout,
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        let addr = listener.local_addr().expect("local addr");
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+        std::thread::sleep(Duration::from_millis(50));
+
+        // A finite replay sends its last tick and disconnects.
+        quote_tx
+            .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+            .expect("send quote");
+        assert_eq!(recv_quote(&listener).ticker, "AAPL");
+        drop(quote_tx);
+
+        ping_quietly(&listener, server_addr, 3 * timeout);
+
+        // Still registered: subscribing again primes the client with the cached tick.
+        manager_tx
+            .send(UdpCommand::Subscribe {
+                client_addr: addr,
+                tickers: vec!["AAPL".to_string()],
+            })
+            .expect("subscribe");
+        assert_eq!(recv_quote(&listener).price, 150.0);
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_subscribe_updates_existing_client() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let addr = listener.local_addr().expect("local addr");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+        manager_tx
+            .send(UdpCommand::Subscribe {
+                client_addr: addr,
+                tickers: vec!["MSFT".to_string()],
+            })
+            .expect("subscribe");
+        manager_tx
+            .send(UdpCommand::Unsubscribe {
+                client_addr: addr,
+                tickers: vec!["AAPL".to_string()],
+            })
+            .expect("unsubscribe");
+
+        std::thread::sleep(Duration::from_millis(50));
+
+        quote_tx
+            .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+            .expect("send unsubscribed quote");
+        quote_tx
+            .send(StockQuote::new("MSFT", 200.0, 2_000).into())
+            .expect("send subscribed quote");
+
+        let mut buffer = [0u8; 1024];
+        let (size, _) = listener.recv_from(&mut buffer).expect("receive quote");
+        let (_, received) = decode_datagram(&buffer[..size]);
+        assert_eq!(received.ticker, "MSFT");
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_client_receives_binary_quotes() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let addr = listener.local_addr().expect("local addr");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Binary,
+            }))
+            .expect("add client");
+
+        std::thread::sleep(Duration::from_millis(50));
+
+        let quote = StockQuote::new("AAPL", 150.0, 1_000);
+        quote_tx.send(quote.clone().into()).expect("send quote");
+
+        let mut buffer = [0u8; 1024];
+        let (size, _) = listener.recv_from(&mut buffer).expect("receive quote");
+        let (_, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+        assert_eq!(entries[0][0], quote_common::BINARY_QUOTE_VERSION);
+        assert_eq!(decode_quote(entries[0]).expect("decode quote"), quote);
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_client_batches_quotes_into_datagrams() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let batching = Batching {
+            max_payload: quote_common::BUFFER_SIZE,
+            flush_interval: Duration::from_millis(50),
+        };
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            batching,
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let addr = listener.local_addr().expect("local addr");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string(), "MSFT".to_string()],
+                encoding: QuoteEncoding::Binary,
+            }))
+            .expect("add client");
+        std::thread::sleep(Duration::from_millis(50));
+
+        for _ in 0..5 {
+            quote_tx
+                .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+                .expect("send quote");
+            quote_tx
+                .send(StockQuote::new("MSFT", 200.0, 2_000).into())
+                .expect("send quote");
+        }
+
+        let mut buffer = [0u8; quote_common::BUFFER_SIZE];
+        let (size, _) = listener.recv_from(&mut buffer).expect("receive batch");
+        let (sequence, entries) = decode_packet(&buffer[..size]).expect("decode packet");
+        assert_eq!(sequence, 0);
+        assert_eq!(entries.len(), 10);
+        assert!(size <= batching.max_payload);
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_tcp_client_receives_filtered_frames_until_removed() {
+        use std::io::{BufRead, BufReader};
+        use std::net::{TcpListener, TcpStream};
+
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        // A keep-alive timeout shorter than the test proves TCP clients are exempt from it.
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_millis(50),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let client =
+            TcpStream::connect(listener.local_addr().expect("local addr")).expect("connect client");
+        client
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let (server_side, _) = listener.accept().expect("accept");
+        let connection = ConnectionWriter::new(&server_side).expect("connection writer");
+        let client_addr = connection.peer_addr();
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Tcp(connection),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+        std::thread::sleep(Duration::from_millis(150));
+
+        quote_tx
+            .send(StockQuote::new("MSFT", 200.0, 2_000).into())
+            .expect("send filtered");
+        let quote = StockQuote::new("AAPL", 150.0, 1_000);
+        quote_tx
+            .send(quote.clone().into())
+            .expect("send matching quote");
+
+        let mut reader = BufReader::new(&client);
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("read frame");
+        assert_eq!(
+            decode_quote(line.trim_end().as_bytes()).expect("decode"),
+            quote
+        );
+
+        manager_tx
+            .send(UdpCommand::RemoveClient { client_addr })
+            .expect("remove client");
+        std::thread::sleep(Duration::from_millis(50));
+        quote_tx.send(quote.into()).expect("send after removal");
+        line.clear();
+        assert!(
+            reader.read_line(&mut line).is_err(),
+            "no frames after removal"
+        );
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_multicast_group_receives_quotes_on_loopback() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        // Multicast groups never PING, so a short keep-alive must not expire them.
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_millis(50),
+            server_addr,
+            unbatched(),
+            QuoteCache::default(),
+        )
+        .expect("start manager");
+
+        let receiver = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).expect("bind receiver");
+        receiver
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let group_ip = Ipv4Addr::new(239, 255, 42, 7);
+        receiver
+            .join_multicast_v4(&group_ip, &Ipv4Addr::LOCALHOST)
+            .expect("join group");
+        let port = receiver.local_addr().expect("local addr").port();
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Multicast {
+                    group: std::net::SocketAddrV4::new(group_ip, port),
+                    interface: Ipv4Addr::LOCALHOST,
+                    ttl: quote_common::DEFAULT_MULTICAST_TTL,
+                },
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add group");
+        std::thread::sleep(Duration::from_millis(150));
+
+        quote_tx
+            .send(StockQuote::new("MSFT", 200.0, 2_000).into())
+            .expect("send filtered");
+        quote_tx
+            .send(StockQuote::new("AAPL", 150.0, 1_000).into())
+            .expect("send matching quote");
+
+        let mut buffer = [0u8; 1024];
+        let (size, _) = receiver.recv_from(&mut buffer).expect("receive quote");
+        let (sequence, received) = decode_datagram(&buffer[..size]);
+        assert_eq!(sequence, 0);
+        assert_eq!(received.ticker, "AAPL");
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
+
+    #[test]
+    fn test_new_client_is_primed_with_cached_quotes() {
+        let (quote_tx, quote_rx) = channel::unbounded::<StreamMessage>();
+        let server_addr: std::net::SocketAddr = "127.0.0.1:0".parse().expect("parse addr");
+        let cache = QuoteCache::default();
+        let cached = StockQuote::new("AAPL", 150.0, 1_000);
+        cache.update(&cached);
+        cache.update(&StockQuote::new("MSFT", 200.0, 2_000));
+        let (manager_tx, manager_handle) = start_udp_streamer(
+            quote_rx,
+            Duration::from_secs(DEFAULT_KEEPALIVE_TIMEOUT_SECS),
+            server_addr,
+            unbatched(),
+            cache,
+        )
+        .expect("start manager");
+
+        let listener = UdpSocket::bind("127.0.0.1:0").expect("bind listener");
+        listener
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .expect("set timeout");
+        let addr = listener.local_addr().expect("local addr");
+
+        manager_tx
+            .send(UdpCommand::AddClient(StreamRequest {
+                delivery: Delivery::Udp(addr),
+                tickers: vec!["AAPL".to_string()],
+                encoding: QuoteEncoding::Json,
+            }))
+            .expect("add client");
+
+        // No quote is generated: the datagram comes from the cache.
+        let mut buffer = [0u8; 1024];
+        let (size, _) = listener
+            .recv_from(&mut buffer)
+            .expect("receive cached quote");
+        let (_, received) = decode_datagram(&buffer[..size]);
+        assert_eq!(received, cached);
+
+        manager_tx
+            .send(UdpCommand::Shutdown)
+            .expect("shutdown manager");
+        drop(quote_tx);
+
+        manager_handle.join().expect("join manager");
+    }
 }
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate};
+
+use quote_common::StockQuote;
+
+use crate::calendar::MarketCalendar;
+
+/// Milliseconds over which a ticker quoted around the clock trades its daily volume.
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1_000;
+/// Mean absolute value of a standard normal draw, `sqrt(2 / pi)`.
+const MEAN_ABS_SHOCK: f64 = 0.797_884_560_802_865_4;
+/// Price moves beyond this many standard deviations add no further volume.
+const MAX_SPIKE_SIGMAS: f64 = 10.0;
+
+/// Milliseconds a ticker trades its average daily volume over: the regular session
+/// under a calendar, otherwise the whole day.
+pub fn trading_day_ms(calendar: Option<&MarketCalendar>) -> i64 {
+    calendar.map_or(MS_PER_DAY, MarketCalendar::regular_session_ms)
+}
+
+/// Trading day `timestamp` counts towards: the exchange-local date under a calendar,
+/// otherwise the UTC date.
+pub fn trading_date(timestamp: i64, calendar: Option<&MarketCalendar>) -> NaiveDate {
+    calendar
+        .and_then(|calendar| calendar.local_date(timestamp))
+        .or_else(|| DateTime::from_timestamp_millis(timestamp).map(|utc| utc.date_naive()))
+        .unwrap_or_default()
+}
+
+/// Relative volume rate `progress` of the way through the regular session. The rate
+/// follows a U shape `open_close_ratio` times higher at the open and close than at
+/// midday and averages one over the session.
+pub fn intraday_profile(progress: f64, open_close_ratio: f64) -> f64 {
+    let edge = (2.0 * progress - 1.0).powi(2);
+    (1.0 + (open_close_ratio - 1.0) * edge) / (1.0 + (open_close_ratio - 1.0) / 3.0)
+}
+
+/// Volume multiplier of a trade whose price moved `sigmas` standard deviations. It
+/// grows linearly with the move and averages one over normally distributed moves.
+pub fn spike_multiplier(sigmas: f64, sensitivity: f64) -> f64 {
+    (1.0 + sensitivity * sigmas.min(MAX_SPIKE_SIGMAS)) / (1.0 + sensitivity * MEAN_ABS_SHOCK)
+}
+
+/// Volume and trade count of each ticker so far in its current trading day.
+#[derive(Debug, Default)]
+pub struct DailyTotals {
+    days: HashMap<String, DayTotals>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DayTotals {
+    date: NaiveDate,
+    volume: u64,
+    trades: u64,
+}
+
+impl DailyTotals {
+    /// Count the trade behind `quote` on `date` and stamp the quote with its day's
+    /// totals. A new date starts the ticker's totals from zero.
+    pub fn record(&mut self, quote: &mut StockQuote, date: NaiveDate) {
+        let totals = self.days.entry(quote.ticker.clone()).or_insert(DayTotals {
+            date,
+            volume: 0,
+            trades: 0,
+        });
+        if totals.date != date {
+            *totals = DayTotals {
+                date,
+                volume: 0,
+                trades: 0,
+            };
+        }
+        totals.volume += u64::from(quote.volume);
+        totals.trades += 1;
+        quote.daily_volume = totals.volume;
+        quote.trade_count = totals.trades;
+    }
+
+    /// Stamp `quote`, which carries no trade, with its ticker's totals on `date` so far.
+    pub fn stamp(&self, quote: &mut StockQuote, date: NaiveDate) {
+        let (volume, trades) = self
+            .days
+            .get(&quote.ticker)
+            .filter(|totals| totals.date == date)
+            .map_or((0, 0), |totals| (totals.volume, totals.trades));
+        quote.daily_volume = volume;
+        quote.trade_count = trades;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intraday_profile_is_u_shaped_with_unit_mean() {
+        let ratio = 3.0;
+        let midday = intraday_profile(0.5, ratio);
+        assert!((intraday_profile(0.0, ratio) / midday - ratio).abs() < 1e-9);
+        assert!((intraday_profile(1.0, ratio) / midday - ratio).abs() < 1e-9);
+        assert_eq!(intraday_profile(0.3, 1.0), 1.0);
+
+        let steps = 10_000;
+        let mean = (0..steps)
+            .map(|step| intraday_profile((step as f64 + 0.5) / steps as f64, ratio))
+            .sum::<f64>()
+            / steps as f64;
+        assert!((mean - 1.0).abs() < 1e-6, "mean profile {mean}");
+    }
+
+    #[test]
+    fn test_spike_multiplier_grows_with_move_and_is_capped() {
+        assert!(spike_multiplier(0.0, 1.0) < 1.0);
+        assert!(spike_multiplier(3.0, 1.0) > 2.0);
+        assert_eq!(spike_multiplier(50.0, 1.0), spike_multiplier(10.0, 1.0));
+        assert_eq!(spike_multiplier(4.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_daily_totals_accumulate_and_reset_each_day() {
+        let monday = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let mut totals = DailyTotals::default();
+        let mut record = |ticker: &str, volume: u32, date: NaiveDate| {
+            let mut quote = StockQuote::new(ticker, 100.0, volume);
+            totals.record(&mut quote, date);
+            (quote.daily_volume, quote.trade_count)
+        };
+
+        assert_eq!(record("AAPL", 300, monday), (300, 1));
+        assert_eq!(record("MSFT", 50, monday), (50, 1));
+        assert_eq!(record("AAPL", 200, monday), (500, 2));
+        assert_eq!(record("AAPL", 100, tuesday), (100, 1));
+    }
+}